@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serenity::model::id::GuildId;
+
+use crate::game_night::{GameNightSchedule, ScheduleError};
+
+// Next occurrence of each guild's schedule (none for the default one), so a
+// channel spamming !nextgame doesn't redo the timezone math on every message.
+// Every schedule change bumps `version`, which retires all the entries.
+#[derive(Default)]
+pub struct NextOccurrenceCache {
+    version: u64,
+    entries: HashMap<Option<GuildId>, CachedOccurrence>,
+}
+
+struct CachedOccurrence {
+    version: u64,
+    index: usize,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl NextOccurrenceCache {
+    // Forget everything cached, for when a schedule changed
+    pub fn invalidate(&mut self) {
+        self.version += 1;
+    }
+
+    // Index of the schedule's soonest night along with its start, the same as
+    // GameNightSchedule::next_occurrence
+    pub fn next_occurrence(
+        &mut self,
+        guild_id: Option<GuildId>,
+        schedule: &GameNightSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<(usize, DateTime<Utc>), ScheduleError> {
+        self.get_or_compute(guild_id, now, || {
+            let (index, start) = schedule.next_occurrence_at(now)?;
+            let end = schedule.nights[index].session_end(start)?;
+            Ok((index, start, end))
+        })
+    }
+
+    // A live session is still the next game night, so an entry stays good
+    // until its session is over rather than until it starts. Errors aren't
+    // cached, the next lookup tries again.
+    fn get_or_compute(
+        &mut self,
+        guild_id: Option<GuildId>,
+        now: DateTime<Utc>,
+        compute: impl FnOnce() -> Result<(usize, DateTime<Utc>, DateTime<Utc>), ScheduleError>,
+    ) -> Result<(usize, DateTime<Utc>), ScheduleError> {
+        if let Some(cached) = self.entries.get(&guild_id)
+            && cached.version == self.version
+            && now < cached.end
+        {
+            return Ok((cached.index, cached.start));
+        }
+
+        let (index, start, end) = compute()?;
+        self.entries.insert(guild_id, CachedOccurrence { version: self.version, index, start, end });
+        Ok((index, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session() -> Result<(usize, DateTime<Utc>, DateTime<Utc>), ScheduleError> {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        Ok((0, start, start + chrono::Duration::hours(4)))
+    }
+
+    #[test]
+    fn test_cache_hit_skips_recomputation() {
+        let mut cache = NextOccurrenceCache::default();
+        let (_, start, end) = session().unwrap();
+        let mut computed = 0;
+
+        for minutes in [300, 60, 1, -30] {
+            let now = start - chrono::Duration::minutes(minutes);
+            let next = cache.get_or_compute(None, now, || {
+                computed += 1;
+                session()
+            });
+            assert_eq!(next, Ok((0, start)));
+        }
+        assert_eq!(computed, 1);
+
+        // Recomputed once the session is over
+        cache.get_or_compute(None, end, || {
+            computed += 1;
+            session()
+        })
+        .unwrap();
+        assert_eq!(computed, 2);
+    }
+
+    #[test]
+    fn test_invalidate_recomputes() {
+        let mut cache = NextOccurrenceCache::default();
+        let (_, start, _) = session().unwrap();
+        let now = start - chrono::Duration::hours(1);
+        let mut computed = 0;
+        let mut lookup = |cache: &mut NextOccurrenceCache| {
+            cache.get_or_compute(None, now, || {
+                computed += 1;
+                session()
+            })
+            .unwrap();
+        };
+
+        lookup(&mut cache);
+        lookup(&mut cache);
+        cache.invalidate();
+        lookup(&mut cache);
+        assert_eq!(computed, 2);
+    }
+
+    #[test]
+    fn test_guilds_cached_separately() {
+        let mut cache = NextOccurrenceCache::default();
+        let (_, start, _) = session().unwrap();
+        let now = start - chrono::Duration::hours(1);
+
+        cache.get_or_compute(None, now, session).unwrap();
+        let (_, other_start) = cache
+            .get_or_compute(Some(GuildId::new(1)), now, || {
+                Ok((1, start + chrono::Duration::days(1), start + chrono::Duration::days(1)))
+            })
+            .unwrap();
+        assert_eq!(other_start, start + chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_next_occurrence_as_of_now() {
+        let mut cache = NextOccurrenceCache::default();
+        let schedule = GameNightSchedule::default();
+        // The weekend before, with the default night on Fridays at 8 PM in New York
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let (_, start, _) = session().unwrap();
+        assert_eq!(cache.next_occurrence(None, &schedule, now), Ok((0, start)));
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+
+use crate::game_night::{GameNightConfig, GameNightSchedule, upcoming_game_nights};
+
+// Longest a content line may get before it has to be folded, in octets
+const MAX_LINE_OCTETS: usize = 75;
+
+// Render the next `count` sessions of every night in the schedule as an
+// iCalendar file for !calendar
+pub fn export_ics(schedule: &GameNightSchedule, count: usize) -> String {
+    export_ics_at(schedule, count, Utc::now())
+}
+
+fn export_ics_at(schedule: &GameNightSchedule, count: usize, now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//tannius-bot//Game Night//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    // Every session is its own event, the times upcoming_game_nights gives. A
+    // weekly RRULE would have to be counted in the night's own timezone to
+    // survive DST, and would still need an EXDATE for every skipped date and
+    // holiday off.
+    for (index, night) in schedule.nights.iter().enumerate() {
+        for start in upcoming_game_nights(night, now).take(count) {
+            lines.extend(event_lines(index, night, start, now));
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+fn event_lines(
+    index: usize,
+    config: &GameNightConfig,
+    start: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    // Left out like upcoming_game_nights leaves out a session that can't be placed
+    let Ok(end) = config.session_end(start) else {
+        return Vec::new();
+    };
+    vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:gamenight-{}-{}@tannius-bot", index, format_utc(start)),
+        format!("DTSTAMP:{}", format_utc(now)),
+        format!("DTSTART:{}", format_utc(start)),
+        format!("DTEND:{}", format_utc(end)),
+        format!("SUMMARY:{}", escape_text("Game Night")),
+        "END:VEVENT".to_string(),
+    ]
+}
+
+// UTC date-time in the iCalendar basic format, e.g. 20240608T000000Z
+fn format_utc(datetime: DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Escape the characters TEXT values treat specially
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Split a content line into pieces of at most 75 octets, each continuation
+// starting with a space. Never splits inside a UTF-8 character.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_octets = 0;
+    for character in line.chars() {
+        if line_octets + character.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            line_octets = 1;
+        }
+        folded.push(character);
+        line_octets += character.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_export_weekly_night() {
+        let schedule = GameNightSchedule::default();
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+        let ics = export_ics_at(&schedule, 4, now);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        // Friday 8 PM Eastern is Saturday midnight UTC
+        assert!(ics.contains("\r\nDTSTART:20240608T000000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20240608T040000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Game Night\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20240629T000000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20240603T120000Z\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 4);
+        assert!(!ics.contains("RRULE"));
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn test_export_keeps_local_time_across_dst() {
+        let schedule = GameNightSchedule::default();
+        let now = Utc.with_ymd_and_hms(2024, 10, 28, 12, 0, 0).unwrap();
+        let ics = export_ics_at(&schedule, 2, now);
+
+        // 8 PM Eastern on both sides of the November change
+        assert!(ics.contains("DTSTART:20241102T000000Z"));
+        assert!(ics.contains("DTSTART:20241109T010000Z"));
+    }
+
+    #[test]
+    fn test_export_leaves_out_skipped_dates() {
+        let schedule = GameNightSchedule {
+            nights: vec![GameNightConfig {
+                skipped_dates: [chrono::NaiveDate::from_ymd_opt(2024, 6, 14).unwrap()].into(),
+                ..GameNightConfig::default()
+            }],
+        };
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+        let ics = export_ics_at(&schedule, 2, now);
+
+        assert!(ics.contains("DTSTART:20240608T000000Z"));
+        assert!(!ics.contains("DTSTART:20240615T000000Z"));
+        assert!(ics.contains("DTSTART:20240622T000000Z"));
+    }
+
+    #[test]
+    fn test_export_monthly_night() {
+        let schedule = GameNightSchedule {
+            nights: vec![GameNightConfig {
+                recurrence: crate::game_night::Recurrence::MonthlyNth { week: 1, weekday: chrono::Weekday::Fri },
+                ..GameNightConfig::default()
+            }],
+        };
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+        let ics = export_ics_at(&schedule, 2, now);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(!ics.contains("RRULE"));
+        assert!(ics.contains("DTSTART:20240608T000000Z"));
+        assert!(ics.contains("DTSTART:20240706T000000Z"));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        assert_eq!(fold_line("SUMMARY:Game Night"), "SUMMARY:Game Night");
+
+        let long = format!("DESCRIPTION:{}", "🎮".repeat(30));
+        let folded = fold_line(&long);
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("Games; snacks, fun\nand\\more"), "Games\\; snacks\\, fun\\nand\\\\more");
+    }
+}
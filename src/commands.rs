@@ -0,0 +1,260 @@
+// Prefix used by guilds that haven't picked their own with !setprefix
+#[cfg(feature = "prefix-commands")]
+pub const DEFAULT_PREFIX: &str = "!";
+
+// Longest prefix a guild may pick, in characters
+#[cfg(feature = "prefix-commands")]
+const MAX_PREFIX_CHARS: usize = 5;
+
+// The rest of a message after the guild's command prefix, or none when it
+// isn't a command at all. Prefixes match regardless of case.
+#[cfg(feature = "prefix-commands")]
+pub fn strip_prefix<'a>(content: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = content.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    Some(&content[prefix.len()..])
+}
+
+// Split what follows the prefix into the command word, lowercased, and its
+// arguments as typed. None when there's no command word at all.
+#[cfg(feature = "prefix-commands")]
+pub fn parse_command(text: &str) -> Option<(String, Vec<String>)> {
+    let mut words = text.split_whitespace();
+    let command = words.next()?.to_lowercase();
+    Some((command, words.map(str::to_string).collect()))
+}
+
+// Split a command's arguments on whitespace, keeping "quoted text" together
+// as one argument without its quotes. None when a quote is left open.
+pub fn split_args(text: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Set while inside quotes, and whether the current argument has started
+    // at all, so "" still counts as an (empty) argument
+    let mut quoted = false;
+    let mut started = false;
+    for c in text.chars() {
+        match c {
+            // Phones like to turn quotes into curly ones
+            '"' | '“' | '”' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    args.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if started {
+        args.push(current);
+    }
+    Some(args)
+}
+
+// A command's arguments, taken one at a time. Anything missing, left over or
+// badly quoted turns into a reply saying so along with the command's usage.
+pub struct CommandArgs<'a> {
+    usage: &'a str,
+    args: std::vec::IntoIter<String>,
+}
+
+impl<'a> CommandArgs<'a> {
+    // `args` as parse_command split them, split again respecting quotes
+    pub fn new(usage: &'a str, args: &[String]) -> Result<Self, String> {
+        let args = split_args(&args.join(" "))
+            .ok_or_else(|| format!("❌ A quote is missing its closing `\"`.\n{}", usage))?;
+        Ok(Self { usage, args: args.into_iter() })
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    pub fn next_required(&mut self, name: &str) -> Result<String, String> {
+        self.args.next().ok_or_else(|| format!("❌ Missing <{}>.\n{}", name, self.usage))
+    }
+
+    pub fn next_optional(&mut self) -> Option<String> {
+        self.args.next()
+    }
+
+    // Call once every argument has been taken, to catch extra ones
+    pub fn finish(mut self) -> Result<(), String> {
+        match self.args.next() {
+            Some(extra) => Err(format!("❌ Didn't expect '{}'.\n{}", extra, self.usage)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Check a prefix given to !setprefix, explaining what's wrong with it if anything
+#[cfg(feature = "prefix-commands")]
+pub fn validate_prefix(prefix: &str) -> Result<(), String> {
+    if prefix.is_empty() || prefix.chars().any(char::is_whitespace) {
+        return Err("❌ The prefix can't be empty or contain spaces.".to_string());
+    }
+    if prefix.chars().count() > MAX_PREFIX_CHARS {
+        return Err(format!("❌ The prefix can be at most {} characters long.", MAX_PREFIX_CHARS));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "prefix-commands")]
+    use proptest::prelude::*;
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_strip_prefix() {
+        assert_eq!(strip_prefix("!ping", "!"), Some("ping"));
+        assert_eq!(strip_prefix("tb?PING", "TB?"), Some("PING"));
+        assert_eq!(strip_prefix("ping", "!"), None);
+        assert_eq!(strip_prefix("!", "!!"), None);
+        // Doesn't trip over the prefix length landing inside a character
+        assert_eq!(strip_prefix("é!", "!!"), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("ping"), Some(("ping".to_string(), vec![])));
+        assert_eq!(parse_command("ping  \n"), Some(("ping".to_string(), vec![])));
+        assert_eq!(parse_command("PiNg"), Some(("ping".to_string(), vec![])));
+        assert_eq!(
+            parse_command("  SetGameNight fri 20:00  4 America/New_York "),
+            Some((
+                "setgamenight".to_string(),
+                vec!["fri".to_string(), "20:00".to_string(), "4".to_string(), "America/New_York".to_string()]
+            ))
+        );
+        assert_eq!(parse_command("   "), None);
+        assert_eq!(parse_command(""), None);
+    }
+
+    #[test]
+    fn test_split_args() {
+        let split = |text| split_args(text).unwrap();
+        assert_eq!(split("fri 20:00  4"), ["fri", "20:00", "4"]);
+        assert_eq!(split("\"America/New_York\" 20:00"), ["America/New_York", "20:00"]);
+        assert_eq!(split("\"Rocket League\" 5"), ["Rocket League", "5"]);
+        assert_eq!(split("“curly quotes” too"), ["curly quotes", "too"]);
+        assert_eq!(split("a\"b c\"d"), ["ab cd"]);
+        assert_eq!(split("\"\" x"), ["", "x"]);
+        assert!(split("").is_empty());
+        assert_eq!(split_args("\"never closed"), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_command_args() {
+        let usage = "Usage: `!until <YYYY-MM-DD> [HH:MM]`";
+        let words = |text: &str| text.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+
+        let mut args = CommandArgs::new(usage, &words("2024-12-24 20:00")).unwrap();
+        assert_eq!(args.next_required("date").unwrap(), "2024-12-24");
+        assert_eq!(args.next_optional().as_deref(), Some("20:00"));
+        assert!(args.finish().is_ok());
+
+        let mut args = CommandArgs::new(usage, &[]).unwrap();
+        assert_eq!(args.next_required("date").unwrap_err(), format!("❌ Missing <date>.\n{}", usage));
+        assert_eq!(args.next_optional(), None);
+
+        let mut args = CommandArgs::new(usage, &words("2024-12-24 20:00 extra")).unwrap();
+        args.next_required("date").unwrap();
+        args.next_optional();
+        assert_eq!(args.finish().unwrap_err(), format!("❌ Didn't expect 'extra'.\n{}", usage));
+
+        assert!(CommandArgs::new(usage, &words("\"2024-12-24")).is_err());
+    }
+
+    // Runs a message through everything that parses it on the way to a
+    // command, checking the pieces add up to no more than what was typed
+    #[cfg(feature = "prefix-commands")]
+    fn check_parsers(input: &str) {
+        for prefix in ["!", "🎮"] {
+            let _ = strip_prefix(input, prefix);
+        }
+        let Some((command, args)) = parse_command(input) else {
+            assert_eq!(input.split_whitespace().count(), 0, "{:?}", input);
+            return;
+        };
+        assert!(!command.is_empty(), "{:?}", input);
+        assert_eq!(args.len() + 1, input.split_whitespace().count(), "{:?}", input);
+
+        if let Some(split) = split_args(input) {
+            assert!(split.len() <= input.chars().count(), "{:?}", input);
+            let chars: usize = split.iter().map(|arg| arg.chars().count()).sum();
+            assert!(chars <= input.chars().count(), "{:?}", input);
+        }
+
+        if let Ok(mut args) = CommandArgs::new("Usage", &args) {
+            let mut taken = 0;
+            while args.next_optional().is_some() {
+                taken += 1;
+                assert!(taken <= input.chars().count(), "{:?}", input);
+            }
+            assert!(args.finish().is_ok());
+        }
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_parsers_on_odd_input() {
+        let long = "x".repeat(100_000);
+        let long_quoted = format!("\"{}", "é ".repeat(50_000));
+        let corpus = [
+            "",
+            " ",
+            "ping   ",
+            "setgamenight fri 20:00 4 \t\n",
+            "🎲 🎮\"🎯 \"",
+            "🎲\u{200d}🎮 \u{0301}",
+            "\0",
+            "ping \0 \"\0\" \0\0",
+            "\"",
+            "\"\"",
+            "“only opened",
+            "addgame \"Rocket League",
+            "İSTANBUL \u{3000}wide\u{3000}space",
+            long.as_str(),
+            long_quoted.as_str(),
+        ];
+        for input in corpus {
+            check_parsers(input);
+        }
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    proptest! {
+        // Characters that mean something to the parsers, some that are
+        // awkward in UTF-8, and anything else at all
+        #[test]
+        fn test_parsers_on_random_input(
+            input in prop_oneof!["[aZ! \t\n\"“”\u{0}é🎮\u{3000}\u{0301}]{0,64}", any::<String>()],
+        ) {
+            check_parsers(&input);
+        }
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_validate_prefix() {
+        assert!(validate_prefix("?").is_ok());
+        assert!(validate_prefix("tb!").is_ok());
+        assert!(validate_prefix("🎮").is_ok());
+        assert!(validate_prefix("").is_err());
+        assert!(validate_prefix("t b").is_err());
+        assert!(validate_prefix("toolong").is_err());
+    }
+}
@@ -0,0 +1,2078 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use serenity::model::Colour;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use tracing::warn;
+
+use crate::emoji::{self, EmojiSlot};
+use crate::game_night::{
+    DEFAULT_REMINDER_TEMPLATE, DEFAULT_SCHEDULER_TICK_SECONDS, DateStyle, GameNightConfig, GameNightSchedule, Holiday,
+    HolidayPolicy, LIVE_COLOUR, MAX_SCHEDULER_TICK_SECONDS, REMINDER_PLACEHOLDERS, Recurrence, SessionLength, TimeFormat,
+    UPCOMING_COLOUR,
+};
+use crate::i18n::Locale;
+use crate::template;
+
+// Longest a single game night may run. Sessions are assumed to end before
+// the same start time comes around the next day.
+const MAX_DURATION_HOURS: u32 = 24;
+
+// Everything that can go wrong while loading a game night config
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    InvalidWeekday(String),
+    InvalidStartTime(String),
+    // A start time typed into !setgamenight
+    InvalidTime(String),
+    #[cfg(feature = "prefix-commands")]
+    InvalidDuration(String),
+    DurationOutOfRange(u32),
+    InvalidEndTime(String),
+    EndTimeOutOfRange(NaiveTime),
+    SessionLengthConflict,
+    InvalidHoliday(String),
+    InvalidHolidayPolicy(String),
+    InvalidSpecialDate(String),
+    InvalidLocale(String),
+    InvalidTimezone(String),
+    // A timezone typed into a command, with the known ones it might have meant
+    UnknownTimezone { value: String, suggestions: Vec<&'static str> },
+    InvalidAnchor(String),
+    InvalidWeekOfMonth(u8),
+    InvalidTimeFormat(String),
+    InvalidDateStyle(String),
+    InvalidEmojiSlot(String),
+    // Not one emoji, or a custom emoji mention
+    InvalidEmoji(String),
+    InvalidColor(String),
+    // A late join grace longer than the session it belongs to
+    GraceOutOfRange(u32),
+    SchedulerTickOutOfRange(u32),
+    EmptySchedule,
+    InvalidGuildId(String),
+    // A GAMENIGHT_* environment variable that can't be used, with the format
+    // it should have
+    InvalidEnvVar { name: &'static str, value: String, expected: &'static str },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(why) => write!(f, "could not read config file: {}", why),
+            ConfigError::Parse(why) => write!(f, "invalid config file: {}", why),
+            ConfigError::InvalidWeekday(value) => {
+                write!(f, "invalid day_of_week '{}', expected a weekday like \"Friday\" or \"Fri\"", value)
+            }
+            ConfigError::InvalidStartTime(value) => {
+                write!(f, "invalid start_time '{}', expected HH:MM like \"20:00\"", value)
+            }
+            ConfigError::InvalidTime(value) => {
+                write!(f, "invalid start time '{}', expected a time like 20:00, 8pm or 8:30 PM", value)
+            }
+            #[cfg(feature = "prefix-commands")]
+            ConfigError::InvalidDuration(value) => {
+                write!(f, "invalid duration '{}', expected a number of hours like 4 or 2.5", value)
+            }
+            ConfigError::DurationOutOfRange(minutes) => write!(
+                f,
+                "the session length is {} minutes, but a game night has to last from 1 minute to {} hours",
+                minutes, MAX_DURATION_HOURS
+            ),
+            ConfigError::InvalidEndTime(value) => {
+                write!(f, "invalid end_time '{}', expected HH:MM like \"23:30\"", value)
+            }
+            ConfigError::EndTimeOutOfRange(value) => write!(
+                f,
+                "end_time {} has to come after start_time, or up to 24 hours later with next_day = true",
+                value.format("%H:%M")
+            ),
+            ConfigError::SessionLengthConflict => {
+                write!(f, "set exactly one of duration_hours, duration_minutes and end_time")
+            }
+            ConfigError::InvalidHoliday(value) => write!(
+                f,
+                "unknown holiday '{}', expected one of {}",
+                value,
+                Holiday::ALL.map(Holiday::key).join(", ")
+            ),
+            ConfigError::InvalidHolidayPolicy(value) => {
+                write!(f, "invalid holiday policy '{}', expected \"theme\", \"skip\" or \"ignore\"", value)
+            }
+            ConfigError::InvalidSpecialDate(value) => {
+                write!(f, "invalid special night date '{}', expected MM-DD like \"07-04\"", value)
+            }
+            ConfigError::InvalidLocale(value) => write!(
+                f,
+                "unknown locale '{}', expected one of {}",
+                value,
+                Locale::ALL.map(Locale::code).join(", ")
+            ),
+            ConfigError::InvalidTimezone(value) => {
+                write!(f, "invalid timezone '{}', expected an IANA name like \"America/New_York\"", value)
+            }
+            ConfigError::UnknownTimezone { value, suggestions } if !suggestions.is_empty() => {
+                write!(f, "unknown timezone '{}', did you mean: {}?", value, suggestions.join(", "))
+            }
+            ConfigError::UnknownTimezone { value, .. } => {
+                write!(f, "unknown timezone '{}', expected an IANA name like \"America/New_York\"", value)
+            }
+            ConfigError::InvalidAnchor(value) => {
+                write!(f, "invalid recurrence anchor '{}', expected a date like \"2024-06-07\"", value)
+            }
+            ConfigError::InvalidWeekOfMonth(value) => {
+                write!(f, "invalid recurrence week {}, expected 1 to 5", value)
+            }
+            ConfigError::InvalidTimeFormat(value) => {
+                write!(f, "invalid time_format '{}', expected \"12h\" or \"24h\"", value)
+            }
+            ConfigError::InvalidDateStyle(value) => {
+                write!(f, "invalid date_style '{}', expected \"month-first\" or \"day-first\"", value)
+            }
+            ConfigError::InvalidEmojiSlot(value) => write!(
+                f,
+                "unknown emoji slot '{}', expected one of {}",
+                value,
+                EmojiSlot::ALL.map(EmojiSlot::name).join(", ")
+            ),
+            ConfigError::InvalidEmoji(value) => {
+                write!(f, "invalid emoji '{}', expected a single emoji like 🎲 or a custom one like <:name:123>", value)
+            }
+            ConfigError::InvalidColor(value) => {
+                write!(f, "invalid colour '{}', expected a hex colour like \"#FF0000\" or \"#F00\"", value)
+            }
+            ConfigError::GraceOutOfRange(minutes) => write!(
+                f,
+                "late_join_grace_minutes is {}, but it can't be longer than the session",
+                minutes
+            ),
+            ConfigError::SchedulerTickOutOfRange(seconds) => write!(
+                f,
+                "scheduler_tick_seconds is {}, but it has to be from 1 to {} seconds",
+                seconds, MAX_SCHEDULER_TICK_SECONDS
+            ),
+            ConfigError::EmptySchedule => write!(f, "nights must list at least one game night"),
+            ConfigError::InvalidGuildId(value) => write!(f, "invalid guild id '{}'", value),
+            ConfigError::InvalidEnvVar { name, value, expected } => {
+                write!(f, "invalid {} '{}', expected {}", name, value, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(why: std::io::Error) -> Self {
+        ConfigError::Io(why)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(why: toml::de::Error) -> Self {
+        ConfigError::Parse(why)
+    }
+}
+
+// Raw layout of gamenight.toml, before any of the values are validated
+#[derive(Deserialize, Serialize)]
+struct ConfigFile {
+    day_of_week: String,
+    start_time: String,
+    // Exactly one of duration_hours, duration_minutes and end_time. next_day
+    // marks an end time after midnight. Whole hours are saved as duration_hours,
+    // which is all older configs have.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_hours: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end_time: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    next_day: bool,
+    timezone: String,
+    // Weekly when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recurrence: Option<RecurrenceFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reminder_channel: Option<u64>,
+    #[serde(default = "default_reminder_lead_minutes")]
+    reminder_lead_minutes: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notify_role_id: Option<u64>,
+    // Voice channel the group plays in, for !whoisplaying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    voice_channel_id: Option<u64>,
+    // "12h" or "24h", 12h when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_format: Option<String>,
+    // "month-first" or "day-first", month-first when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_style: Option<String>,
+    // Set with !override, as an RFC 3339 timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    override_date: Option<DateTime<Utc>>,
+    // Dates skipped with !skip, as YYYY-MM-DD
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    skipped_dates: BTreeSet<NaiveDate>,
+    // IANA names !nextgame also shows the start time in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    display_timezones: Vec<String>,
+    // Holiday name to "theme", "skip" or "ignore", e.g.
+    // `holidays = { christmas_eve = "skip" }`. Themed when left out.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    holidays: BTreeMap<String, String>,
+    // Announcements added with !addspecial, keyed by MM-DD, e.g.
+    // `specials = { "06-07" = "🎂 **Server Anniversary Game Night!**" }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    specials: BTreeMap<String, String>,
+    // Minutes special nights run instead of the usual length, keyed by
+    // MM-DD, e.g. `special_durations = { "12-31" = 360 }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    special_durations: BTreeMap<String, u32>,
+    // Language code replies are written in, "en" when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    locale: Option<String>,
+    // "Going" RSVPs needed for the live announcement, always announced when
+    // left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_players: Option<usize>,
+    // Text of the reminders, see game_night::REMINDER_PLACEHOLDERS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reminder_template: Option<String>,
+    // Turned off by !pause, so a restart doesn't start announcing again
+    #[serde(default = "default_announcements_enabled", skip_serializing_if = "is_true")]
+    announcements_enabled: bool,
+    // Minutes before the end to post the last call, 0 for none
+    #[serde(default = "default_last_call_minutes")]
+    last_call_minutes: u32,
+    // Emoji by slot name, e.g. `emoji = { live = "🚨" }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    emoji: BTreeMap<String, String>,
+    // Embed sidebar colours as hex, e.g. "#FF0000"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed_color_live: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed_color_upcoming: Option<String>,
+    // Minutes after the start !nextgame still shows a live session, the whole
+    // session when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    late_join_grace_minutes: Option<u32>,
+    // How often the scheduler wakes up, 60 seconds when left out
+    #[serde(default = "default_scheduler_tick_seconds", skip_serializing_if = "is_default_scheduler_tick")]
+    scheduler_tick_seconds: u32,
+}
+
+// Raw recurrence, e.g. `recurrence = { every = "month", week = 3 }`. Monthly
+// nights fall on the config's day_of_week.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "every", rename_all = "snake_case")]
+enum RecurrenceFile {
+    Week,
+    OtherWeek { anchor: String },
+    // By ISO week number
+    EvenWeek,
+    OddWeek,
+    Month { week: u8 },
+}
+
+fn default_announcements_enabled() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn default_reminder_lead_minutes() -> Vec<u32> {
+    GameNightConfig::default().reminder_lead_minutes
+}
+
+fn default_last_call_minutes() -> u32 {
+    GameNightConfig::default().last_call_minutes.unwrap_or_default()
+}
+
+fn default_scheduler_tick_seconds() -> u32 {
+    DEFAULT_SCHEDULER_TICK_SECONDS
+}
+
+fn is_default_scheduler_tick(seconds: &u32) -> bool {
+    *seconds == DEFAULT_SCHEDULER_TICK_SECONDS
+}
+
+// Layout for running several game nights a week, one [[nights]] table each
+#[derive(Deserialize, Serialize)]
+struct ScheduleFile {
+    nights: Vec<ConfigFile>,
+}
+
+// Layout of guilds.toml, holding each guild's own schedule keyed by guild id
+#[derive(Default, Deserialize, Serialize)]
+struct GuildsFile {
+    guilds: HashMap<String, ScheduleFile>,
+}
+
+impl TryFrom<ConfigFile> for GameNightConfig {
+    type Error = ConfigError;
+
+    fn try_from(file: ConfigFile) -> Result<Self, Self::Error> {
+        let day_of_week = parse_weekday(&file.day_of_week).ok_or(ConfigError::InvalidWeekday(file.day_of_week))?;
+        let recurrence = match file.recurrence {
+            None | Some(RecurrenceFile::Week) => Recurrence::Weekly,
+            Some(RecurrenceFile::OtherWeek { anchor }) => Recurrence::Biweekly {
+                anchor: NaiveDate::parse_from_str(anchor.trim(), "%Y-%m-%d")
+                    .map_err(|_| ConfigError::InvalidAnchor(anchor))?,
+            },
+            Some(RecurrenceFile::EvenWeek) => Recurrence::EvenWeeks,
+            Some(RecurrenceFile::OddWeek) => Recurrence::OddWeeks,
+            Some(RecurrenceFile::Month { week }) if (1..=5).contains(&week) => {
+                Recurrence::MonthlyNth { week, weekday: day_of_week }
+            }
+            Some(RecurrenceFile::Month { week }) => return Err(ConfigError::InvalidWeekOfMonth(week)),
+        };
+
+        let config = Self {
+            day_of_week,
+            recurrence,
+            start_time: NaiveTime::parse_from_str(file.start_time.trim(), "%H:%M")
+                .map_err(|_| ConfigError::InvalidStartTime(file.start_time))?,
+            length: match (file.duration_hours, file.duration_minutes, file.end_time) {
+                (Some(hours), None, None) => SessionLength::Minutes(hours.saturating_mul(60)),
+                (None, Some(minutes), None) => SessionLength::Minutes(minutes),
+                (None, None, Some(end_time)) => SessionLength::EndTime {
+                    end_time: NaiveTime::parse_from_str(end_time.trim(), "%H:%M")
+                        .map_err(|_| ConfigError::InvalidEndTime(end_time))?,
+                    next_day: file.next_day,
+                },
+                _ => return Err(ConfigError::SessionLengthConflict),
+            },
+            timezone: chrono_tz::Tz::from_str(file.timezone.trim())
+                .map_err(|_| ConfigError::InvalidTimezone(file.timezone))?,
+            reminder_channel: file.reminder_channel.filter(|id| *id != 0).map(ChannelId::new),
+            reminder_lead_minutes: file.reminder_lead_minutes,
+            notify_role_id: file.notify_role_id.filter(|id| *id != 0).map(RoleId::new),
+            voice_channel_id: file.voice_channel_id.filter(|id| *id != 0).map(ChannelId::new),
+            time_format: match file.time_format.as_deref().map(str::trim) {
+                None | Some("12h") => TimeFormat::Twelve,
+                Some("24h") => TimeFormat::TwentyFour,
+                Some(value) => return Err(ConfigError::InvalidTimeFormat(value.to_string())),
+            },
+            date_style: match file.date_style.as_deref().map(str::trim) {
+                None | Some("month-first") => DateStyle::MonthFirst,
+                Some("day-first") => DateStyle::DayFirst,
+                Some(value) => return Err(ConfigError::InvalidDateStyle(value.to_string())),
+            },
+            override_date: file.override_date,
+            skipped_dates: file.skipped_dates.into_iter().collect(),
+            display_timezones: file.display_timezones
+                .into_iter()
+                .map(|name| chrono_tz::Tz::from_str(name.trim()).map_err(|_| ConfigError::InvalidTimezone(name)))
+                .collect::<Result<_, _>>()?,
+            holiday_policies: file.holidays
+                .into_iter()
+                .map(|(holiday, policy)| {
+                    let holiday = Holiday::from_key(&holiday).ok_or(ConfigError::InvalidHoliday(holiday))?;
+                    let policy = parse_holiday_policy(&policy).ok_or(ConfigError::InvalidHolidayPolicy(policy))?;
+                    Ok((holiday, policy))
+                })
+                .collect::<Result<_, ConfigError>>()?,
+            custom_specials: file.specials
+                .into_iter()
+                .map(|(date, message)| Ok((parse_month_day(&date).ok_or(ConfigError::InvalidSpecialDate(date))?, message)))
+                .collect::<Result<_, ConfigError>>()?,
+            special_durations: file.special_durations
+                .into_iter()
+                .map(|(date, minutes)| Ok((parse_month_day(&date).ok_or(ConfigError::InvalidSpecialDate(date))?, minutes)))
+                .collect::<Result<_, ConfigError>>()?,
+            locale: match file.locale {
+                None => Locale::default(),
+                Some(code) => Locale::from_code(&code).ok_or(ConfigError::InvalidLocale(code))?,
+            },
+            min_players: file.min_players.filter(|min_players| *min_players != 0),
+            reminder_template: match file.reminder_template {
+                Some(reminder_template) => {
+                    let unknown = template::unknown_placeholders(&reminder_template, &REMINDER_PLACEHOLDERS);
+                    if !unknown.is_empty() {
+                        warn!("reminder_template has unknown placeholders, they'll show up as typed: {}", unknown.join(", "));
+                    }
+                    reminder_template
+                }
+                None => DEFAULT_REMINDER_TEMPLATE.to_string(),
+            },
+            announcements_enabled: file.announcements_enabled,
+            last_call_minutes: Some(file.last_call_minutes).filter(|minutes| *minutes != 0),
+            emoji: file.emoji
+                .into_iter()
+                .map(|(slot, value)| {
+                    let slot = EmojiSlot::from_name(&slot).ok_or(ConfigError::InvalidEmojiSlot(slot))?;
+                    if !emoji::is_single_emoji(&value) {
+                        return Err(ConfigError::InvalidEmoji(value));
+                    }
+                    Ok((slot, value))
+                })
+                .collect::<Result<_, ConfigError>>()?,
+            embed_color_live: match file.embed_color_live {
+                Some(value) => parse_hex_color(&value).ok_or(ConfigError::InvalidColor(value))?,
+                None => LIVE_COLOUR,
+            },
+            embed_color_upcoming: match file.embed_color_upcoming {
+                Some(value) => parse_hex_color(&value).ok_or(ConfigError::InvalidColor(value))?,
+                None => UPCOMING_COLOUR,
+            },
+            late_join_grace_minutes: file.late_join_grace_minutes,
+            scheduler_tick_seconds: file.scheduler_tick_seconds,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl From<&GameNightConfig> for ConfigFile {
+    fn from(config: &GameNightConfig) -> Self {
+        Self {
+            day_of_week: match config.recurrence {
+                Recurrence::MonthlyNth { weekday, .. } => weekday.to_string(),
+                _ => config.day_of_week.to_string(),
+            },
+            start_time: config.start_time.format("%H:%M").to_string(),
+            duration_hours: match config.length {
+                SessionLength::Minutes(minutes) if minutes % 60 == 0 => Some(minutes / 60),
+                _ => None,
+            },
+            duration_minutes: match config.length {
+                SessionLength::Minutes(minutes) if minutes % 60 != 0 => Some(minutes),
+                _ => None,
+            },
+            end_time: match config.length {
+                SessionLength::Minutes(_) => None,
+                SessionLength::EndTime { end_time, .. } => Some(end_time.format("%H:%M").to_string()),
+            },
+            next_day: matches!(config.length, SessionLength::EndTime { next_day: true, .. }),
+            timezone: config.timezone.name().to_string(),
+            recurrence: match config.recurrence {
+                Recurrence::Weekly => None,
+                Recurrence::Biweekly { anchor } => Some(RecurrenceFile::OtherWeek {
+                    anchor: anchor.format("%Y-%m-%d").to_string(),
+                }),
+                Recurrence::EvenWeeks => Some(RecurrenceFile::EvenWeek),
+                Recurrence::OddWeeks => Some(RecurrenceFile::OddWeek),
+                Recurrence::MonthlyNth { week, .. } => Some(RecurrenceFile::Month { week }),
+            },
+            reminder_channel: config.reminder_channel.map(|id| id.get()),
+            reminder_lead_minutes: config.reminder_lead_minutes.clone(),
+            notify_role_id: config.notify_role_id.map(|id| id.get()),
+            voice_channel_id: config.voice_channel_id.map(|id| id.get()),
+            time_format: match config.time_format {
+                TimeFormat::Twelve => None,
+                TimeFormat::TwentyFour => Some("24h".to_string()),
+            },
+            date_style: match config.date_style {
+                DateStyle::MonthFirst => None,
+                DateStyle::DayFirst => Some("day-first".to_string()),
+            },
+            override_date: config.override_date,
+            skipped_dates: config.skipped_dates.iter().copied().collect(),
+            display_timezones: config.display_timezones.iter().map(|zone| zone.name().to_string()).collect(),
+            holidays: config.holiday_policies
+                .iter()
+                .filter(|(_, policy)| **policy != HolidayPolicy::Theme)
+                .map(|(holiday, policy)| (holiday.key().to_string(), holiday_policy_key(*policy).to_string()))
+                .collect(),
+            specials: config.custom_specials
+                .iter()
+                .map(|((month, day), message)| (format!("{:02}-{:02}", month, day), message.clone()))
+                .collect(),
+            special_durations: config.special_durations
+                .iter()
+                .map(|((month, day), minutes)| (format!("{:02}-{:02}", month, day), *minutes))
+                .collect(),
+            locale: (config.locale != Locale::default()).then(|| config.locale.code().to_string()),
+            min_players: config.min_players,
+            reminder_template: (config.reminder_template != DEFAULT_REMINDER_TEMPLATE).then(|| config.reminder_template.clone()),
+            announcements_enabled: config.announcements_enabled,
+            last_call_minutes: config.last_call_minutes.unwrap_or_default(),
+            emoji: config.emoji.iter().map(|(slot, value)| (slot.name().to_string(), value.clone())).collect(),
+            embed_color_live: (config.embed_color_live != LIVE_COLOUR)
+                .then(|| format_hex_color(config.embed_color_live)),
+            embed_color_upcoming: (config.embed_color_upcoming != UPCOMING_COLOUR)
+                .then(|| format_hex_color(config.embed_color_upcoming)),
+            late_join_grace_minutes: config.late_join_grace_minutes,
+            scheduler_tick_seconds: config.scheduler_tick_seconds,
+        }
+    }
+}
+
+impl GameNightConfig {
+    // Checks that hold no matter where the night came from. Parsing already
+    // runs these, so every loaded or !setgamenight night passes them.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self.length {
+            SessionLength::Minutes(minutes) if minutes == 0 || minutes > MAX_DURATION_HOURS * 60 => {
+                Err(ConfigError::DurationOutOfRange(minutes))
+            }
+            SessionLength::EndTime { end_time, .. } => {
+                let duration = self.length.duration(self.start_time);
+                if duration <= chrono::Duration::zero() || duration > chrono::Duration::hours(MAX_DURATION_HOURS as i64) {
+                    return Err(ConfigError::EndTimeOutOfRange(end_time));
+                }
+                Ok(())
+            }
+            SessionLength::Minutes(_) => Ok(()),
+        }?;
+        if let Some(minutes) = self.special_durations
+            .values()
+            .find(|minutes| **minutes == 0 || **minutes > MAX_DURATION_HOURS * 60)
+        {
+            return Err(ConfigError::DurationOutOfRange(*minutes));
+        }
+        if let Some(minutes) = self.late_join_grace_minutes
+            && minutes as i64 > self.length.duration(self.start_time).num_minutes()
+        {
+            return Err(ConfigError::GraceOutOfRange(minutes));
+        }
+        if !(1..=MAX_SCHEDULER_TICK_SECONDS).contains(&self.scheduler_tick_seconds) {
+            return Err(ConfigError::SchedulerTickOutOfRange(self.scheduler_tick_seconds));
+        }
+        Ok(())
+    }
+}
+
+// Single-night loading, for a gamenight.toml without [[nights]]
+impl GameNightConfig {
+    // The night in the config file at `path`. Fields it leaves out come from
+    // the GAMENIGHT_* environment variables, or else the built-in defaults,
+    // which are all there is when there's no file.
+    pub fn from_toml_path(path: &Path) -> Result<GameNightConfig, ConfigError> {
+        Self::from_toml_path_with_env(path, |name| std::env::var(name).ok())
+    }
+
+    fn from_toml_path_with_env(
+        path: &Path,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<GameNightConfig, ConfigError> {
+        let base = toml::Table::try_from(env_night(var)?).expect("ConfigFile serializes to a TOML table");
+        let night = match std::fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents)?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+            Err(why) => return Err(why.into()),
+        };
+        Self::from_toml_str(&layer(&base, night).to_string())
+    }
+
+    // A night with nothing underneath it, so `contents` has to give every
+    // field that has no default of its own
+    pub fn from_toml_str(contents: &str) -> Result<GameNightConfig, ConfigError> {
+        let file: ConfigFile = toml::from_str(contents)?;
+        GameNightConfig::try_from(file)
+    }
+}
+
+impl TryFrom<ScheduleFile> for GameNightSchedule {
+    type Error = ConfigError;
+
+    fn try_from(file: ScheduleFile) -> Result<Self, Self::Error> {
+        if file.nights.is_empty() {
+            return Err(ConfigError::EmptySchedule);
+        }
+        let nights = file.nights
+            .into_iter()
+            .map(GameNightConfig::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GameNightSchedule { nights })
+    }
+}
+
+impl From<&GameNightSchedule> for ScheduleFile {
+    fn from(schedule: &GameNightSchedule) -> Self {
+        Self {
+            nights: schedule.nights.iter().map(ConfigFile::from).collect(),
+        }
+    }
+}
+
+// A schedule as gamenight.toml contents, for !exportconfig: a single night at
+// the top level, or a list of [[nights]] when there are several. Loading it
+// gives the same schedule back.
+#[cfg(feature = "prefix-commands")]
+pub fn export_schedule(schedule: &GameNightSchedule) -> String {
+    match schedule.nights.as_slice() {
+        [night] => night_to_toml(night),
+        _ => toml::to_string(&ScheduleFile::from(schedule)).expect("schedules serialize to TOML"),
+    }
+}
+
+// One night as its own config file, the way GameNightConfig::from_toml_str
+// reads it back
+#[cfg(any(feature = "prefix-commands", feature = "sqlite"))]
+pub fn night_to_toml(night: &GameNightConfig) -> String {
+    toml::to_string(&ConfigFile::from(night)).expect("nights serialize to TOML")
+}
+
+// Load every guild's own schedule, or none at all when the file doesn't exist
+pub fn load_guild_schedules(path: &Path) -> Result<HashMap<GuildId, GameNightSchedule>, ConfigError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(why) => return Err(why.into()),
+    };
+
+    let file: GuildsFile = toml::from_str(&contents)?;
+    file.guilds
+        .into_iter()
+        .map(|(guild_id, schedule)| {
+            let id = guild_id
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+                .ok_or(ConfigError::InvalidGuildId(guild_id))?;
+            Ok((GuildId::new(id), GameNightSchedule::try_from(schedule)?))
+        })
+        .collect()
+}
+
+pub fn save_guild_schedules(
+    path: &Path,
+    schedules: &HashMap<GuildId, GameNightSchedule>,
+) -> Result<(), ConfigError> {
+    let file = GuildsFile {
+        guilds: schedules
+            .iter()
+            .map(|(guild_id, schedule)| (guild_id.to_string(), ScheduleFile::from(schedule)))
+            .collect(),
+    };
+    let contents = toml::to_string(&file).expect("guild schedules serialize to TOML");
+
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+// Build a new night from `<day> <HH:MM> <hours> [timezone]` as given to
+// !setgamenight, where hours may have a fraction like 2.5. Everything else,
+// including the timezone when none is passed, carries over from the current
+// night.
+#[cfg(feature = "prefix-commands")]
+pub fn parse_set_game_night_args(
+    args: &[&str],
+    current: &GameNightConfig,
+) -> Result<GameNightConfig, ConfigError> {
+    let mut file = ConfigFile::from(current);
+    file.day_of_week = args[0].to_string();
+    let start_time = parse_time(args[1]).ok_or_else(|| ConfigError::InvalidTime(args[1].to_string()))?;
+    file.start_time = start_time.format("%H:%M").to_string();
+    file.duration_hours = None;
+    file.duration_minutes = Some(parse_hours(args[2]).ok_or_else(|| ConfigError::InvalidDuration(args[2].to_string()))?);
+    file.end_time = None;
+    file.next_day = false;
+    // Checked here rather than by try_from, so a typo comes back with the
+    // timezones it might have meant
+    if let Some(timezone) = args.get(3) {
+        let timezone = parse_timezone(timezone)
+            .map_err(|suggestions| ConfigError::UnknownTimezone { value: timezone.to_string(), suggestions })?;
+        file.timezone = timezone.name().to_string();
+    }
+
+    GameNightConfig::try_from(file)
+}
+
+// One of the settings /settings can change on its own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    Day,
+    Time,
+    Timezone,
+}
+
+// A copy of `current` with one setting changed to `value`, as given to
+// /settings. Everything else carries over, like with !setgamenight.
+pub fn change_setting(current: &GameNightConfig, setting: Setting, value: &str) -> Result<GameNightConfig, ConfigError> {
+    let mut file = ConfigFile::from(current);
+    match setting {
+        Setting::Day => file.day_of_week = value.to_string(),
+        Setting::Time => {
+            let start_time = parse_time(value).ok_or_else(|| ConfigError::InvalidTime(value.to_string()))?;
+            file.start_time = start_time.format("%H:%M").to_string();
+        }
+        Setting::Timezone => {
+            let timezone = parse_timezone(value)
+                .map_err(|suggestions| ConfigError::UnknownTimezone { value: value.to_string(), suggestions })?;
+            file.timezone = timezone.name().to_string();
+        }
+    }
+    GameNightConfig::try_from(file)
+}
+
+impl GameNightSchedule {
+    // Load the default schedule. Each layer wins over the one before it: the
+    // built-in defaults, the GAMENIGHT_* environment variables, then the
+    // config file at `path` when there is one. Changes made with commands
+    // are kept per guild and win over all of these.
+    pub fn load(path: &Path) -> Result<GameNightSchedule, ConfigError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(why) => return Err(why.into()),
+        };
+        // Most files hold a single night, which GameNightConfig reads itself
+        if !contents.parse::<toml::Table>()?.contains_key("nights") {
+            return Ok(GameNightSchedule { nights: vec![GameNightConfig::from_toml_path(path)?] });
+        }
+        Self::from_toml_str_over(&contents, env_night(|name| std::env::var(name).ok())?)
+    }
+
+    // Parse a config file, taking whatever a night leaves out from `base`.
+    // Accepts either a list of [[nights]] or a single night at the top level.
+    fn from_toml_str_over(contents: &str, base: ConfigFile) -> Result<GameNightSchedule, ConfigError> {
+        let base = toml::Table::try_from(base).expect("ConfigFile serializes to a TOML table");
+        let mut table: toml::Table = toml::from_str(contents)?;
+        let Some(nights) = table.remove("nights") else {
+            let file: ConfigFile = layer(&base, table).try_into()?;
+            return Ok(GameNightSchedule { nights: vec![GameNightConfig::try_from(file)?] });
+        };
+
+        let nights: Vec<toml::Table> = nights.try_into()?;
+        let nights = nights
+            .into_iter()
+            .map(|night| layer(&base, night).try_into())
+            .collect::<Result<Vec<ConfigFile>, _>>()?;
+        GameNightSchedule::try_from(ScheduleFile { nights })
+    }
+}
+
+// A number of hours like "4" or "2.5" in whole minutes, none when it isn't one
+fn parse_hours(value: &str) -> Option<u32> {
+    let hours: f64 = value.trim().parse().ok()?;
+    let minutes = (hours * 60.0).round();
+    (minutes.is_finite() && minutes >= 0.0 && minutes <= u32::MAX as f64).then_some(minutes as u32)
+}
+
+// Environment variables for the default night, each with the format it takes
+const ENV_DAY: (&str, &str) = ("GAMENIGHT_DAY", "a weekday like \"Friday\" or \"Fri\"");
+const ENV_TIME: (&str, &str) = ("GAMENIGHT_TIME", "a start time as HH:MM like \"20:00\"");
+const ENV_DURATION: (&str, &str) = ("GAMENIGHT_DURATION", "a number of hours up to 24, like 4 or 2.5");
+const ENV_TZ: (&str, &str) = ("GAMENIGHT_TZ", "an IANA timezone like \"America/New_York\"");
+const ENV_SCHEDULER_TICK: (&str, &str) = ("GAMENIGHT_SCHEDULER_TICK_SECONDS", "a number of seconds from 1 to 900");
+
+// The built-in default night with whatever the GAMENIGHT_* variables change.
+// `var` looks a variable up, none when it isn't set.
+fn env_night(var: impl Fn(&str) -> Option<String>) -> Result<ConfigFile, ConfigError> {
+    let mut night = ConfigFile::from(&GameNightConfig::default());
+    let invalid = |(name, expected): (&'static str, &'static str), value: String| ConfigError::InvalidEnvVar {
+        name,
+        value,
+        expected,
+    };
+
+    if let Some(day) = var(ENV_DAY.0) {
+        parse_weekday(&day).ok_or_else(|| invalid(ENV_DAY, day.clone()))?;
+        night.day_of_week = day;
+    }
+    if let Some(time) = var(ENV_TIME.0) {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid(ENV_TIME, time.clone()))?;
+        night.start_time = time;
+    }
+    if let Some(duration) = var(ENV_DURATION.0) {
+        let minutes = parse_hours(&duration)
+            .filter(|minutes| (1..=MAX_DURATION_HOURS * 60).contains(minutes))
+            .ok_or_else(|| invalid(ENV_DURATION, duration.clone()))?;
+        night.duration_hours = None;
+        night.duration_minutes = Some(minutes);
+        night.end_time = None;
+        night.next_day = false;
+    }
+    if let Some(timezone) = var(ENV_TZ.0) {
+        chrono_tz::Tz::from_str(timezone.trim()).map_err(|_| invalid(ENV_TZ, timezone.clone()))?;
+        night.timezone = timezone;
+    }
+    if let Some(tick) = var(ENV_SCHEDULER_TICK.0) {
+        night.scheduler_tick_seconds = tick
+            .trim()
+            .parse()
+            .ok()
+            .filter(|seconds| (1..=MAX_SCHEDULER_TICK_SECONDS).contains(seconds))
+            .ok_or_else(|| invalid(ENV_SCHEDULER_TICK, tick.clone()))?;
+    }
+    Ok(night)
+}
+
+// A night from the config file on top of `base`. A night giving its own
+// session length replaces the base's instead of clashing with it.
+fn layer(base: &toml::Table, night: toml::Table) -> toml::Table {
+    let mut layered = base.clone();
+    if ["duration_hours", "duration_minutes", "end_time"].iter().any(|key| night.contains_key(*key)) {
+        for key in ["duration_hours", "duration_minutes", "end_time", "next_day"] {
+            layered.remove(key);
+        }
+    }
+    layered.extend(night);
+    layered
+}
+
+// How many close matches an unknown timezone gets suggested
+const MAX_TIMEZONE_SUGGESTIONS: usize = 5;
+
+// Parse an IANA timezone name as given to !mytz. An unknown name comes back
+// with the known ones it most likely meant.
+pub fn parse_timezone(value: &str) -> Result<chrono_tz::Tz, Vec<&'static str>> {
+    chrono_tz::Tz::from_str(value.trim()).map_err(|_| timezone_suggestions(value))
+}
+
+// Known timezones close to `value`: ones containing it, or a few typos away
+// from it or from their city part, closest first
+fn timezone_suggestions(value: &str) -> Vec<&'static str> {
+    let wanted = value.trim().to_lowercase().replace(' ', "_");
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(usize, &'static str)> = chrono_tz::TZ_VARIANTS
+        .iter()
+        .filter_map(|tz| {
+            let name = tz.name();
+            let lower = name.to_lowercase();
+            let city = lower.rsplit('/').next().unwrap_or(&lower);
+            let distance = edit_distance(&wanted, &lower).min(edit_distance(&wanted, city));
+            if lower.contains(&wanted) {
+                Some((0, name))
+            } else if distance <= 2 {
+                Some((distance, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    matches.into_iter().take(MAX_TIMEZONE_SUGGESTIONS).map(|(_, name)| name).collect()
+}
+
+// Every known timezone whose name contains `query`, ignoring case, in
+// alphabetical order. Spaces match underscores, so "new york" finds
+// America/New_York.
+pub fn search_timezones(query: &str) -> Vec<&'static str> {
+    let wanted = query.trim().to_lowercase().replace(' ', "_");
+    let mut matches: Vec<&'static str> = chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&wanted))
+        .collect();
+    matches.sort_unstable();
+    matches
+}
+
+// Levenshtein distance between two strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// A month and day in any year written MM-DD, like "07-04". February 29 is
+// allowed, it comes around on leap years.
+pub fn parse_month_day(value: &str) -> Option<(u32, u32)> {
+    let (month, day) = value.trim().split_once('-')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+    Some((month, day))
+}
+
+// A time of day as an admin might type it: 24-hour like "20:00" or
+// "20:00:00", or 12-hour like "8pm" or "8:30 PM". A bare "8" could be
+// either, so it doesn't count.
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    let mut value: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    // chrono wants minutes, so "8pm" is read as "8:00pm"
+    if !value.contains(':') && (value.ends_with("am") || value.ends_with("pm")) {
+        value.insert_str(value.len() - 2, ":00");
+    }
+    ["%H:%M", "%H:%M:%S", "%I:%M%p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&value, format).ok())
+}
+
+// A colour written as hex, "#FF0000" or the short "#F00", the # optional
+pub fn parse_hex_color(value: &str) -> Option<Colour> {
+    let hex = value.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    u32::from_str_radix(&hex, 16).ok().map(Colour::new)
+}
+
+// A colour the way parse_hex_color reads it, e.g. "#E74C3C"
+pub fn format_hex_color(colour: Colour) -> String {
+    format!("#{:06X}", colour.0)
+}
+
+// "theme", "skip" or "ignore" in any case, as in config files and !holiday
+pub fn parse_holiday_policy(value: &str) -> Option<HolidayPolicy> {
+    match value.trim().to_lowercase().as_str() {
+        "theme" => Some(HolidayPolicy::Theme),
+        "skip" => Some(HolidayPolicy::Skip),
+        "ignore" => Some(HolidayPolicy::Ignore),
+        _ => None,
+    }
+}
+
+fn holiday_policy_key(policy: HolidayPolicy) -> &'static str {
+    match policy {
+        HolidayPolicy::Theme => "theme",
+        HolidayPolicy::Skip => "skip",
+        HolidayPolicy::Ignore => "ignore",
+    }
+}
+
+// Every weekday by its full name, for parse_weekday
+const WEEKDAY_NAMES: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+// Accepts full names and abbreviations of at least three letters ("thurs")
+// in any case, ignoring spaces and punctuation ("fri day"). Longer words a
+// typo or two away from exactly one day ("wensday") count as that day.
+pub fn parse_weekday(value: &str) -> Option<Weekday> {
+    let wanted: String = value.chars().filter(char::is_ascii_alphabetic).collect::<String>().to_lowercase();
+    if wanted.len() < 3 {
+        return None;
+    }
+    if let Some((_, weekday)) = WEEKDAY_NAMES.iter().find(|(name, _)| name.starts_with(&wanted)) {
+        return Some(*weekday);
+    }
+    // The one common abbreviation that isn't the start of its day's name
+    if wanted == "weds" {
+        return Some(Weekday::Wed);
+    }
+    if wanted.len() < 4 {
+        return None;
+    }
+
+    let max_typos = if wanted.len() >= 7 { 2 } else { 1 };
+    let mut close = WEEKDAY_NAMES
+        .iter()
+        .map(|(name, weekday)| (edit_distance(&wanted, name), *weekday))
+        .filter(|(distance, _)| *distance <= max_typos);
+    match (close.next(), close.next()) {
+        (Some((_, weekday)), None) => Some(weekday),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_from_toml_str() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "saturday"
+            start_time = "19:30"
+            duration_hours = 3
+            timezone = "Europe/Berlin"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.day_of_week, Weekday::Sat);
+        assert_eq!(config.start_time, NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+        assert_eq!(config.length, SessionLength::Minutes(3 * 60));
+        assert_eq!(config.timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(config.reminder_channel, None);
+        assert_eq!(config.reminder_lead_minutes, vec![60, 15]);
+    }
+
+    #[test]
+    fn test_reminder_settings() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            reminder_channel = 123456789012345678
+            reminder_lead_minutes = [30]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.reminder_channel, Some(ChannelId::new(123456789012345678)));
+        assert_eq!(config.reminder_lead_minutes, vec![30]);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_recurrence() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            recurrence = { every = "other_week", anchor = "2024-06-07" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.recurrence, Recurrence::Biweekly { anchor: NaiveDate::from_ymd_opt(2024, 6, 7).unwrap() });
+
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            recurrence = { every = "odd_week" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.recurrence, Recurrence::OddWeeks);
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().recurrence, Recurrence::OddWeeks);
+
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Thursday"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            recurrence = { every = "month", week = 3 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.recurrence, Recurrence::MonthlyNth { week: 3, weekday: Weekday::Thu });
+
+        // Survives being rewritten by !setgamenight
+        let config = parse_set_game_night_args(&["Sat", "18:00", "2"], &config).unwrap();
+        assert_eq!(config.recurrence, Recurrence::MonthlyNth { week: 3, weekday: Weekday::Sat });
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            recurrence = { every = "month", week = 6 }
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidWeekOfMonth(6))));
+    }
+
+    #[test]
+    fn test_time_and_date_formats() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "Europe/Berlin"
+            time_format = "24h"
+            date_style = "day-first"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.time_format, TimeFormat::TwentyFour);
+        assert_eq!(config.date_style, DateStyle::DayFirst);
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "Europe/Berlin"
+            time_format = "military"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidTimeFormat(_))));
+    }
+
+    #[test]
+    fn test_parse_weekday() {
+        assert_eq!(parse_weekday("Friday"), Some(Weekday::Fri));
+        assert_eq!(parse_weekday("FRI"), Some(Weekday::Fri));
+        assert_eq!(parse_weekday("tue"), Some(Weekday::Tue));
+        assert_eq!(parse_weekday("someday"), None);
+    }
+
+    #[test]
+    fn test_parse_weekday_variants() {
+        let cases = [
+            (Weekday::Mon, ["monday", "MON", " Mon ", "mondy", "mon day"]),
+            (Weekday::Tue, ["tuesday", "Tue", "tues", "tusday", "TUESDAY"]),
+            (Weekday::Wed, ["wednesday", "wed", "weds", "wensday", "Wednsday"]),
+            (Weekday::Thu, ["thursday", "thu", "thurs", "thirsday", "Thur"]),
+            (Weekday::Fri, ["friday", "fri", "FRIDAY", "fri day", "fryday"]),
+            (Weekday::Sat, ["saturday", "sat", "satur day", "saterday", "Sat."]),
+            (Weekday::Sun, ["sunday", "sun", "SUN", "sundai", "sundy"]),
+        ];
+        for (weekday, inputs) in cases {
+            for input in inputs {
+                assert_eq!(parse_weekday(input), Some(weekday), "{:?}", input);
+            }
+        }
+
+        for input in ["", "  ", "s", "t", "th", "xyz", "noday", "weekend", "tomorrow", "day"] {
+            assert_eq!(parse_weekday(input), None, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "8pm"
+            duration_hours = 4
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidStartTime(_))));
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "Mars/Olympus_Mons"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidTimezone(_))));
+    }
+
+    #[test]
+    fn test_schedule_with_multiple_nights() {
+        let schedule = schedule_from_toml(
+            r#"
+            [[nights]]
+            day_of_week = "Tue"
+            start_time = "19:00"
+            duration_hours = 2
+            timezone = "America/New_York"
+
+            [[nights]]
+            day_of_week = "Fri"
+            start_time = "21:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(schedule.nights.len(), 2);
+        assert_eq!(schedule.nights[0].day_of_week, Weekday::Tue);
+        assert_eq!(schedule.nights[1].day_of_week, Weekday::Fri);
+
+        let result = schedule_from_toml("nights = []");
+        assert!(matches!(result, Err(ConfigError::EmptySchedule)));
+    }
+
+    #[test]
+    fn test_single_night_schedule() {
+        let schedule = schedule_from_toml(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(schedule.nights.len(), 1);
+    }
+
+    #[test]
+    fn test_guild_schedules_round_trip() {
+        let mut schedules = HashMap::new();
+        schedules.insert(GuildId::new(1234), GameNightSchedule::default());
+        schedules.insert(
+            GuildId::new(5678),
+            GameNightSchedule {
+                nights: vec![GameNightConfig { day_of_week: Weekday::Sat, ..GameNightConfig::default() }],
+            },
+        );
+
+        let path = std::env::temp_dir().join("tannius-bot-guilds.toml");
+        save_guild_schedules(&path, &schedules).unwrap();
+        let loaded = load_guild_schedules(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[&GuildId::new(1234)].nights[0].day_of_week, Weekday::Fri);
+        assert_eq!(loaded[&GuildId::new(5678)].nights[0].day_of_week, Weekday::Sat);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_export_schedule_round_trip() {
+        let night = GameNightConfig {
+            day_of_week: Weekday::Sat,
+            start_time: NaiveTime::from_hms_opt(19, 30, 0).unwrap(),
+            timezone: chrono_tz::Europe::Berlin,
+            recurrence: Recurrence::OddWeeks,
+            reminder_channel: Some(ChannelId::new(42)),
+            ..GameNightConfig::default()
+        };
+        let exported = export_schedule(&GameNightSchedule { nights: vec![night.clone()] });
+        assert!(exported.contains("day_of_week = \"Sat\""), "{}", exported);
+        assert!(exported.contains("timezone = \"Europe/Berlin\""), "{}", exported);
+
+        let loaded = GameNightConfig::from_toml_str(&exported).unwrap();
+        assert_eq!(loaded.day_of_week, Weekday::Sat);
+        assert_eq!(loaded.start_time, NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+        assert_eq!(loaded.timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(loaded.recurrence, Recurrence::OddWeeks);
+        assert_eq!(loaded.reminder_channel, Some(ChannelId::new(42)));
+        assert_eq!(export_schedule(&GameNightSchedule { nights: vec![loaded] }), exported);
+
+        // Several nights come out as [[nights]]
+        let schedule = GameNightSchedule { nights: vec![night, GameNightConfig::default()] };
+        let exported = export_schedule(&schedule);
+        assert!(exported.contains("[[nights]]"), "{}", exported);
+        let base = ConfigFile::from(&GameNightConfig::default());
+        let loaded = GameNightSchedule::from_toml_str_over(&exported, base).unwrap();
+        assert_eq!(loaded.nights.len(), 2);
+        assert_eq!(export_schedule(&loaded), exported);
+    }
+
+    #[test]
+    fn test_override_round_trip() {
+        let override_date = chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 6, 8, 22, 0, 0).unwrap();
+        let mut schedules = HashMap::new();
+        schedules.insert(
+            GuildId::new(1234),
+            GameNightSchedule {
+                nights: vec![GameNightConfig { override_date: Some(override_date), ..GameNightConfig::default() }],
+            },
+        );
+
+        let path = std::env::temp_dir().join("tannius-bot-override.toml");
+        save_guild_schedules(&path, &schedules).unwrap();
+        let loaded = load_guild_schedules(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded[&GuildId::new(1234)].nights[0].override_date, Some(override_date));
+    }
+
+    #[test]
+    fn test_skipped_dates() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            skipped_dates = ["2024-06-07", "2024-06-21"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.skipped_dates.len(), 2);
+        assert!(config.skipped_dates.contains(&NaiveDate::from_ymd_opt(2024, 6, 21).unwrap()));
+    }
+
+    #[test]
+    fn test_end_time() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            end_time = "01:30"
+            next_day = true
+            timezone = "America/New_York"
+            "#,
+        )
+        .unwrap();
+        let end_time = NaiveTime::from_hms_opt(1, 30, 0).unwrap();
+        assert_eq!(config.length, SessionLength::EndTime { end_time, next_day: true });
+
+        // Saved the same way it was written
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(saved.contains("end_time = \"01:30\""));
+        assert!(saved.contains("next_day = true"));
+        assert!(!saved.contains("duration_hours"));
+    }
+
+    #[test]
+    fn test_session_length_exactly_one() {
+        let both = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            end_time = "23:00"
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(both, Err(ConfigError::SessionLengthConflict)));
+
+        let neither = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(neither, Err(ConfigError::SessionLengthConflict)));
+    }
+
+    #[test]
+    fn test_end_time_out_of_range() {
+        // Ending before it starts needs next_day
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            end_time = "01:00"
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::EndTimeOutOfRange(_))));
+
+        // ...and with next_day it can't run past a full day
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            end_time = "21:00"
+            next_day = true
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::EndTimeOutOfRange(_))));
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            end_time = "2pm"
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidEndTime(_))));
+    }
+
+    #[test]
+    fn test_holiday_policies() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            holidays = { christmas_eve = "skip", halloween = "Ignore" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.holiday_policies[&Holiday::ChristmasEve], HolidayPolicy::Skip);
+        assert_eq!(config.holiday_policies[&Holiday::Halloween], HolidayPolicy::Ignore);
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        let reloaded = GameNightConfig::from_toml_str(&saved).unwrap();
+        assert_eq!(reloaded.holiday_policies, config.holiday_policies);
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            holidays = { easter = "skip" }
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidHoliday(_))));
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            holidays = { halloween = "maybe" }
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidHolidayPolicy(_))));
+    }
+
+    #[test]
+    fn test_custom_specials() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            specials = { "06-07" = "Server anniversary!", "2-29" = "Leap night!" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.custom_specials[&(6, 7)], "Server anniversary!");
+        assert_eq!(config.custom_specials[&(2, 29)], "Leap night!");
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(saved.contains("02-29"));
+        let reloaded = GameNightConfig::from_toml_str(&saved).unwrap();
+        assert_eq!(reloaded.custom_specials, config.custom_specials);
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            specials = { "June 7" = "Server anniversary!" }
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidSpecialDate(_))));
+    }
+
+    #[test]
+    fn test_special_durations() {
+        let toml = |durations: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                durations
+            )
+        };
+        let config = GameNightConfig::from_toml_str(&toml("special_durations = { \"12-31\" = 360 }")).unwrap();
+        assert_eq!(config.special_durations[&(12, 31)], 360);
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().special_durations, config.special_durations);
+        assert!(matches!(
+            GameNightConfig::from_toml_str(&toml("special_durations = { \"12-31\" = 0 }")),
+            Err(ConfigError::DurationOutOfRange(0))
+        ));
+        assert!(matches!(
+            GameNightConfig::from_toml_str(&toml("special_durations = { \"Dec 31\" = 360 }")),
+            Err(ConfigError::InvalidSpecialDate(_))
+        ));
+    }
+
+    #[test]
+    fn test_locale() {
+        let toml = |locale: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                locale
+            )
+        };
+        assert_eq!(GameNightConfig::from_toml_str(&toml("")).unwrap().locale, Locale::English);
+        let config = GameNightConfig::from_toml_str(&toml("locale = \"es\"")).unwrap();
+        assert_eq!(config.locale, Locale::Spanish);
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().locale, Locale::Spanish);
+        assert!(matches!(GameNightConfig::from_toml_str(&toml("locale = \"xx\"")), Err(ConfigError::InvalidLocale(_))));
+    }
+
+    #[test]
+    fn test_reminder_template() {
+        let toml = |reminder_template: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                reminder_template
+            )
+        };
+        assert_eq!(GameNightConfig::from_toml_str(&toml("")).unwrap().reminder_template, DEFAULT_REMINDER_TEMPLATE);
+        // Unknown placeholders only get a warning
+        let config = GameNightConfig::from_toml_str(&toml("reminder_template = \"{game} in {countdown} {snacks}\"")).unwrap();
+        assert_eq!(config.reminder_template, "{game} in {countdown} {snacks}");
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().reminder_template, config.reminder_template);
+        let saved = toml::to_string(&ConfigFile::from(&GameNightConfig::default())).unwrap();
+        assert!(!saved.contains("reminder_template"));
+    }
+
+    #[test]
+    fn test_announcements_paused() {
+        let toml = |paused: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                paused
+            )
+        };
+        assert!(GameNightConfig::from_toml_str(&toml("")).unwrap().announcements_enabled);
+        let config = GameNightConfig::from_toml_str(&toml("announcements_enabled = false")).unwrap();
+        assert!(!config.announcements_enabled);
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(!GameNightConfig::from_toml_str(&saved).unwrap().announcements_enabled);
+        let saved = toml::to_string(&ConfigFile::from(&GameNightConfig::default())).unwrap();
+        assert!(!saved.contains("announcements_enabled"));
+    }
+
+    #[test]
+    fn test_last_call_minutes() {
+        let toml = |last_call: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                last_call
+            )
+        };
+        assert_eq!(GameNightConfig::from_toml_str(&toml("")).unwrap().last_call_minutes, Some(30));
+        assert_eq!(GameNightConfig::from_toml_str(&toml("last_call_minutes = 0")).unwrap().last_call_minutes, None);
+        let config = GameNightConfig::from_toml_str(&toml("last_call_minutes = 10")).unwrap();
+        assert_eq!(config.last_call_minutes, Some(10));
+
+        let saved = toml::to_string(&ConfigFile { last_call_minutes: 0, ..ConfigFile::from(&config) }).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().last_call_minutes, None);
+    }
+
+    #[test]
+    fn test_emoji() {
+        let toml = |emoji: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                emoji
+            )
+        };
+        assert!(GameNightConfig::from_toml_str(&toml("")).unwrap().emoji.is_empty());
+        let config = GameNightConfig::from_toml_str(&toml("emoji = { live = \"🚨\", game = \"<:dice:123>\" }")).unwrap();
+        assert_eq!(config.emoji.get(&EmojiSlot::Live).map(String::as_str), Some("🚨"));
+        assert_eq!(config.emoji.get(&EmojiSlot::Game).map(String::as_str), Some("<:dice:123>"));
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().emoji, config.emoji);
+
+        let result = GameNightConfig::from_toml_str(&toml("emoji = { snacks = \"🍕\" }"));
+        assert!(matches!(result, Err(ConfigError::InvalidEmojiSlot(slot)) if slot == "snacks"));
+        let result = GameNightConfig::from_toml_str(&toml("emoji = { live = \"LIVE\" }"));
+        assert!(matches!(result, Err(ConfigError::InvalidEmoji(_))));
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#FF0000"), Some(Colour::new(0xFF0000)));
+        assert_eq!(parse_hex_color("#FFF"), Some(Colour::new(0xFFFFFF)));
+        assert_eq!(parse_hex_color("#f80"), Some(Colour::new(0xFF8800)));
+        assert_eq!(parse_hex_color(" 2ecc71 "), Some(Colour::new(0x2ECC71)));
+        for value in ["", "#", "red", "#FF00", "#FF00000", "#GG0000", "##FF0000", "#+FFFFF", "#FF 000"] {
+            assert_eq!(parse_hex_color(value), None, "{:?}", value);
+        }
+        assert_eq!(format_hex_color(Colour::new(0x00AB0C)), "#00AB0C");
+    }
+
+    #[test]
+    fn test_embed_colors() {
+        let toml = |colors: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                colors
+            )
+        };
+        let config = GameNightConfig::from_toml_str(&toml("")).unwrap();
+        assert_eq!((config.embed_color_live, config.embed_color_upcoming), (LIVE_COLOUR, UPCOMING_COLOUR));
+        let config = GameNightConfig::from_toml_str(&toml("embed_color_live = \"#FF0000\"")).unwrap();
+        assert_eq!((config.embed_color_live, config.embed_color_upcoming), (Colour::new(0xFF0000), UPCOMING_COLOUR));
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(saved.contains("embed_color_live = \"#FF0000\""), "{}", saved);
+        assert!(!saved.contains("embed_color_upcoming"), "{}", saved);
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().embed_color_live, Colour::new(0xFF0000));
+
+        let result = GameNightConfig::from_toml_str(&toml("embed_color_upcoming = \"green\""));
+        assert!(matches!(result, Err(ConfigError::InvalidColor(value)) if value == "green"));
+    }
+
+    #[test]
+    fn test_late_join_grace_minutes() {
+        let toml = |grace: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                grace
+            )
+        };
+        assert_eq!(GameNightConfig::from_toml_str(&toml("")).unwrap().late_join_grace_minutes, None);
+        let config = GameNightConfig::from_toml_str(&toml("late_join_grace_minutes = 30")).unwrap();
+        assert_eq!(config.late_join_grace_minutes, Some(30));
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().late_join_grace_minutes, Some(30));
+
+        // Up to the whole session, but no longer
+        assert!(GameNightConfig::from_toml_str(&toml("late_join_grace_minutes = 240")).is_ok());
+        let result = GameNightConfig::from_toml_str(&toml("late_join_grace_minutes = 241"));
+        assert!(matches!(result, Err(ConfigError::GraceOutOfRange(241))));
+    }
+
+    #[test]
+    fn test_scheduler_tick_seconds() {
+        let toml = |tick: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                tick
+            )
+        };
+        let config = GameNightConfig::from_toml_str(&toml("")).unwrap();
+        assert_eq!(config.scheduler_tick_seconds, DEFAULT_SCHEDULER_TICK_SECONDS);
+        assert!(!toml::to_string(&ConfigFile::from(&config)).unwrap().contains("scheduler_tick_seconds"));
+
+        let config = GameNightConfig::from_toml_str(&toml("scheduler_tick_seconds = 15")).unwrap();
+        assert_eq!(config.scheduler_tick_seconds, 15);
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().scheduler_tick_seconds, 15);
+
+        for seconds in [0, MAX_SCHEDULER_TICK_SECONDS + 1] {
+            let result = GameNightConfig::from_toml_str(&toml(&format!("scheduler_tick_seconds = {}", seconds)));
+            assert!(matches!(result, Err(ConfigError::SchedulerTickOutOfRange(value)) if value == seconds));
+        }
+
+        // With several nights the scheduler goes by the shortest
+        let mut schedule = GameNightSchedule { nights: vec![GameNightConfig::default(), config] };
+        assert_eq!(schedule.scheduler_tick(), std::time::Duration::from_secs(15));
+        schedule.nights.pop();
+        assert_eq!(schedule.scheduler_tick(), std::time::Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_min_players() {
+        let toml = |min_players: &str| {
+            format!(
+                "day_of_week = \"Fri\"\nstart_time = \"20:00\"\nduration_hours = 4\ntimezone = \"UTC\"\n{}",
+                min_players
+            )
+        };
+        assert_eq!(GameNightConfig::from_toml_str(&toml("")).unwrap().min_players, None);
+        assert_eq!(GameNightConfig::from_toml_str(&toml("min_players = 0")).unwrap().min_players, None);
+        let config = GameNightConfig::from_toml_str(&toml("min_players = 4")).unwrap();
+        assert_eq!(config.min_players, Some(4));
+
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert_eq!(GameNightConfig::from_toml_str(&saved).unwrap().min_players, Some(4));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0);
+        let cases = [
+            ("8pm", time(20, 0)),
+            ("8:00 PM", time(20, 0)),
+            ("8:30pm", time(20, 30)),
+            ("12am", time(0, 0)),
+            ("20:00", time(20, 0)),
+            ("20:00:00", time(20, 0)),
+            (" 09:15 ", time(9, 15)),
+            ("25:00", None),
+            ("13pm", None),
+            ("8", None),
+            ("eight", None),
+            ("", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_time(value), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_month_day() {
+        assert_eq!(parse_month_day("07-04"), Some((7, 4)));
+        assert_eq!(parse_month_day("2-29"), Some((2, 29)));
+        assert_eq!(parse_month_day("02-30"), None);
+        assert_eq!(parse_month_day("13-01"), None);
+        assert_eq!(parse_month_day("July 4"), None);
+    }
+
+    #[test]
+    fn test_display_timezones() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            display_timezones = ["America/Los_Angeles", "UTC", "Europe/Paris"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.display_timezones,
+            vec![chrono_tz::America::Los_Angeles, chrono_tz::UTC, chrono_tz::Europe::Paris]
+        );
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Fri"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            display_timezones = ["Mars/Olympus_Mons"]
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::InvalidTimezone(name)) if name == "Mars/Olympus_Mons"));
+    }
+
+    #[test]
+    fn test_invalid_guild_id() {
+        let path = std::env::temp_dir().join("tannius-bot-bad-guild.toml");
+        std::fs::write(&path, "[guilds.abc]\nnights = []\n").unwrap();
+        let result = load_guild_schedules(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ConfigError::InvalidGuildId(_))));
+    }
+
+    #[test]
+    fn test_change_setting() {
+        let current = GameNightConfig { length: SessionLength::Minutes(150), ..GameNightConfig::default() };
+        let config = change_setting(&current, Setting::Day, "tue").unwrap();
+        assert_eq!(config.day_of_week, Weekday::Tue);
+        assert_eq!((config.start_time, config.length), (current.start_time, current.length));
+
+        let config = change_setting(&current, Setting::Time, "7:30 PM").unwrap();
+        assert_eq!(config.start_time, NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+        assert_eq!(config.day_of_week, current.day_of_week);
+
+        let config = change_setting(&current, Setting::Timezone, "Europe/Madrid").unwrap();
+        assert_eq!(config.timezone, chrono_tz::Europe::Madrid);
+
+        // A monthly night moves its weekday along with the day
+        let monthly = GameNightConfig { recurrence: Recurrence::MonthlyNth { week: 2, weekday: Weekday::Fri }, ..current.clone() };
+        let config = change_setting(&monthly, Setting::Day, "Sunday").unwrap();
+        assert_eq!(config.recurrence, Recurrence::MonthlyNth { week: 2, weekday: Weekday::Sun });
+
+        assert!(matches!(change_setting(&current, Setting::Day, "someday"), Err(ConfigError::InvalidWeekday(_))));
+        assert!(matches!(change_setting(&current, Setting::Time, "25:00"), Err(ConfigError::InvalidTime(_))));
+        assert!(matches!(
+            change_setting(&current, Setting::Timezone, "Mars/Base"),
+            Err(ConfigError::UnknownTimezone { .. })
+        ));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_parse_set_game_night_args() {
+        let current = GameNightConfig::default();
+        let config = parse_set_game_night_args(&["sat", "19:30", "3"], &current).unwrap();
+        assert_eq!(config.day_of_week, Weekday::Sat);
+        assert_eq!(config.start_time, NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+        assert_eq!(config.length, SessionLength::Minutes(3 * 60));
+        assert_eq!(config.timezone, current.timezone);
+
+        let config = parse_set_game_night_args(&["Sun", "18:00", "2", "Europe/London"], &current).unwrap();
+        assert_eq!(config.timezone, chrono_tz::Europe::London);
+
+        let current = GameNightConfig { reminder_channel: Some(ChannelId::new(42)), ..current };
+        let config = parse_set_game_night_args(&["Sun", "18:00", "2"], &current).unwrap();
+        assert_eq!(config.reminder_channel, Some(ChannelId::new(42)));
+
+        let result = parse_set_game_night_args(&["Sun", "18:00", "two"], &current);
+        assert!(matches!(result, Err(ConfigError::InvalidDuration(_))));
+
+        let result = parse_set_game_night_args(&["Sun", "18:00", "100"], &current);
+        assert!(matches!(result, Err(ConfigError::DurationOutOfRange(6000))));
+
+        let config = parse_set_game_night_args(&["Sun", "18:00", "2.5"], &current).unwrap();
+        assert_eq!(config.length, SessionLength::Minutes(150));
+
+        let config = parse_set_game_night_args(&["Sun", "7:30pm", "2"], &current).unwrap();
+        assert_eq!(config.start_time, NaiveTime::from_hms_opt(19, 30, 0).unwrap());
+        let result = parse_set_game_night_args(&["Sun", "25:00", "2"], &current);
+        assert!(matches!(result, Err(ConfigError::InvalidTime(_))));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_set_game_night_timezone_typo() {
+        let current = GameNightConfig::default();
+        let result = parse_set_game_night_args(&["Sun", "18:00", "2", "America/New_Yrok"], &current);
+        let Err(why) = result else {
+            panic!("America/New_Yrok shouldn't parse");
+        };
+        assert!(why.to_string().starts_with("unknown timezone 'America/New_Yrok', did you mean: "));
+        let ConfigError::UnknownTimezone { value, suggestions } = why else {
+            panic!("expected an unknown timezone, got {:?}", why);
+        };
+        assert_eq!(value, "America/New_Yrok");
+        assert!(suggestions.contains(&"America/New_York"));
+
+        let result = parse_set_game_night_args(&["Sun", "18:00", "2", "Nowhere/Special"], &current);
+        assert_eq!(
+            result.map(|_| ()).unwrap_err().to_string(),
+            "unknown timezone 'Nowhere/Special', expected an IANA name like \"America/New_York\""
+        );
+    }
+
+    #[test]
+    fn test_duration_minutes() {
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Friday"
+            start_time = "20:00"
+            duration_minutes = 90
+            timezone = "America/New_York"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.length, SessionLength::Minutes(90));
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(saved.contains("duration_minutes = 90"));
+        assert!(!saved.contains("duration_hours"));
+
+        // Older configs with whole hours load, and save the way they were
+        let config = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Friday"
+            start_time = "20:00"
+            duration_hours = 4
+            timezone = "America/New_York"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.length, SessionLength::Minutes(240));
+        let saved = toml::to_string(&ConfigFile::from(&config)).unwrap();
+        assert!(saved.contains("duration_hours = 4"));
+        assert!(!saved.contains("duration_minutes"));
+
+        let both = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Friday"
+            start_time = "20:00"
+            duration_hours = 1
+            duration_minutes = 30
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(both, Err(ConfigError::SessionLengthConflict)));
+    }
+
+    #[test]
+    fn test_validate_duration() {
+        let config = GameNightConfig { length: SessionLength::Minutes(24 * 60), ..GameNightConfig::default() };
+        assert!(config.validate().is_ok());
+
+        for minutes in [0, 24 * 60 + 1] {
+            let config = GameNightConfig { length: SessionLength::Minutes(minutes), ..GameNightConfig::default() };
+            assert!(matches!(config.validate(), Err(ConfigError::DurationOutOfRange(_))));
+        }
+
+        let result = GameNightConfig::from_toml_str(
+            r#"
+            day_of_week = "Friday"
+            start_time = "20:00"
+            duration_hours = 0
+            timezone = "America/New_York"
+            "#,
+        );
+        assert!(matches!(result, Err(ConfigError::DurationOutOfRange(0))));
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse_timezone(" Europe/Berlin "), Ok(chrono_tz::Europe::Berlin));
+
+        let suggestions = parse_timezone("europe/berlin").unwrap_err();
+        assert_eq!(suggestions[0], "Europe/Berlin");
+        assert!(parse_timezone("America/New_Yrok").unwrap_err().contains(&"America/New_York"));
+        assert!(parse_timezone("Tokio").unwrap_err().contains(&"Asia/Tokyo"));
+        assert!(parse_timezone("Nowhere/Special").unwrap_err().is_empty());
+    }
+
+    #[test]
+    fn test_search_timezones() {
+        assert_eq!(search_timezones("new york"), vec!["America/New_York"]);
+        assert_eq!(search_timezones("BERLIN"), vec!["Europe/Berlin"]);
+        let america = search_timezones("america/");
+        assert!(america.len() > 100);
+        assert!(america.is_sorted());
+        assert!(search_timezones("Nowhere").is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("tokio", "tokyo"), 1);
+        assert_eq!(edit_distance("berlin", "berlin"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    fn schedule_from_toml(contents: &str) -> Result<GameNightSchedule, ConfigError> {
+        GameNightSchedule::from_toml_str_over(contents, ConfigFile::from(&GameNightConfig::default()))
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let night = GameNightConfig::from_toml_path_with_env(Path::new("does-not-exist.toml"), |_| None).unwrap();
+        assert_eq!(night.day_of_week, Weekday::Fri);
+    }
+
+    #[test]
+    fn test_from_toml_path() {
+        let path = std::env::temp_dir().join("tannius-bot-single-night.toml");
+        std::fs::write(&path, "day_of_week = \"Sat\"\nend_time = \"23:00\"\n").unwrap();
+        let night = GameNightConfig::from_toml_path_with_env(&path, env(&[("GAMENIGHT_TZ", "Europe/Berlin")])).unwrap();
+        assert_eq!(night.day_of_week, Weekday::Sat);
+        assert_eq!(night.timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(
+            night.length,
+            SessionLength::EndTime { end_time: NaiveTime::from_hms_opt(23, 0, 0).unwrap(), next_day: false }
+        );
+
+        // The schedule loader reads a single night through it
+        let schedule = GameNightSchedule::load(&path).unwrap();
+        assert_eq!(schedule.nights.len(), 1);
+        assert_eq!(schedule.nights[0].day_of_week, Weekday::Sat);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_env_overrides_defaults() {
+        let vars = env(&[
+            ("GAMENIGHT_DAY", "saturday"),
+            ("GAMENIGHT_TIME", "19:00"),
+            ("GAMENIGHT_DURATION", "3"),
+            ("GAMENIGHT_TZ", "Europe/Berlin"),
+            ("GAMENIGHT_SCHEDULER_TICK_SECONDS", "30"),
+        ]);
+        let night = GameNightConfig::from_toml_path_with_env(Path::new("does-not-exist.toml"), vars).unwrap();
+        assert_eq!(night.day_of_week, Weekday::Sat);
+        assert_eq!(night.start_time, NaiveTime::from_hms_opt(19, 0, 0).unwrap());
+        assert_eq!(night.length, SessionLength::Minutes(3 * 60));
+        assert_eq!(night.timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(night.scheduler_tick_seconds, 30);
+
+        // Anything not set keeps its default
+        let night = GameNightConfig::from_toml_path_with_env(Path::new("does-not-exist.toml"), env(&[("GAMENIGHT_DAY", "Tue")]))
+            .unwrap();
+        assert_eq!(night.day_of_week, Weekday::Tue);
+        assert_eq!(night.start_time, GameNightConfig::default().start_time);
+    }
+
+    #[test]
+    fn test_file_overrides_env() {
+        let base = env_night(env(&[("GAMENIGHT_DAY", "Tue"), ("GAMENIGHT_TZ", "Europe/Berlin")])).unwrap();
+        let schedule = GameNightSchedule::from_toml_str_over(
+            r#"
+            day_of_week = "Thursday"
+            end_time = "23:00"
+            "#,
+            base,
+        )
+        .unwrap();
+        let night = &schedule.nights[0];
+        // The file's day and length win, the timezone it left out comes from the environment
+        assert_eq!(night.day_of_week, Weekday::Thu);
+        assert_eq!(night.timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(
+            night.length,
+            SessionLength::EndTime { end_time: NaiveTime::from_hms_opt(23, 0, 0).unwrap(), next_day: false }
+        );
+
+        // Each of several nights fills in from the environment the same way
+        let base = env_night(env(&[("GAMENIGHT_TZ", "Europe/Berlin")])).unwrap();
+        let schedule = GameNightSchedule::from_toml_str_over(
+            r#"
+            [[nights]]
+            day_of_week = "Tue"
+
+            [[nights]]
+            day_of_week = "Fri"
+            timezone = "UTC"
+            "#,
+            base,
+        )
+        .unwrap();
+        assert_eq!(schedule.nights[0].timezone, chrono_tz::Europe::Berlin);
+        assert_eq!(schedule.nights[1].timezone, chrono_tz::UTC);
+    }
+
+    #[test]
+    fn test_invalid_env_vars() {
+        for (name, value) in [
+            ("GAMENIGHT_DAY", "someday"),
+            ("GAMENIGHT_TIME", "8pm"),
+            ("GAMENIGHT_DURATION", "0"),
+            ("GAMENIGHT_TZ", "Mars/Olympus"),
+            ("GAMENIGHT_SCHEDULER_TICK_SECONDS", "0"),
+            ("GAMENIGHT_SCHEDULER_TICK_SECONDS", "901"),
+        ] {
+            let why = env_night(env(&[(name, value)])).err().unwrap();
+            assert!(matches!(why, ConfigError::InvalidEnvVar { name: invalid, .. } if invalid == name));
+        }
+        let why = env_night(env(&[("GAMENIGHT_DURATION", "four")])).err().unwrap();
+        assert_eq!(why.to_string(), "invalid GAMENIGHT_DURATION 'four', expected a number of hours up to 24, like 4 or 2.5");
+    }
+
+    // Odd input, from anything at all to strings built out of the pieces the
+    // parsers look for, mustn't panic any of them
+    fn messy() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "[0-9:apmAPM .\u{0}é🎮]{0,12}", "[a-zA-Z ]{0,12}"]
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_time_never_panics(value in messy()) {
+            let _ = parse_time(&value);
+        }
+
+        #[test]
+        fn test_parse_time_reads_what_it_formats(hour in 0u32..24, minute in 0u32..60, pm in any::<bool>()) {
+            let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+            let twelve_hour = NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0).unwrap();
+            prop_assert_eq!(parse_time(&time.format("%H:%M").to_string()), Some(time));
+            prop_assert_eq!(parse_time(&time.format("%H:%M:%S").to_string()), Some(time));
+            prop_assert_eq!(parse_time(&twelve_hour.format(" %-I:%M %p ").to_string()), Some(twelve_hour));
+            if minute == 0 {
+                prop_assert_eq!(parse_time(&twelve_hour.format("%-I%P").to_string()), Some(twelve_hour));
+            }
+        }
+
+        #[test]
+        fn test_parse_weekday_never_panics(value in messy()) {
+            let _ = parse_weekday(&value);
+        }
+
+        #[test]
+        fn test_parse_weekday_reads_prefixes(day in 0usize..7, length in 3usize..10, upper in any::<bool>()) {
+            let (name, weekday) = WEEKDAY_NAMES[day];
+            let prefix = &name[..length.min(name.len())];
+            let prefix = if upper { prefix.to_uppercase() } else { prefix.to_string() };
+            prop_assert_eq!(parse_weekday(&prefix), Some(weekday));
+        }
+
+        #[cfg(feature = "prefix-commands")]
+        #[test]
+        fn test_parse_set_game_night_args_never_panics(args in prop::collection::vec(messy(), 3..=4)) {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let _ = parse_set_game_night_args(&args, &GameNightConfig::default());
+        }
+
+        #[cfg(feature = "prefix-commands")]
+        #[test]
+        fn test_parse_set_game_night_args_keeps_what_it_was_given(
+            day in 0usize..7,
+            hour in 0u32..24,
+            minute in 0u32..60,
+            half_hours in 1u32..=48,
+        ) {
+            let (name, weekday) = WEEKDAY_NAMES[day];
+            let time = format!("{:02}:{:02}", hour, minute);
+            let hours = (half_hours as f64 / 2.0).to_string();
+            let config = parse_set_game_night_args(&[name, &time, &hours], &GameNightConfig::default()).unwrap();
+            prop_assert_eq!(config.day_of_week, weekday);
+            prop_assert_eq!(config.start_time, NaiveTime::from_hms_opt(hour, minute, 0).unwrap());
+            prop_assert_eq!(config.length, SessionLength::Minutes(half_hours * 30));
+        }
+    }
+}
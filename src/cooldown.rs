@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serenity::model::id::UserId;
+
+// How long a member waits between two uses of the same command, unless
+// COOLDOWN_SECONDS or COMMAND_COOLDOWNS say otherwise
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(3);
+
+// What to do with a command a member just sent
+#[derive(Debug, PartialEq)]
+pub enum CooldownCheck {
+    // Run it
+    Ready,
+    // Too soon. `first` is set for the first try while waiting, so the
+    // member hears about it once and further tries are dropped silently.
+    Waiting { first: bool },
+}
+
+struct LastUse {
+    at: Instant,
+    warned: bool,
+}
+
+// When each member last used each command
+pub struct Cooldowns {
+    default: Duration,
+    // Commands with their own cooldown
+    per_command: HashMap<String, Duration>,
+    last_used: HashMap<(UserId, String), LastUse>,
+}
+
+impl Cooldowns {
+    pub fn new(default: Duration, per_command: HashMap<String, Duration>) -> Self {
+        Self {
+            default,
+            per_command,
+            last_used: HashMap::new(),
+        }
+    }
+
+    fn cooldown(&self, command: &str) -> Duration {
+        self.per_command.get(command).copied().unwrap_or(self.default)
+    }
+
+    // Whether `user_id` may use `command` now. Using it starts the cooldown
+    // over; trying while it's running doesn't.
+    pub fn check(&mut self, user_id: UserId, command: &str) -> CooldownCheck {
+        self.check_at(user_id, command, Instant::now())
+    }
+
+    fn check_at(&mut self, user_id: UserId, command: &str, now: Instant) -> CooldownCheck {
+        let cooldown = self.cooldown(command);
+        // Forget uses whose cooldown is over, so the map stays small
+        let default = self.default;
+        let per_command = &self.per_command;
+        self.last_used.retain(|(_, command), last| {
+            now.duration_since(last.at) < per_command.get(command).copied().unwrap_or(default)
+        });
+
+        match self.last_used.get_mut(&(user_id, command.to_string())) {
+            Some(last) if now.duration_since(last.at) < cooldown => {
+                let first = !last.warned;
+                last.warned = true;
+                CooldownCheck::Waiting { first }
+            }
+            _ => {
+                if !cooldown.is_zero() {
+                    self.last_used.insert((user_id, command.to_string()), LastUse { at: now, warned: false });
+                }
+                CooldownCheck::Ready
+            }
+        }
+    }
+}
+
+// Parse COMMAND_COOLDOWNS, a comma separated list of command=seconds like
+// "nextgame=10,vote=60". A cooldown of 0 turns it off for that command.
+pub fn parse_cooldowns(value: &str) -> Result<HashMap<String, Duration>, String> {
+    let mut cooldowns = HashMap::new();
+    for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (command, seconds) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' should look like command=seconds", entry))?;
+        let seconds: u64 = seconds
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' isn't a number of seconds for {}", seconds.trim(), command.trim()))?;
+        let command = command.trim().trim_start_matches('!').to_lowercase();
+        cooldowns.insert(command, Duration::from_secs(seconds));
+    }
+    Ok(cooldowns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_cooldown_per_user_and_command() {
+        let mut cooldowns = Cooldowns::new(DEFAULT_COOLDOWN, HashMap::new());
+        let start = Instant::now();
+        let alice = UserId::new(1);
+        let bob = UserId::new(2);
+
+        assert_eq!(cooldowns.check_at(alice, "nextgame", start), CooldownCheck::Ready);
+        assert_eq!(cooldowns.check_at(alice, "nextgame", start + Duration::from_secs(1)), CooldownCheck::Waiting { first: true });
+        assert_eq!(cooldowns.check_at(alice, "nextgame", start + Duration::from_secs(2)), CooldownCheck::Waiting { first: false });
+        // Other members and other commands aren't held up
+        assert_eq!(cooldowns.check_at(bob, "nextgame", start + Duration::from_secs(1)), CooldownCheck::Ready);
+        assert_eq!(cooldowns.check_at(alice, "help", start + Duration::from_secs(1)), CooldownCheck::Ready);
+
+        assert_eq!(cooldowns.check_at(alice, "nextgame", start + Duration::from_secs(3)), CooldownCheck::Ready);
+    }
+
+    #[test]
+    fn test_cooldown_per_command_override() {
+        let per_command = parse_cooldowns("vote=60, !Ping=0").unwrap();
+        let mut cooldowns = Cooldowns::new(DEFAULT_COOLDOWN, per_command);
+        let start = Instant::now();
+        let alice = UserId::new(1);
+
+        assert_eq!(cooldowns.check_at(alice, "vote", start), CooldownCheck::Ready);
+        assert_eq!(cooldowns.check_at(alice, "vote", start + Duration::from_secs(30)), CooldownCheck::Waiting { first: true });
+        assert_eq!(cooldowns.check_at(alice, "vote", start + Duration::from_secs(60)), CooldownCheck::Ready);
+
+        assert_eq!(cooldowns.check_at(alice, "ping", start), CooldownCheck::Ready);
+        assert_eq!(cooldowns.check_at(alice, "ping", start), CooldownCheck::Ready);
+    }
+
+    #[test]
+    fn test_parse_cooldowns() {
+        assert!(parse_cooldowns("").unwrap().is_empty());
+        assert_eq!(parse_cooldowns("nextgame=10").unwrap()["nextgame"], Duration::from_secs(10));
+        assert!(parse_cooldowns("nextgame").is_err());
+        assert!(parse_cooldowns("nextgame=soon").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_cooldowns_never_panics(value in prop_oneof![any::<String>(), "[a-z!=, 0-9\u{0}é🎮]{0,24}"]) {
+            let _ = parse_cooldowns(&value);
+        }
+
+        #[test]
+        fn test_parse_cooldowns_reads_what_it_formats(
+            cooldowns in prop::collection::hash_map("[a-z]{1,12}", any::<u64>(), 0..8),
+            bang in any::<bool>(),
+        ) {
+            let value: Vec<String> = cooldowns
+                .iter()
+                .map(|(command, seconds)| format!(" {}{} = {} ", if bang { "!" } else { "" }, command, seconds))
+                .collect();
+            let parsed = parse_cooldowns(&value.join(",")).unwrap();
+            let expected: HashMap<String, Duration> =
+                cooldowns.into_iter().map(|(command, seconds)| (command, Duration::from_secs(seconds))).collect();
+            prop_assert_eq!(parsed, expected);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+// The emoji a guild can swap for its own with !setemoji
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EmojiSlot {
+    // Game night is live
+    Live,
+    // Playing, and the planned game
+    Game,
+    // The next game night
+    Date,
+}
+
+impl EmojiSlot {
+    pub const ALL: [EmojiSlot; 3] = [EmojiSlot::Live, EmojiSlot::Game, EmojiSlot::Date];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EmojiSlot::Live => "live",
+            EmojiSlot::Game => "game",
+            EmojiSlot::Date => "date",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.name().eq_ignore_ascii_case(name))
+    }
+
+    // What replies use unless the guild picked something else
+    pub fn default_emoji(self) -> &'static str {
+        match self {
+            EmojiSlot::Live => "🔴",
+            EmojiSlot::Game => "🎮",
+            EmojiSlot::Date => "📅",
+        }
+    }
+}
+
+// `text` with each slot's default emoji swapped for the guild's own
+pub fn apply(custom: &HashMap<EmojiSlot, String>, text: &str) -> String {
+    let mut text = text.to_string();
+    for slot in EmojiSlot::ALL {
+        if let Some(emoji) = custom.get(&slot) {
+            text = text.replace(slot.default_emoji(), emoji);
+        }
+    }
+    text
+}
+
+// Whether `value` is one emoji: a custom one like <:name:id> or <a:name:id>,
+// or a single Unicode emoji, skin tones, keycaps, flags and ZWJ sequences
+// like 👩‍💻 included
+pub fn is_single_emoji(value: &str) -> bool {
+    is_custom_emoji(value) || is_unicode_emoji(value)
+}
+
+fn is_custom_emoji(value: &str) -> bool {
+    let Some(inner) = value.strip_prefix('<').and_then(|value| value.strip_suffix('>')) else {
+        return false;
+    };
+    let inner = inner.strip_prefix('a').unwrap_or(inner);
+    let mut parts = inner.split(':');
+    let (Some(""), Some(name), Some(id), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    (2..=32).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !id.is_empty()
+        && id.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_unicode_emoji(value: &str) -> bool {
+    let pieces: Vec<&str> = value.split('\u{200D}').collect();
+    if pieces.len() == 1 && is_flag(value) {
+        return true;
+    }
+    pieces.iter().all(|piece| {
+        let mut chars = piece.chars();
+        let Some(base) = chars.next() else {
+            return false;
+        };
+        let keycap = matches!(base, '0'..='9' | '#' | '*');
+        let modifiers: Vec<char> = chars.collect();
+        if keycap {
+            // Only counts with the keycap mark, a bare 7 isn't an emoji
+            return modifiers.last() == Some(&'\u{20E3}') && modifiers.iter().all(|c| is_modifier(*c));
+        }
+        is_emoji_base(base) && modifiers.iter().all(|c| is_modifier(*c))
+    })
+}
+
+// Two regional indicators make a country flag, e.g. 🇪🇸
+fn is_flag(value: &str) -> bool {
+    let chars: Vec<char> = value.chars().collect();
+    chars.len() == 2 && chars.iter().all(|c| ('\u{1F1E6}'..='\u{1F1FF}').contains(c))
+}
+
+fn is_emoji_base(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x2190..=0x21FF
+            | 0x2300..=0x23FF
+            | 0x24C2
+            | 0x25A0..=0x27BF
+            | 0x2900..=0x297F
+            | 0x2B00..=0x2BFF
+            | 0x1F000..=0x1F1E5
+            | 0x1F200..=0x1FAFF
+    )
+}
+
+// Variation selector, skin tones, the keycap mark and tag characters, which
+// all change the emoji before them rather than being one of their own
+fn is_modifier(c: char) -> bool {
+    matches!(c as u32, 0xFE0F | 0x1F3FB..=0x1F3FF | 0x20E3 | 0xE0020..=0xE007F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_emoji() {
+        for emoji in ["🎲", "❤️", "👍🏽", "👩‍💻", "🏳️‍🌈", "🇪🇸", "7️⃣", "<:pog:123456789>", "<a:party_parrot:42>"] {
+            assert!(is_single_emoji(emoji), "{}", emoji);
+        }
+        for value in ["", "a", "7", "🎲🎲", "🎲 ", "🇪🇸🇪", "<:x:1>", "<:pog:>", "<:pog:12a>", "<@123>", "‍", "🎲‍"] {
+            assert!(!is_single_emoji(value), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let custom = HashMap::from([(EmojiSlot::Live, "<:live:1>".to_string())]);
+        assert_eq!(apply(&custom, "🔴 LIVE 🔴\n🎮 playing"), "<:live:1> LIVE <:live:1>\n🎮 playing");
+        assert_eq!(apply(&HashMap::new(), "📅 soon"), "📅 soon");
+    }
+
+    #[test]
+    fn test_slot_names() {
+        for slot in EmojiSlot::ALL {
+            assert_eq!(EmojiSlot::from_name(slot.name()), Some(slot));
+        }
+        assert_eq!(EmojiSlot::from_name("LIVE"), Some(EmojiSlot::Live));
+        assert_eq!(EmojiSlot::from_name("snacks"), None);
+    }
+}
@@ -1,244 +1,2893 @@
-use chrono::{DateTime, Datelike, Local, NaiveTime, Timelike, Utc, Weekday};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, Datelike, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
 use chrono::TimeZone;
+use chrono_tz::{OffsetName, Tz};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter};
+use serenity::model::Colour;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::model::mention::Mentionable;
+
+use crate::emoji::{self, EmojiSlot};
+use crate::i18n::{Locale, Text, fill, month_name, ordinal, plural, tr, weekday_name};
+use crate::template;
+
+// How often a game night comes around
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    // Every week on `day_of_week`
+    Weekly,
+    // Every other week on `day_of_week`, on the weeks lining up with `anchor`
+    Biweekly { anchor: NaiveDate },
+    // Every other week on `day_of_week`, by ISO week number. Years with a
+    // week 53 go straight into week 1, so odd weeks get two in a row then.
+    EvenWeeks,
+    OddWeeks,
+    // The `week`th (1 to 5) `weekday` of each month, e.g. the third Thursday
+    MonthlyNth { week: u8, weekday: Weekday },
+}
+
+// 12-hour clock with AM/PM, or a 24-hour clock
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimeFormat {
+    #[default]
+    Twelve,
+    TwentyFour,
+}
+
+impl TimeFormat {
+    // strftime pattern for a time of day, e.g. "8:00 PM" or "20:00"
+    pub fn pattern(self) -> &'static str {
+        match self {
+            TimeFormat::Twelve => "%-I:%M %p",
+            TimeFormat::TwentyFour => "%H:%M",
+        }
+    }
+}
+
+// Order of day and month in dates, e.g. "June 07, 2024" or "07/06/2024"
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DateStyle {
+    #[default]
+    MonthFirst,
+    DayFirst,
+}
+
+// How long a game night runs: a number of minutes from the start, or until a
+// set time of day. `next_day` says the end time falls after midnight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionLength {
+    Minutes(u32),
+    EndTime { end_time: NaiveTime, next_day: bool },
+}
+
+impl SessionLength {
+    // How long a session starting at `start_time` runs, going by the clock
+    pub fn duration(self, start_time: NaiveTime) -> chrono::Duration {
+        match self {
+            SessionLength::Minutes(minutes) => chrono::Duration::minutes(minutes as i64),
+            SessionLength::EndTime { end_time, next_day } => {
+                let duration = end_time - start_time;
+                if next_day { duration + chrono::Duration::days(1) } else { duration }
+            }
+        }
+    }
+
+    // Time of day a session starting at `start_time` ends, and whether that's
+    // on the next day
+    #[cfg(feature = "prefix-commands")]
+    pub fn end_time(self, start_time: NaiveTime) -> (NaiveTime, bool) {
+        match self {
+            SessionLength::Minutes(minutes) => {
+                let (end_time, wrapped) = start_time.overflowing_add_signed(chrono::Duration::minutes(minutes as i64));
+                (end_time, wrapped > 0)
+            }
+            SessionLength::EndTime { end_time, next_day } => (end_time, next_day),
+        }
+    }
+}
+
+// Holidays that get a themed game night, or can be set to skip it instead
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Holiday {
+    ChristmasEve,
+    NewYearsEve,
+    Halloween,
+    IndependenceDay,
+}
+
+impl Holiday {
+    pub const ALL: [Holiday; 4] = [Holiday::ChristmasEve, Holiday::NewYearsEve, Holiday::Halloween, Holiday::IndependenceDay];
+
+    // The holiday falling on a local date, if any
+    pub fn on(date: NaiveDate) -> Option<Holiday> {
+        Holiday::on_month_day(date.month(), date.day())
+    }
+
+    // The holiday falling on a month and day in any year, as picked by
+    // !disablespecial and !enablespecial
+    pub fn on_month_day(month: u32, day: u32) -> Option<Holiday> {
+        Holiday::ALL.into_iter().find(|holiday| holiday.month_day() == (month, day))
+    }
+
+    fn month_day(self) -> (u32, u32) {
+        match self {
+            Holiday::ChristmasEve => (12, 24),
+            Holiday::NewYearsEve => (12, 31),
+            Holiday::Halloween => (10, 31),
+            Holiday::IndependenceDay => (7, 4),
+        }
+    }
+
+    fn theme(self) -> &'static str {
+        match self {
+            Holiday::ChristmasEve => "🎄 **Christmas Eve Game Night!** 🎅",
+            Holiday::NewYearsEve => "🎊 **New Year's Eve Game Night!** 🥳",
+            Holiday::Halloween => "🎃 **Halloween Game Night!** 👻",
+            Holiday::IndependenceDay => "🎆 **Independence Day Game Night!** 🇺🇸",
+        }
+    }
+
+    // Name used in config files and commands, e.g. "christmas_eve"
+    pub fn key(self) -> &'static str {
+        match self {
+            Holiday::ChristmasEve => "christmas_eve",
+            Holiday::NewYearsEve => "new_years_eve",
+            Holiday::Halloween => "halloween",
+            Holiday::IndependenceDay => "independence_day",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Holiday> {
+        Holiday::ALL.into_iter().find(|holiday| holiday.key().eq_ignore_ascii_case(key.trim()))
+    }
+}
+
+// What a game night landing on a holiday does: play it themed, not play at
+// all, or play it like any other night
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HolidayPolicy {
+    #[default]
+    Theme,
+    Skip,
+    Ignore,
+}
+
+// Which part of a date and time `format_local` shows
+#[derive(Clone, Copy)]
+enum LocalFormat {
+    Date,
+    DateWithoutYear,
+    Time,
+}
 
 // Configuration for game night
+#[derive(Clone)]
 pub struct GameNightConfig {
     pub day_of_week: Weekday,
+    pub recurrence: Recurrence,
     pub start_time: NaiveTime,
-    pub duration_hours: u32,
+    pub length: SessionLength,
     pub timezone: chrono_tz::Tz,
+    // Where reminders get posted, none when reminders are off
+    pub reminder_channel: Option<ChannelId>,
+    // How many minutes before the start each reminder goes out
+    pub reminder_lead_minutes: Vec<u32>,
+    // Role pinged by reminders and the live announcement
+    pub notify_role_id: Option<RoleId>,
+    // Voice channel the group plays in, listed by !whoisplaying
+    pub voice_channel_id: Option<ChannelId>,
+    // How times and dates are written in replies
+    pub time_format: TimeFormat,
+    pub date_style: DateStyle,
+    // One-off session replacing the regular one in its week, e.g. playing
+    // Saturday instead of Friday. Ignored once it's over.
+    pub override_date: Option<DateTime<Utc>>,
+    // Local dates skipped with !skip, when the group agreed not to play
+    pub skipped_dates: HashSet<NaiveDate>,
+    // Extra timezones !nextgame converts the start time into, for groups
+    // spread around the world
+    pub display_timezones: Vec<Tz>,
+    // How each holiday is handled, themed unless listed here
+    pub holiday_policies: HashMap<Holiday, HolidayPolicy>,
+    // Announcements added with !addspecial by month and day, e.g. for a
+    // server anniversary. They come before the holidays.
+    pub custom_specials: HashMap<(u32, u32), String>,
+    // Minutes special nights run instead of the usual length, by month and
+    // day, e.g. a longer New Year's Eve. Only used while that date is special.
+    pub special_durations: HashMap<(u32, u32), u32>,
+    // Language replies are written in
+    pub locale: Locale,
+    // How many have to RSVP "going" for the live announcement to go out,
+    // none to always announce. Below it the group gets a nudge instead.
+    pub min_players: Option<usize>,
+    // Text of the reminders, with REMINDER_PLACEHOLDERS filled in
+    pub reminder_template: String,
+    // Off while !pause holds back reminders and announcements, e.g. during an
+    // off-season
+    pub announcements_enabled: bool,
+    // How many minutes before the end the "last call" goes out, none for no
+    // last call
+    pub last_call_minutes: Option<u32>,
+    // Emoji picked with !setemoji for status and countdown replies, the
+    // defaults for any slot left out
+    pub emoji: HashMap<EmojiSlot, String>,
+    // Sidebar colours of the embeds, picked with !setcolor
+    pub embed_color_live: Colour,
+    pub embed_color_upcoming: Colour,
+    // How long after the start !nextgame keeps pointing at a live session
+    // before moving on to the next one. None for the whole session.
+    pub late_join_grace_minutes: Option<u32>,
+    // How often the scheduler wakes up to look for reminders to send, in
+    // seconds. There's one scheduler for every guild, so only the default
+    // schedule's counts.
+    pub scheduler_tick_seconds: u32,
 }
 
+// Every check the scheduler makes compares against the instant it's waiting
+// for, so any tick works, but one longer than a reminder's lead could sleep
+// right through it
+pub const DEFAULT_SCHEDULER_TICK_SECONDS: u32 = 60;
+pub const MAX_SCHEDULER_TICK_SECONDS: u32 = 15 * 60;
+
 impl Default for GameNightConfig {
     fn default() -> Self {
         Self {
             day_of_week: Weekday::Fri,  // Friday
+            recurrence: Recurrence::Weekly,
             start_time: NaiveTime::from_hms_opt(20, 0, 0).unwrap(), // 8:00 PM
-            duration_hours: 4,
+            length: SessionLength::Minutes(4 * 60),
             timezone: chrono_tz::US::Eastern,
+            reminder_channel: None,
+            reminder_lead_minutes: vec![60, 15],
+            notify_role_id: None,
+            voice_channel_id: None,
+            time_format: TimeFormat::Twelve,
+            date_style: DateStyle::MonthFirst,
+            override_date: None,
+            skipped_dates: HashSet::new(),
+            display_timezones: Vec::new(),
+            holiday_policies: HashMap::new(),
+            custom_specials: HashMap::new(),
+            special_durations: HashMap::new(),
+            locale: Locale::English,
+            min_players: None,
+            reminder_template: DEFAULT_REMINDER_TEMPLATE.to_string(),
+            announcements_enabled: true,
+            last_call_minutes: Some(30),
+            emoji: HashMap::new(),
+            embed_color_live: LIVE_COLOUR,
+            embed_color_upcoming: UPCOMING_COLOUR,
+            late_join_grace_minutes: None,
+            scheduler_tick_seconds: DEFAULT_SCHEDULER_TICK_SECONDS,
         }
     }
 }
 
-pub fn get_next_game_night(config: &GameNightConfig) -> DateTime<Utc> {
-    let now = Utc::now();
+// Every recurring game night a community runs, e.g. Tuesday casual and
+// Friday competitive. Always holds at least one night.
+#[derive(Clone)]
+pub struct GameNightSchedule {
+    pub nights: Vec<GameNightConfig>,
+}
+
+impl Default for GameNightSchedule {
+    fn default() -> Self {
+        Self {
+            nights: vec![GameNightConfig::default()],
+        }
+    }
+}
+
+impl GameNightSchedule {
+    // Index of the soonest upcoming night along with its start instant
+    pub fn next_occurrence(&self) -> Result<(usize, DateTime<Utc>), ScheduleError> {
+        self.next_occurrence_at(Utc::now())
+    }
+
+    pub fn next_occurrence_at(&self, now: DateTime<Utc>) -> Result<(usize, DateTime<Utc>), ScheduleError> {
+        let starts = self.nights
+            .iter()
+            .map(|night| get_next_game_night_at(night, now))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(starts
+            .into_iter()
+            .enumerate()
+            .min_by_key(|(_, start)| *start)
+            .expect("schedule has at least one night"))
+    }
+
+    // Start and end of the soonest upcoming session
+    pub fn next_session(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), ScheduleError> {
+        let (index, start) = self.next_occurrence()?;
+        Ok((start, self.nights[index].session_end(start)?))
+    }
+
+    pub fn is_game_night_now(&self) -> bool {
+        self.nights.iter().any(is_game_night_now)
+    }
+
+    // How often the scheduler wakes up for this schedule, the shortest tick
+    // any of its nights asks for
+    pub fn scheduler_tick(&self) -> std::time::Duration {
+        let seconds = self.nights.iter().map(|night| night.scheduler_tick_seconds).min();
+        std::time::Duration::from_secs(seconds.unwrap_or(DEFAULT_SCHEDULER_TICK_SECONDS) as u64)
+    }
+
+    // Skip the soonest night that hasn't started yet, for !skip, and return
+    // when it would have started. A session that's already live doesn't count.
+    #[cfg(feature = "prefix-commands")]
+    pub fn skip_next(&mut self) -> Result<DateTime<Utc>, ScheduleError> {
+        self.skip_next_at(Utc::now())
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    fn skip_next_at(&mut self, now: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+        let starts = self.nights.iter().map(|night| next_start_at(night, now)).collect::<Result<Vec<_>, _>>()?;
+        let (index, start) = starts
+            .into_iter()
+            .enumerate()
+            .min_by_key(|(_, start)| *start)
+            .expect("schedule has at least one night");
+        let night = &mut self.nights[index];
+        // Forget skips that are already behind us while we're here
+        let today = local_date(night, now);
+        night.skipped_dates.retain(|date| *date >= today);
+        night.skipped_dates.insert(local_date(night, start));
+        Ok(start)
+    }
+
+    // Language of replies that aren't about one night in particular, like !help.
+    // !setlocale keeps every night on the same one.
+    pub fn locale(&self) -> Locale {
+        self.nights[0].locale
+    }
+}
+
+impl GameNightSchedule {
+    // Roles any of the nights ping, for allowing those mentions when sending
+    pub fn notify_roles(&self) -> Vec<RoleId> {
+        let mut roles: Vec<RoleId> = self.nights.iter().filter_map(|night| night.notify_role_id).collect();
+        roles.sort();
+        roles.dedup();
+        roles
+    }
+
+    // Schedule a message from `guild_id` goes by: the guild's own, or the
+    // default one when it hasn't set one. DMs get the default one without its
+    // notify roles, since there's nobody to ping there.
+    pub fn for_guild(
+        default: &GameNightSchedule,
+        schedules: &HashMap<GuildId, GameNightSchedule>,
+        guild_id: Option<GuildId>,
+    ) -> GameNightSchedule {
+        let Some(guild_id) = guild_id else {
+            let mut schedule = default.clone();
+            for night in &mut schedule.nights {
+                night.notify_role_id = None;
+            }
+            return schedule;
+        };
+        schedules.get(&guild_id).unwrap_or(default).clone()
+    }
+}
+
+// Write an instant as the night's local date or time, following its
+// time_format and date_style
+fn format_local(datetime: DateTime<Utc>, config: &GameNightConfig, part: LocalFormat) -> String {
+    format_in_timezone(datetime, config.timezone, config, part)
+}
+
+// Same as format_local, but in some other timezone than the night's own
+fn format_in_timezone(datetime: DateTime<Utc>, timezone: Tz, config: &GameNightConfig, part: LocalFormat) -> String {
+    let local_time = timezone.from_utc_datetime(&datetime.naive_utc());
+    let pattern = match (part, config.date_style) {
+        (LocalFormat::Date, DateStyle::MonthFirst) => "%A, %B %d, %Y",
+        (LocalFormat::Date, DateStyle::DayFirst) => "%A, %d/%m/%Y",
+        (LocalFormat::DateWithoutYear, DateStyle::MonthFirst) => "%A, %B %d",
+        (LocalFormat::DateWithoutYear, DateStyle::DayFirst) => "%A, %d/%m",
+        (LocalFormat::Time, _) => config.time_format.pattern(),
+    };
+    // Day and month names in the night's language, chrono only knows English
+    let pattern = pattern
+        .replace("%A", weekday_name(config.locale, local_time.weekday()))
+        .replace("%B", month_name(config.locale, local_time.month()));
+    local_time.format(&pattern).to_string()
+}
+
+// Put the night's notify role (if any) in front of a message
+fn with_role_ping(config: &GameNightConfig, message: String) -> String {
+    match config.notify_role_id {
+        Some(role_id) => format!("{}\n{}", role_id.mention(), message),
+        None => message,
+    }
+}
+
+// `text` with the night's own emoji in place of the defaults
+pub fn with_emoji(config: &GameNightConfig, text: &str) -> String {
+    emoji::apply(&config.emoji, text)
+}
+
+pub fn get_next_game_night(config: &GameNightConfig) -> Result<DateTime<Utc>, ScheduleError> {
+    get_next_game_night_at(config, Utc::now())
+}
+
+// While a session is live this is when it started, so the "next" game night
+// is the one going on right now rather than next week's. Once the late join
+// grace is over it moves on to the next one, even if the session still runs.
+fn get_next_game_night_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+    match live_session_at(config, now) {
+        Some((start, end)) if now <= late_join_deadline(config, start, end) => Ok(start),
+        _ => next_start_at(config, now),
+    }
+}
+
+// Last moment a session from `start` to `end` still counts as the next game night
+fn late_join_deadline(config: &GameNightConfig, start: DateTime<Utc>, end: DateTime<Utc>) -> DateTime<Utc> {
+    match config.late_join_grace_minutes {
+        Some(minutes) => end.min(start + chrono::Duration::minutes(minutes as i64)),
+        None => end,
+    }
+}
+
+// Start of the first session that hasn't begun yet, or begins right at `now`
+fn next_start_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+    let regular = next_regular_start_at(config, now)?;
+    Ok(match active_override(config) {
+        Some(override_date) if override_date >= now && override_date < regular => override_date,
+        _ => regular,
+    })
+}
+
+// Same as next_start_at, going by the recurrence alone
+fn next_regular_start_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
     let local_now = config.timezone.from_utc_datetime(&now.naive_utc());
     
-    let days_until_game_night = days_until_weekday(local_now.weekday(), config.day_of_week);
+    let weekday = match config.recurrence {
+        Recurrence::MonthlyNth { weekday, .. } => weekday,
+        _ => config.day_of_week,
+    };
+    // Today still counts as long as the start time hasn't passed
+    let include_today = local_now.time() <= config.start_time;
+    let days_until_game_night = days_until_next_weekday(local_now.weekday(), weekday, include_today);
     
     let mut next_game_night = local_now.date_naive() + chrono::Duration::days(days_until_game_night as i64);
     
-    // If it's game night today but the time has passed, schedule for next week
-    if days_until_game_night == 0 && local_now.time() > config.start_time {
-        next_game_night = next_game_night + chrono::Duration::weeks(1);
+    // Weeks the recurrence skips, e.g. the off week of a biweekly night, the
+    // week an override replaced or a night skipped with !skip
+    while !occurs_on(config, next_game_night) {
+        next_game_night += chrono::Duration::weeks(1);
     }
     
-    let game_night_datetime = config.timezone
-        .from_local_datetime(&next_game_night.and_time(config.start_time))
-        .unwrap();
-    
-    game_night_datetime.with_timezone(&Utc)
+    resolve_local(config.timezone, next_game_night.and_time(config.start_time))
 }
 
-pub fn is_game_night_now(config: &GameNightConfig) -> bool {
-    let now = Utc::now();
-    let local_now = config.timezone.from_utc_datetime(&now.naive_utc());
-    
-    if local_now.weekday() != config.day_of_week {
+// Whether there is a regular session starting on the given local date
+fn occurs_on(config: &GameNightConfig, date: NaiveDate) -> bool {
+    if overridden_week(config, date) || config.skipped_dates.contains(&date) {
         return false;
     }
-    
-    let current_time = local_now.time();
-    let end_time = config.start_time + chrono::Duration::hours(config.duration_hours as i64);
-    
-    current_time >= config.start_time && current_time <= end_time
+    if Holiday::on(date).is_some_and(|holiday| holiday_policy(config, holiday) == HolidayPolicy::Skip) {
+        return false;
+    }
+    match config.recurrence {
+        Recurrence::Weekly => date.weekday() == config.day_of_week,
+        Recurrence::Biweekly { anchor } => {
+            let weeks_since_anchor = (date - anchor).num_days().div_euclid(7);
+            date.weekday() == config.day_of_week && weeks_since_anchor % 2 == 0
+        }
+        Recurrence::EvenWeeks => date.weekday() == config.day_of_week && date.iso_week().week().is_multiple_of(2),
+        Recurrence::OddWeeks => date.weekday() == config.day_of_week && !date.iso_week().week().is_multiple_of(2),
+        Recurrence::MonthlyNth { week, weekday } => {
+            date.weekday() == weekday && (date.day0() / 7 + 1) == week as u32
+        }
+    }
 }
 
-pub fn time_until_game_night(config: &GameNightConfig) -> chrono::Duration {
-    let now = Utc::now();
-    let next_game_night = get_next_game_night(config);
-    next_game_night - now
+// The override, unless its day got skipped too
+fn active_override(config: &GameNightConfig) -> Option<DateTime<Utc>> {
+    config.override_date.filter(|override_date| !config.skipped_dates.contains(&local_date(config, *override_date)))
 }
 
-fn days_until_weekday(from: Weekday, to: Weekday) -> u32 {
-    let from_num = from.num_days_from_monday();
-    let to_num = to.num_days_from_monday();
-    
-    if to_num >= from_num {
-        to_num - from_num
+// Local calendar date of an instant in the night's timezone
+pub fn local_date(config: &GameNightConfig, datetime: DateTime<Utc>) -> NaiveDate {
+    config.timezone.from_utc_datetime(&datetime.naive_utc()).date_naive()
+}
+
+// Whether the date falls in the same week (Monday to Sunday) as the override,
+// which takes the place of that week's regular session
+fn overridden_week(config: &GameNightConfig, date: NaiveDate) -> bool {
+    config.override_date.is_some_and(|override_date| {
+        local_date(config, override_date).iso_week() == date.iso_week()
+    })
+}
+
+// Parse a local date (YYYY-MM-DD) and optional HH:MM time, midnight without
+// one, into an instant in the night's timezone
+#[cfg(feature = "prefix-commands")]
+pub fn parse_local_datetime(config: &GameNightConfig, date: &str, time: Option<&str>) -> Result<DateTime<Utc>, String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("❌ '{}' isn't a date, expected YYYY-MM-DD like 2024-12-24", date))?;
+    let time = match time {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|_| format!("❌ '{}' isn't a time, expected HH:MM like 20:00", time))?,
+        None => NaiveTime::MIN,
+    };
+    resolve_local(config.timezone, date.and_time(time)).map_err(|why| format!("❌ {}.", why))
+}
+
+// Format for !tz - a wall-clock time today in one timezone, as read in
+// another, noting when that lands on a different day
+#[cfg(feature = "prefix-commands")]
+pub fn format_timezone_conversion(time: NaiveTime, from: Tz, to: Tz, locale: Locale) -> Result<String, ScheduleError> {
+    format_timezone_conversion_at(time, from, to, locale, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_timezone_conversion_at(
+    time: NaiveTime,
+    from: Tz,
+    to: Tz,
+    locale: Locale,
+    now: DateTime<Utc>,
+) -> Result<String, ScheduleError> {
+    let date = from.from_utc_datetime(&now.naive_utc()).date_naive();
+    let instant = resolve_local(from, date.and_time(time))?;
+    // Can differ from `time` when it falls in a DST gap
+    let from_time = from.from_utc_datetime(&instant.naive_utc());
+    let to_time = to.from_utc_datetime(&instant.naive_utc());
+
+    let day_note = match (to_time.date_naive() - from_time.date_naive()).num_days() {
+        0 => String::new(),
+        1 => tr(locale, Text::TheNextDay).to_string(),
+        -1 => tr(locale, Text::TheDayBefore).to_string(),
+        days if days > 0 => fill(tr(locale, Text::DaysLater), &[days.to_string()]),
+        days => fill(tr(locale, Text::DaysEarlier), &[(-days).to_string()]),
+    };
+    Ok(fill(
+        tr(locale, Text::TimezoneConversion),
+        &[
+            from_time.format("%H:%M").to_string(),
+            from.to_string(),
+            to_time.format("%H:%M").to_string(),
+            to.to_string(),
+            day_note,
+        ],
+    ))
+}
+
+// Start of every session of a night from `now` on, soonest first. A session
+// that's live at `now` comes first. The list stops short of a session that
+// can't be placed, see ScheduleError.
+#[cfg(feature = "prefix-commands")]
+pub fn upcoming_game_nights(config: &GameNightConfig, now: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+    std::iter::successors(get_next_game_night_at(config, now).ok(), |start| {
+        next_start_at(config, *start + chrono::Duration::minutes(1)).ok()
+    })
+}
+
+// A night that can't be put on the calendar. Clocks springing forward only
+// push a time on to the end of the gap, see resolve_local, but a day a
+// timezone skipped whole (Samoa dropped December 30, 2011 crossing the date
+// line) leaves nowhere to push it to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleError {
+    // A wall-clock time that never happens in the timezone
+    NoLocalTime { timezone: Tz, local: NaiveDateTime },
+    // A date past the last one chrono can represent
+    OutOfRange(NaiveDate),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::NoLocalTime { timezone, local } => {
+                write!(f, "{} never happens in {}", local.format("%A, %B %d, %Y at %H:%M"), timezone)
+            }
+            ScheduleError::OutOfRange(date) => write!(f, "there's no date after {} to schedule on", date),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+// Turn a local wall-clock time into a real instant without panicking on DST
+// transitions. Ambiguous times (clocks falling back) resolve to the earliest
+// instant, and times inside a spring-forward gap roll forward to the next
+// minute that actually exists, as long as that's still on the same day.
+fn resolve_local(timezone: Tz, local: NaiveDateTime) -> Result<DateTime<Utc>, ScheduleError> {
+    let mut candidate = local;
+    while candidate.date() == local.date() {
+        match timezone.from_local_datetime(&candidate) {
+            LocalResult::Single(datetime) => return Ok(datetime.with_timezone(&Utc)),
+            LocalResult::Ambiguous(earliest, _) => return Ok(earliest.with_timezone(&Utc)),
+            LocalResult::None => candidate += chrono::Duration::minutes(1),
+        }
+    }
+    Err(ScheduleError::NoLocalTime { timezone, local })
+}
+
+// Start and end of the session beginning on the given local date.
+// The end can land on the next calendar day for late-night sessions.
+fn session_window(config: &GameNightConfig, date: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), ScheduleError> {
+    let start = resolve_local(config.timezone, date.and_time(config.start_time))?;
+    Ok((start, config.session_end(start)?))
+}
+
+impl GameNightConfig {
+    // When the session starting at `start` ends. An end time is read on the
+    // clock of the day the session starts (or the day after), so it holds
+    // across DST changes.
+    pub fn session_end(&self, start: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+        match self.length_on(local_date(self, start)) {
+            SessionLength::Minutes(minutes) => Ok(start + chrono::Duration::minutes(minutes as i64)),
+            SessionLength::EndTime { end_time, next_day } => {
+                let mut date = local_date(self, start);
+                if next_day {
+                    date = date.succ_opt().ok_or(ScheduleError::OutOfRange(date))?;
+                }
+                resolve_local(self.timezone, date.and_time(end_time))
+            }
+        }
+    }
+}
+
+impl GameNightConfig {
+    // How long the session on a local date runs, longer or shorter than usual
+    // when it's a special night with its own duration
+    pub fn length_on(&self, date: NaiveDate) -> SessionLength {
+        match self.special_durations.get(&(date.month(), date.day())) {
+            Some(minutes) if special_on(self, date).is_some() => SessionLength::Minutes(*minutes),
+            _ => self.length,
+        }
+    }
+}
+
+// Session length as replies show it, e.g. "4 hours" or "until 11:30 PM"
+pub fn format_session_length(config: &GameNightConfig) -> String {
+    format_length(config, config.length)
+}
+
+// Same as format_session_length, for the session starting at `start` in
+// particular, which may be a special night with its own duration
+fn format_session_length_for(config: &GameNightConfig, start: DateTime<Utc>) -> String {
+    format_length(config, config.length_on(local_date(config, start)))
+}
+
+fn format_length(config: &GameNightConfig, length: SessionLength) -> String {
+    match length {
+        SessionLength::Minutes(minutes) => format_minutes(minutes, config.locale),
+        SessionLength::EndTime { end_time, next_day } => fill(
+            tr(config.locale, Text::Until),
+            &[
+                end_time.format(config.time_format.pattern()).to_string(),
+                if next_day { tr(config.locale, Text::NextDay).to_string() } else { String::new() },
+            ],
+        ),
+    }
+}
+
+// A number of minutes in hours and minutes, e.g. "2 hours 30 minutes",
+// leaving out whichever part is zero
+fn format_minutes(minutes: u32, locale: Locale) -> String {
+    let minutes = minutes as i64;
+    let hours_part = |hours| plural(locale, hours, Text::Hour, Text::Hours);
+    let minutes_part = |minutes| plural(locale, minutes, Text::Minute, Text::Minutes);
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => minutes_part(minutes),
+        (hours, 0) => hours_part(hours),
+        (hours, minutes) => format!("{} {}", hours_part(hours), minutes_part(minutes)),
+    }
+}
+
+pub fn is_game_night_now(config: &GameNightConfig) -> bool {
+    is_game_night_at(config, Utc::now())
+}
+
+// Whether a session is running at `when`, from its start to its end
+// inclusive, e.g. for checking any instant without waiting for it
+pub fn is_game_night_at(config: &GameNightConfig, when: DateTime<Utc>) -> bool {
+    live_session_at(config, when).is_some()
+}
+
+// Start and end of the session running at `now`, if there is one
+pub fn live_session_at(config: &GameNightConfig, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    recent_sessions(config, local_date(config, now)).find(|(start, end)| now >= *start && now <= *end)
+}
+
+// Start and end of the sessions starting yesterday or on `today`, and the
+// override's. A session that started yesterday may still be running past
+// midnight.
+fn recent_sessions(
+    config: &GameNightConfig,
+    today: NaiveDate,
+) -> impl Iterator<Item = (DateTime<Utc>, DateTime<Utc>)> + '_ {
+    // A one-off session can start at any time, so it keeps the regular
+    // session's length rather than its end time
+    let duration = config.length.duration(config.start_time);
+    let override_window = active_override(config).map(|start| (start, start + duration));
+
+    [today.pred_opt(), Some(today)]
+        .into_iter()
+        .flatten()
+        .filter(|date| occurs_on(config, *date))
+        // A session that can't be placed on the calendar can't be live either
+        .filter_map(|date| session_window(config, date).ok())
+        .chain(override_window)
+}
+
+// End of a session that started or ended on today's local date and is
+// already over, e.g. asking at 1 AM after a session that ended at midnight.
+// None while one is live or when nothing ran today.
+fn session_over_today_at(config: &GameNightConfig, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if live_session_at(config, now).is_some() {
+        return None;
+    }
+    let today = local_date(config, now);
+    recent_sessions(config, today)
+        .filter(|(start, end)| *end < now && (local_date(config, *start) == today || local_date(config, *end) == today))
+        .map(|(_, end)| end)
+        .max()
+}
+
+// How long the live session has left, none when no session is running.
+// Works on the full session window, so late nights past midnight come out right.
+pub fn time_remaining_in_session(config: &GameNightConfig, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    live_session_at(config, now).map(|(_, end)| end - now)
+}
+
+fn time_until_game_night_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<chrono::Duration, ScheduleError> {
+    Ok(get_next_game_night_at(config, now)? - now)
+}
+
+// Days from `from` to the next `to`, 0 to 6 when today counts and 1 to 7
+// when it doesn't
+fn days_until_next_weekday(from: Weekday, to: Weekday, include_today: bool) -> u32 {
+    let days = (7 + to.num_days_from_monday() - from.num_days_from_monday()) % 7;
+    debug_assert!(days < 7, "{} days from {} to {}", days, from, to);
+    if days == 0 && !include_today { 7 } else { days }
+}
+
+// Sidebar colours of the embeds unless a guild picks its own: red while a
+// session is live, green otherwise
+pub const LIVE_COLOUR: Colour = Colour::new(0xE74C3C);
+pub const UPCOMING_COLOUR: Colour = Colour::new(0x2ECC71);
+
+// Format for !nextgame - detailed countdown information. The command replies
+// with next_game_night_embed, this is the same thing as plain text for
+// channels the bot can't post embeds in, built from the same start and
+// timezones.
+#[cfg(feature = "prefix-commands")]
+pub fn format_next_game_night(
+    config: &GameNightConfig,
+    next_game_night: DateTime<Utc>,
+    user_timezone: Option<Tz>,
+    shown_in: Option<Tz>,
+) -> String {
+    format_next_game_night_details(config, next_game_night, user_timezone, shown_in, Utc::now())
+}
+
+// The night's next session in its own timezone, as of `now`
+fn format_next_game_night_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<String, ScheduleError> {
+    let next_game_night = get_next_game_night_at(config, now)?;
+    Ok(format_next_game_night_details(config, next_game_night, None, None, now))
+}
+
+// Everything below works from the same `now`, so the date and the countdown
+// can't disagree with each other
+fn format_next_game_night_details(
+    config: &GameNightConfig,
+    next_game_night: DateTime<Utc>,
+    user_timezone: Option<Tz>,
+    shown_in: Option<Tz>,
+    now: DateTime<Utc>,
+) -> String {
+    let shown_in = shown_in.unwrap_or(config.timezone);
+    let duration = next_game_night - now;
+
+    // A live session started in the past, so there's nothing to count down
+    let countdown = if duration < chrono::Duration::zero() {
+        tr(config.locale, Text::LiveNow).to_string()
     } else {
-        7 - from_num + to_num
+        format_duration(duration, config.locale)
+    };
+
+    // Same as the embed, only worth a line when it reads differently
+    let mut timezones = match user_timezone.filter(|timezone| *timezone != shown_in) {
+        Some(timezone) => fill(
+            tr(config.locale, Text::YourTime),
+            &[
+                format_in_timezone(next_game_night, timezone, config, LocalFormat::DateWithoutYear),
+                format_in_timezone(next_game_night, timezone, config, LocalFormat::Time),
+                timezone.to_string(),
+            ],
+        ),
+        None => String::new(),
+    };
+    if !config.display_timezones.is_empty() {
+        timezones.push_str(&fill(
+            tr(config.locale, Text::OtherTimezones),
+            &[format_multi_tz(next_game_night, &config.display_timezones)],
+        ));
     }
+
+    let details = fill(
+        tr(config.locale, Text::NextGameDetails),
+        &[
+            format_in_timezone(next_game_night, shown_in, config, LocalFormat::Date),
+            format_in_timezone(next_game_night, shown_in, config, LocalFormat::Time),
+            timezone_label(shown_in, next_game_night),
+            format_session_length_for(config, next_game_night),
+            countdown,
+            get_next_game_suggestion(local_date(config, next_game_night)).to_string(),
+            timezones,
+        ],
+    );
+    with_emoji(config, &details)
 }
 
-// Format for !nextgame - detailed countdown information
-pub fn format_next_game_night(config: &GameNightConfig) -> String {
-    let next_game_night = get_next_game_night(config);
-    let local_time = config.timezone.from_utc_datetime(&next_game_night.naive_utc());
-    let duration = time_until_game_night(config);
-    
-    let total_seconds = duration.num_seconds();
-    let days = duration.num_days();
-    let hours = duration.num_hours() % 24;
-    let minutes = duration.num_minutes() % 60;
-    let seconds = total_seconds % 60;
-    
-    // Get what game to suggest
-    let game_suggestion = get_next_game_suggestion(days as usize);
-    
+// A timezone's name with the abbreviation in effect at `datetime`, e.g.
+// "US/Eastern (EDT)" in summer and "US/Eastern (EST)" in winter. Zones
+// without a real abbreviation only have a numeric one like "+03", so those
+// keep just the name.
+pub fn timezone_label(timezone: Tz, datetime: DateTime<Utc>) -> String {
+    let offset = timezone.offset_from_utc_datetime(&datetime.naive_utc());
+    let abbreviation = offset.abbreviation();
+    if abbreviation.starts_with(['+', '-']) || abbreviation == timezone.name() {
+        timezone.name().to_string()
+    } else {
+        format!("{} ({})", timezone.name(), abbreviation)
+    }
+}
+
+// One line per timezone with the instant's local weekday and time there,
+// lined up as a table for a code block
+pub fn format_multi_tz(datetime: DateTime<Utc>, zones: &[Tz]) -> String {
+    let width = zones.iter().map(|zone| zone.name().len()).max().unwrap_or(0);
+    zones
+        .iter()
+        .map(|zone| {
+            let local_time = zone.from_utc_datetime(&datetime.naive_utc());
+            format!("{:<width$}  {}", zone.name(), local_time.format("%a %b %d, %H:%M"), width = width)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Next game night for dashboards and other bots, served by !nextgamejson
+// and the health server's /next
+#[cfg(any(feature = "prefix-commands", feature = "health-server"))]
+#[derive(Serialize)]
+pub struct NextGameNight {
+    pub start_utc: DateTime<Utc>,
+    // RFC 3339 with the night's own UTC offset
+    pub start_local: String,
+    pub timezone: String,
+    pub duration_hours: f64,
+    // Negative while the session is live
+    pub seconds_until: i64,
+}
+
+#[cfg(any(feature = "prefix-commands", feature = "health-server"))]
+pub fn next_game_night_json(config: &GameNightConfig) -> Result<serde_json::Value, ScheduleError> {
+    next_game_night_json_at(config, Utc::now())
+}
+
+#[cfg(any(feature = "prefix-commands", feature = "health-server"))]
+fn next_game_night_json_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<serde_json::Value, ScheduleError> {
+    let start = get_next_game_night_at(config, now)?;
+    let next = NextGameNight {
+        start_utc: start,
+        start_local: config.timezone.from_utc_datetime(&start.naive_utc()).to_rfc3339(),
+        timezone: config.timezone.name().to_string(),
+        duration_hours: (config.session_end(start)? - start).num_minutes() as f64 / 60.0,
+        seconds_until: (start - now).num_seconds(),
+    };
+    Ok(serde_json::to_value(next).expect("NextGameNight serializes to JSON"))
+}
+
+// Days, hours, minutes and seconds in a duration, as the countdowns show it
+pub fn format_duration(duration: chrono::Duration, locale: Locale) -> String {
+    fill(
+        tr(locale, Text::Countdown),
+        &[
+            duration.num_days().to_string(),
+            (duration.num_hours() % 24).to_string(),
+            (duration.num_minutes() % 60).to_string(),
+            (duration.num_seconds() % 60).to_string(),
+        ],
+    )
+}
+
+// Hours and minutes of a duration under a day, e.g. "1 hour 0 minutes"
+pub fn format_hours_minutes(duration: chrono::Duration, locale: Locale) -> String {
     format!(
-        "📅 **Next Game Night Details**\n\
-        ━━━━━━━━━━━━━━━━━━━━━\n\
-        🗓️ **Date:** {}\n\
-        🕐 **Start Time:** {} {}\n\
-        ⏱️ **Duration:** {} hours\n\
-        \n\
-        ⏳ **Countdown:**\n\
-        ```\n\
-        {} days, {} hours, {} minutes, {} seconds\n\
-        ```\n\
-        \n\
-        🎮 **Planned Game:** {}\n\
-        \n\
-        💡 **Pro tip:** Set a reminder so you don't miss it!",
-        local_time.format("%A, %B %d, %Y"),
-        local_time.format("%I:%M %p"),
-        config.timezone,
-        config.duration_hours,
-        days,
-        hours,
-        minutes,
-        seconds,
-        game_suggestion
+        "{} {}",
+        plural(locale, duration.num_hours(), Text::Hour, Text::Hours),
+        plural(locale, duration.num_minutes() % 60, Text::Minute, Text::Minutes)
     )
 }
 
-// Format for !gamenight - quick status check
-pub fn format_game_night_status(config: &GameNightConfig) -> String {
-    if is_game_night_now(config) {
-        let now = Utc::now();
-        let local_now = config.timezone.from_utc_datetime(&now.naive_utc());
-        let end_time = config.start_time + chrono::Duration::hours(config.duration_hours as i64);
-        let time_remaining = end_time - local_now.time();
-        
-        let hours_left = time_remaining.num_hours();
-        let minutes_left = time_remaining.num_minutes() % 60;
-        
-        format!(
-            "🔴 **GAME NIGHT IS LIVE NOW!** 🔴\n\
-            ━━━━━━━━━━━━━━━━━━━━━\n\
-            🎮 We're currently playing!\n\
-            ⏰ Time remaining: {} hours {} minutes\n\
-            🔗 Hop in the voice channel!\n\
-            \n\
-            Use `!suggest` to see what we're playing!",
-            hours_left,
-            minutes_left
+// Format for !until - countdown to a one-off date (and optional HH:MM time,
+// midnight otherwise) in the night's timezone
+#[cfg(feature = "prefix-commands")]
+pub fn format_until(config: &GameNightConfig, date: &str, time: Option<&str>) -> Result<String, String> {
+    format_until_at(config, date, time, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_until_at(
+    config: &GameNightConfig,
+    date: &str,
+    time: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let target = parse_local_datetime(config, date, time)?;
+    if target <= now {
+        return Err(tr(config.locale, Text::UntilPast).to_string());
+    }
+
+    Ok(fill(
+        tr(config.locale, Text::UntilCountdown),
+        &[
+            format_local(target, config, LocalFormat::Date),
+            format_local(target, config, LocalFormat::Time),
+            config.timezone.to_string(),
+            format_duration(target - now, config.locale),
+        ],
+    ))
+}
+
+// Embed for !nextgame, with the same details as format_next_game_night. Takes
+// the night's next start as already worked out by the caller. The start time
+// also shows up in the caller's own timezone when they set one. `shown_in` is
+// a timezone asked for with !nextgame <timezone>, which the date and start
+// time get written in instead of the night's own.
+pub fn next_game_night_embed(
+    config: &GameNightConfig,
+    next_game_night: DateTime<Utc>,
+    user_timezone: Option<Tz>,
+    shown_in: Option<Tz>,
+) -> CreateEmbed {
+    next_game_night_embed_at(config, next_game_night, user_timezone, shown_in, Utc::now())
+}
+
+fn next_game_night_embed_at(
+    config: &GameNightConfig,
+    next_game_night: DateTime<Utc>,
+    user_timezone: Option<Tz>,
+    shown_in: Option<Tz>,
+    now: DateTime<Utc>,
+) -> CreateEmbed {
+    let shown_in = shown_in.unwrap_or(config.timezone);
+    let duration = next_game_night - now;
+    let live = duration < chrono::Duration::zero();
+    let locale = config.locale;
+    let countdown = if live { with_emoji(config, tr(locale, Text::LiveNow)) } else { format_duration(duration, locale) };
+
+    let mut embed = CreateEmbed::new()
+        .title(with_emoji(config, tr(locale, Text::DetailsTitle)))
+        .colour(if live { config.embed_color_live } else { config.embed_color_upcoming })
+        .field(tr(locale, Text::FieldDate), format_in_timezone(next_game_night, shown_in, config, LocalFormat::Date), false)
+        .field(
+            tr(locale, Text::FieldStartTime),
+            format!(
+                "{} {}",
+                format_in_timezone(next_game_night, shown_in, config, LocalFormat::Time),
+                timezone_label(shown_in, next_game_night)
+            ),
+            true,
+        )
+        .field(tr(locale, Text::FieldDuration), format_session_length_for(config, next_game_night), true);
+    // Only worth a field when it reads differently from the time shown above
+    if let Some(timezone) = user_timezone.filter(|timezone| *timezone != shown_in) {
+        embed = embed.field(
+            tr(locale, Text::FieldYourTime),
+            format!(
+                "{}, {} {}",
+                format_in_timezone(next_game_night, timezone, config, LocalFormat::DateWithoutYear),
+                format_in_timezone(next_game_night, timezone, config, LocalFormat::Time),
+                timezone
+            ),
+            false,
+        );
+    }
+    if !config.display_timezones.is_empty() {
+        embed = embed.field(
+            tr(locale, Text::FieldOtherTimezones),
+            format!("```\n{}\n```", format_multi_tz(next_game_night, &config.display_timezones)),
+            false,
+        );
+    }
+    embed
+        .field(tr(locale, Text::FieldCountdown), countdown, false)
+        .field(with_emoji(config, tr(locale, Text::FieldPlannedGame)), get_next_game_suggestion(local_date(config, next_game_night)), false)
+        .footer(CreateEmbedFooter::new(tr(locale, Text::ProTip)))
+}
+
+// Embed for one night in !gamenight
+fn game_night_status_embed_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<CreateEmbed, ScheduleError> {
+    let locale = config.locale;
+    if let Some(time_remaining) = time_remaining_in_session(config, now) {
+        return Ok(CreateEmbed::new()
+            .title(with_emoji(config, tr(locale, Text::LiveTitle)))
+            .colour(config.embed_color_live)
+            .description(with_emoji(config, tr(locale, Text::LiveDescription)))
+            .field(
+                tr(locale, Text::FieldTimeRemaining),
+                format_hours_minutes(time_remaining, locale),
+                true,
+            )
+            .field(tr(locale, Text::FieldEndsAt), format_local(now + time_remaining, config, LocalFormat::Time), true));
+    }
+
+    let next_game_night = get_next_game_night_at(config, now)?;
+    let over_today = session_over_today_at(config, now).is_some()
+        && local_date(config, next_game_night) != local_date(config, now);
+    let title = if over_today { Text::OverTitle } else { Text::NextTitle };
+    Ok(CreateEmbed::new()
+        .title(with_emoji(config, tr(locale, title)))
+        .colour(config.embed_color_upcoming)
+        .field(tr(locale, Text::FieldDate), format_local(next_game_night, config, LocalFormat::DateWithoutYear), true)
+        .field(
+            tr(locale, Text::FieldStartTime),
+            format!("{} {}", format_local(next_game_night, config, LocalFormat::Time), config.timezone),
+            true,
         )
+        .field(tr(locale, Text::FieldDuration), format_session_length_for(config, next_game_night), true)
+        .field(tr(locale, Text::FieldCountdown), format_duration(next_game_night - now, locale), false))
+}
+
+// Format for !gamenight - quick status check
+#[cfg(feature = "prefix-commands")]
+pub fn format_game_night_status(config: &GameNightConfig) -> Result<String, ScheduleError> {
+    format_game_night_status_at(config, Utc::now())
+}
+
+// Posted when a session goes live, with `going` the number of "going" RSVPs.
+// Short of min_players it asks for more players instead.
+pub fn format_live_announcement(config: &GameNightConfig, going: usize) -> Result<String, ScheduleError> {
+    format_live_announcement_at(config, going, Utc::now())
+}
+
+fn format_live_announcement_at(
+    config: &GameNightConfig,
+    going: usize,
+    now: DateTime<Utc>,
+) -> Result<String, ScheduleError> {
+    match config.min_players {
+        Some(min_players) if going < min_players => {
+            let locale = config.locale;
+            let needed = plural(locale, (min_players - going) as i64, Text::MorePlayer, Text::MorePlayers);
+            let nudge = fill(tr(locale, Text::NeedMorePlayers), &[needed, going.to_string(), min_players.to_string()]);
+            Ok(with_role_ping(config, nudge))
+        }
+        _ => format_game_night_status_at(config, now),
+    }
+}
+
+fn format_game_night_status_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<String, ScheduleError> {
+    Ok(with_emoji(config, &game_night_status_text_at(config, now)?))
+}
+
+fn game_night_status_text_at(config: &GameNightConfig, now: DateTime<Utc>) -> Result<String, ScheduleError> {
+    let locale = config.locale;
+    if let Some(time_remaining) = time_remaining_in_session(config, now) {
+        let status = fill(tr(locale, Text::StatusLive), &[format_hours_minutes(time_remaining, locale)]);
+        Ok(with_role_ping(config, status))
     } else {
         // Simple status for when it's not game night
-        let next_game_night = get_next_game_night(config);
+        let next_game_night = get_next_game_night_at(config, now)?;
         let local_time = config.timezone.from_utc_datetime(&next_game_night.naive_utc());
-        let duration = time_until_game_night(config);
+        let duration = time_until_game_night_at(config, now)?;
         
         let days = duration.num_days();
         let hours = duration.num_hours() % 24;
+        let days_away = (local_date(config, next_game_night) - local_date(config, now)).num_days();
+        let time = format_local(next_game_night, config, LocalFormat::Time);
+        let timezone = config.timezone.to_string();
         
-        if days == 0 && hours < 6 {
-            format!(
-                "⏰ **Game Night Starting Soon!**\n\
-                🎮 Tonight at {} {}\n\
-                ⏳ Only {} hours {} minutes away!\n\
-                🔔 Get ready to game!",
-                local_time.format("%I:%M %p"),
-                config.timezone,
-                hours,
-                duration.num_minutes() % 60
+        Ok(if days_away > 0 && session_over_today_at(config, now).is_some() {
+            let when = if days_away == 1 {
+                tr(locale, Text::Tomorrow).to_string()
+            } else {
+                fill(tr(locale, Text::InDays), &[days_away.to_string()])
+            };
+            fill(
+                tr(locale, Text::StatusOver),
+                &[format_local(next_game_night, config, LocalFormat::DateWithoutYear), when, time, timezone],
             )
+        } else if days == 0 && hours < 6 {
+            fill(tr(locale, Text::StatusSoon), &[time, timezone, format_hours_minutes(duration, locale)])
         } else if days == 0 {
-            format!(
-                "📅 **Game Night is Today!**\n\
-                🕐 Starting at {} {}\n\
-                ⏳ In {} hours {} minutes",
-                local_time.format("%I:%M %p"),
-                config.timezone,
-                hours,
-                duration.num_minutes() % 60
-            )
+            fill(tr(locale, Text::StatusToday), &[time, timezone, format_hours_minutes(duration, locale)])
         } else if days == 1 {
-            format!(
-                "📅 **Game Night is Tomorrow!**\n\
-                🕐 {} at {} {}",
-                local_time.format("%A"),
-                local_time.format("%I:%M %p"),
-                config.timezone
+            fill(
+                tr(locale, Text::StatusTomorrow),
+                &[weekday_name(locale, local_time.weekday()).to_string(), time, timezone],
             )
         } else {
-            format!(
-                "📅 **Next Game Night:**\n\
-                🗓️ {} (in {} days)\n\
-                🕐 {} {}",
-                local_time.format("%A, %B %d"),
-                days,
-                local_time.format("%I:%M %p"),
-                config.timezone
+            fill(
+                tr(locale, Text::StatusLater),
+                &[format_local(next_game_night, config, LocalFormat::DateWithoutYear), days.to_string(), time, timezone],
             )
-        }
+        })
     }
 }
 
-// Game suggestions based on the date
-pub fn get_game_suggestion() -> &'static str {
-    let suggestions = vec![
-        "🎯 **Tonight's Game Suggestions:**\n• Valorant\n• CS2\n• Overwatch 2",
-        "🎯 **Tonight's Game Suggestions:**\n• League of Legends\n• Dota 2\n• Heroes of the Storm",
-        "🎯 **Tonight's Game Suggestions:**\n• Minecraft\n• Terraria\n• Valheim",
-        "🎯 **Tonight's Game Suggestions:**\n• Among Us\n• Fall Guys\n• Jackbox Party Pack",
-        "🎯 **Tonight's Game Suggestions:**\n• Rocket League\n• FIFA\n• NBA 2K",
-    ];
-    
-    let now = Utc::now();
-    let index = (now.timestamp() as usize / 86400) % suggestions.len();
-    suggestions[index]
+// Output of --dry-run and !preview: what !gamenight, !nextgame and !suggest
+// would say at `now`, for checking the formatting at any moment
+pub fn format_preview_at(schedule: &GameNightSchedule, now: DateTime<Utc>) -> String {
+    let mut sections = Vec::new();
+    // A night that can't be placed shows why instead
+    let or_why = |formatted: Result<String, ScheduleError>| formatted.unwrap_or_else(|why| format!("❌ {}", why));
+    for night in &schedule.nights {
+        sections.push(format!("--- !gamenight ---\n{}", or_why(format_game_night_status_at(night, now))));
+        sections.push(format!("--- !nextgame ---\n{}", or_why(format_next_game_night_at(night, now))));
+    }
+    sections.push(format!("--- !suggest ---\n{}", get_game_suggestion(&[], None)));
+    sections.join("\n\n")
 }
 
-fn get_next_game_suggestion(days_away: usize) -> &'static str {
-    let games = vec![
-        "@Amaterasu is cheating in wordle"
+// What reminder templates can fill in
+pub const REMINDER_PLACEHOLDERS: [&str; 7] = ["countdown", "day", "date", "time", "timezone", "game", "role"];
+
+pub const DEFAULT_REMINDER_TEMPLATE: &str = "⏰ **Game night starts in {countdown}!**\n\
+    🕐 {day} at {time} {timezone}\n\
+    🎮 Get ready to game!";
+
+// Reminder posted to the reminder channel ahead of game night. The notify
+// role goes wherever the template puts {role}, or in front without one.
+pub fn format_reminder(config: &GameNightConfig, start: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    // Round up so a reminder a few seconds late still says "15 minutes"
+    let minutes = ((start - now).num_seconds() + 59) / 60;
+    let role = config.notify_role_id.map(|role_id| role_id.mention().to_string()).unwrap_or_default();
+    let values = [
+        ("countdown", format_minutes(minutes.max(0) as u32, config.locale)),
+        ("day", weekday_name(config.locale, local_date(config, start).weekday()).to_string()),
+        ("date", format_local(start, config, LocalFormat::DateWithoutYear)),
+        ("time", format_local(start, config, LocalFormat::Time)),
+        ("timezone", config.timezone.to_string()),
+        ("game", get_next_game_suggestion(local_date(config, start)).to_string()),
+        ("role", role),
     ];
-    
-    games[days_away % games.len()]
-}
 
-// Custom game night configurations for special events
-pub fn get_special_game_night(date: DateTime<Utc>) -> Option<String> {
-    let local_date = chrono_tz::US::Eastern.from_utc_datetime(&date.naive_utc());
-    
-    match (local_date.month(), local_date.day()) {
-        (12, 24) => Some("🎄 **Christmas Eve Game Night!** 🎅".to_string()),
-        (12, 31) => Some("🎊 **New Year's Eve Game Night!** 🥳".to_string()),
-        (10, 31) => Some("🎃 **Halloween Game Night!** 👻".to_string()),
-        (7, 4) => Some("🎆 **Independence Day Game Night!** 🇺🇸".to_string()),
-        _ => None,
+    let reminder = template::render(&config.reminder_template, &values);
+    if config.reminder_template.contains("{role}") {
+        reminder
+    } else {
+        with_role_ping(config, reminder)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Posted `last_call_minutes` before a live session ends, rounded up like the
+// reminders
+pub fn format_last_call(config: &GameNightConfig, end: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = ((end - now).num_seconds() + 59) / 60;
+    fill(tr(config.locale, Text::LastCall), &[format_minutes(minutes.max(0) as u32, config.locale)])
+}
 
-    #[test]
-    fn test_days_until_weekday() {
-        assert_eq!(days_until_weekday(Weekday::Mon, Weekday::Fri), 4);
-        assert_eq!(days_until_weekday(Weekday::Fri, Weekday::Mon), 3);
-        assert_eq!(days_until_weekday(Weekday::Wed, Weekday::Wed), 0);
-    }
+// Format for !gamenight with several configured nights - one status per night.
+// The command replies with schedule_status_embeds, this is the plain text
+// version for channels the bot can't post embeds in.
+#[cfg(feature = "prefix-commands")]
+pub fn format_schedule_status(schedule: &GameNightSchedule) -> Result<String, ScheduleError> {
+    let statuses = schedule.nights
+        .iter()
+        .map(format_game_night_status)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(statuses.join("\n\n"))
+}
+
+// Embeds for !gamenight, one per night, along with the roles to ping for
+// the nights that are live right now
+pub fn schedule_status_embeds(schedule: &GameNightSchedule) -> Result<(Vec<CreateEmbed>, Vec<RoleId>), ScheduleError> {
+    let now = Utc::now();
+    let embeds = schedule.nights
+        .iter()
+        .map(|night| game_night_status_embed_at(night, now))
+        .collect::<Result<_, _>>()?;
+    let mut pings: Vec<RoleId> = schedule.nights
+        .iter()
+        .filter(|night| is_game_night_at(night, now))
+        .filter_map(|night| night.notify_role_id)
+        .collect();
+    pings.sort();
+    pings.dedup();
+    Ok((embeds, pings))
+}
+
+// Which days a night falls on, e.g. "Friday, every week" or "Friday, 1st of the month"
+fn format_recurrence(config: &GameNightConfig) -> String {
+    let locale = config.locale;
+    let day = weekday_name(locale, config.day_of_week).to_string();
+    match config.recurrence {
+        Recurrence::Weekly => fill(tr(locale, Text::EveryWeek), &[day]),
+        Recurrence::Biweekly { .. } => fill(tr(locale, Text::EveryOtherWeek), &[day]),
+        Recurrence::EvenWeeks => fill(tr(locale, Text::EvenWeeks), &[day]),
+        Recurrence::OddWeeks => fill(tr(locale, Text::OddWeeks), &[day]),
+        Recurrence::MonthlyNth { week, weekday } => fill(
+            tr(locale, Text::NthOfMonth),
+            &[weekday_name(locale, weekday).to_string(), ordinal(locale, week.min(5))],
+        ),
+    }
+}
+
+// Settings !gamenightconfig shows for a night, as embed field names and values
+fn config_fields(config: &GameNightConfig) -> Vec<(&'static str, String)> {
+    let text = |text| tr(config.locale, text);
+    vec![
+        (text(Text::ConfigDay), format_recurrence(config)),
+        (text(Text::FieldStartTime), config.start_time.format(config.time_format.pattern()).to_string()),
+        (text(Text::FieldDuration), format_session_length(config)),
+        (text(Text::ConfigTimezone), config.timezone.to_string()),
+        (
+            text(Text::ConfigChannel),
+            config.reminder_channel.map_or(text(Text::ConfigChannelNotSet).to_string(), |id| id.mention().to_string()),
+        ),
+        (
+            text(Text::ConfigNotifyRole),
+            config.notify_role_id.map_or(text(Text::ConfigNone).to_string(), |id| id.mention().to_string()),
+        ),
+        (
+            text(Text::ConfigMinPlayers),
+            config.min_players.map_or(text(Text::ConfigNone).to_string(), |min_players| min_players.to_string()),
+        ),
+        (
+            text(Text::ConfigAnnouncements),
+            text(if config.announcements_enabled { Text::ConfigOn } else { Text::ConfigPaused }).to_string(),
+        ),
+    ]
+}
+
+// Embeds for !gamenightconfig, one per night, with the settings as they're
+// in effect right now for the guild
+pub fn config_embeds(schedule: &GameNightSchedule) -> Vec<CreateEmbed> {
+    schedule.nights
+        .iter()
+        .enumerate()
+        .map(|(index, night)| {
+            let title = if schedule.nights.len() == 1 {
+                tr(night.locale, Text::ConfigTitle).to_string()
+            } else {
+                let of = [(index + 1).to_string(), schedule.nights.len().to_string()];
+                fill(tr(night.locale, Text::ConfigTitleOf), &of)
+            };
+            config_fields(night)
+                .into_iter()
+                .fold(CreateEmbed::new().title(title).colour(night.embed_color_upcoming), |embed, (name, value)| {
+                    embed.field(name, value, true)
+                })
+        })
+        .collect()
+}
+
+// Format for !debug - everything the bot worked out about each night, raw,
+// for chasing down timezone and DST reports
+#[cfg(feature = "prefix-commands")]
+pub fn format_debug(schedule: &GameNightSchedule) -> String {
+    let now = Utc::now();
+    let nights: Vec<String> = schedule.nights
+        .iter()
+        .enumerate()
+        .map(|(index, night)| format!("Night {} of {}\n{}", index + 1, schedule.nights.len(), format_debug_at(night, now)))
+        .collect();
+    format!("🛠️ **Resolved schedule**\n```text\n{}\n```", nights.join("\n\n"))
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_debug_at(config: &GameNightConfig, now: DateTime<Utc>) -> String {
+    let next = match get_next_game_night_at(config, now) {
+        Ok(next_game_night) => format!(
+            "next (UTC): {}\n\
+            next (local): {}\n\
+            time_until_game_night: {} s",
+            next_game_night.to_rfc3339(),
+            config.timezone.from_utc_datetime(&next_game_night.naive_utc()).format("%Y-%m-%d %H:%M:%S %Z (%:z)"),
+            (next_game_night - now).num_seconds()
+        ),
+        Err(why) => format!("next: {}", why),
+    };
+    format!(
+        "day_of_week: {}\n\
+        start_time: {}\n\
+        length: {:?}\n\
+        end_time: {}\n\
+        timezone: {}\n\
+        recurrence: {:?}\n\
+        override_date: {}\n\
+        skipped_dates: {}\n\
+        now (UTC): {}\n\
+        {}\n\
+        live: {}",
+        config.day_of_week,
+        config.start_time.format("%H:%M"),
+        config.length,
+        match config.length.end_time(config.start_time) {
+            (end_time, true) => format!("{} (next day)", end_time.format("%H:%M")),
+            (end_time, false) => end_time.format("%H:%M").to_string(),
+        },
+        config.timezone,
+        config.recurrence,
+        config.override_date.map_or("none".to_string(), |start| start.to_rfc3339()),
+        config.skipped_dates.len(),
+        now.to_rfc3339(),
+        next,
+        is_game_night_at(config, now)
+    )
+}
+
+// A game in a guild's library. Games with a higher weight come up more often
+// in suggestions.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(from = "LibraryEntry")]
+pub struct LibraryGame {
+    pub name: String,
+    pub weight: u32,
+}
+
+// Weight of a game added without one
+pub const DEFAULT_GAME_WEIGHT: u32 = 1;
+// Highest weight a game can be given
+#[cfg(feature = "prefix-commands")]
+pub const MAX_GAME_WEIGHT: u32 = 10;
+
+// How a library game is saved. Libraries saved before games had weights
+// are plain lists of names.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LibraryEntry {
+    Name(String),
+    Weighted { name: String, weight: u32 },
+}
+
+impl From<LibraryEntry> for LibraryGame {
+    fn from(entry: LibraryEntry) -> Self {
+        match entry {
+            LibraryEntry::Name(name) => LibraryGame { name, weight: DEFAULT_GAME_WEIGHT },
+            LibraryEntry::Weighted { name, weight } => LibraryGame { name, weight },
+        }
+    }
+}
+
+impl LibraryGame {
+    pub fn new(name: &str) -> Self {
+        LibraryGame {
+            name: name.to_string(),
+            weight: DEFAULT_GAME_WEIGHT,
+        }
+    }
+}
+
+// Format for !suggest - up to three games picked at random, favouring the
+// ones with a higher weight. Draws from the guild's own game library when it
+// has one it can pick from, otherwise from the built-in games. The same seed
+// always gives the same picks; without one they're different every time.
+pub fn get_game_suggestion(library: &[LibraryGame], seed: Option<u64>) -> String {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut picks = pick_weighted(library.to_vec(), 3, &mut rng);
+    if picks.is_empty() {
+        let pool = GAME_POOL.iter().map(|game| LibraryGame::new(game)).collect();
+        picks = pick_weighted(pool, 3, &mut rng);
+    }
+
+    let picks: Vec<String> = picks
+        .iter()
+        .map(|game| format!("• {}", game.name))
+        .collect();
+    format!("🎯 **Tonight's Game Suggestions:**\n{}", picks.join("\n"))
+}
+
+// Up to `count` different games, each pick weighted by what's left. Games
+// with no weight are never picked.
+fn pick_weighted(mut pool: Vec<LibraryGame>, count: usize, rng: &mut impl Rng) -> Vec<LibraryGame> {
+    pool.retain(|game| game.weight > 0);
+    let mut picks = Vec::new();
+    while picks.len() < count && !pool.is_empty() {
+        let Ok(weights) = WeightedIndex::new(pool.iter().map(|game| game.weight)) else {
+            break;
+        };
+        picks.push(pool.remove(weights.sample(rng)));
+    }
+    picks
+}
+
+// Game played at the session on `date`, for its history: one from the
+// guild's library picked by weight, the same one every time for that date,
+// or the built-in game planned for it when the library has nothing to pick
+pub fn planned_game(library: &[LibraryGame], date: NaiveDate) -> String {
+    let mut rng = StdRng::seed_from_u64(date.num_days_from_ce() as u64);
+    match pick_weighted(library.to_vec(), 1, &mut rng).pop() {
+        Some(game) => game.name,
+        None => get_next_game_suggestion(date).to_string(),
+    }
+}
+
+// Games up for a vote: the guild's library when it has one, otherwise five
+// built-in games picked by date. Never more than a poll can hold.
+#[cfg(feature = "prefix-commands")]
+pub fn vote_candidates(library: &[LibraryGame], date: NaiveDate) -> Vec<String> {
+    if !library.is_empty() {
+        return library.iter().take(10).map(|game| game.name.clone()).collect();
+    }
+    let start = date.num_days_from_ce().rem_euclid(GAME_POOL.len() as i32) as usize;
+    (0..5)
+        .map(|offset| GAME_POOL[(start + offset) % GAME_POOL.len()].to_string())
+        .collect()
+}
+
+// Add a game to a guild's library. Names are compared ignoring case, so
+// returns false when the game is already in there.
+#[cfg(feature = "prefix-commands")]
+pub fn add_to_library(library: &mut Vec<LibraryGame>, name: &str) -> bool {
+    if library.iter().any(|game| game.name.eq_ignore_ascii_case(name)) {
+        return false;
+    }
+    library.push(LibraryGame::new(name));
+    true
+}
+
+// Remove a game from a guild's library, ignoring case. Returns false when
+// there was no such game.
+#[cfg(feature = "prefix-commands")]
+pub fn remove_from_library(library: &mut Vec<LibraryGame>, name: &str) -> bool {
+    let before = library.len();
+    library.retain(|game| !game.name.eq_ignore_ascii_case(name));
+    library.len() != before
+}
+
+// Change how often a library game gets suggested, ignoring case. Returns
+// false when there's no such game.
+#[cfg(feature = "prefix-commands")]
+pub fn set_game_weight(library: &mut [LibraryGame], name: &str, weight: u32) -> bool {
+    match library.iter_mut().find(|game| game.name.eq_ignore_ascii_case(name)) {
+        Some(game) => {
+            game.weight = weight;
+            true
+        }
+        None => false,
+    }
+}
+
+// Every game the suggestions rotate through
+const GAME_POOL: [&str; 15] = [
+    "Valorant", "CS2", "Overwatch 2",
+    "League of Legends", "Dota 2", "Heroes of the Storm",
+    "Minecraft", "Terraria", "Valheim",
+    "Among Us", "Fall Guys", "Jackbox Party Pack",
+    "Rocket League", "FIFA", "NBA 2K",
+];
+
+// Planned game for the night on `date`, so the same night always gets the
+// same game. With 15 games a weekly night steps through them one at a time.
+pub fn get_next_game_suggestion(date: NaiveDate) -> &'static str {
+    let index = date.num_days_from_ce().rem_euclid(GAME_POOL.len() as i32) as usize;
+    GAME_POOL[index]
+}
+
+// Themed nights among the sessions starting within the next `weeks` weeks,
+// soonest first. Each comes with the index of the night it belongs to.
+#[cfg(feature = "prefix-commands")]
+pub fn upcoming_special_nights(
+    schedule: &GameNightSchedule,
+    weeks: u32,
+    now: DateTime<Utc>,
+) -> Vec<(usize, DateTime<Utc>, String)> {
+    let until = now + chrono::Duration::weeks(weeks as i64);
+    let mut specials = Vec::new();
+    for (index, night) in schedule.nights.iter().enumerate() {
+        for start in upcoming_game_nights(night, now).take_while(|start| *start < until) {
+            if let Some(theme) = get_special_game_night(night, start) {
+                specials.push((index, start, theme));
+            }
+        }
+    }
+    specials.sort_by_key(|(_, start, _)| *start);
+    specials
+}
+
+// The next `count` sessions across all of the schedule's nights, soonest
+// first, each with the index of the night it belongs to
+#[cfg(feature = "prefix-commands")]
+pub fn upcoming_sessions(schedule: &GameNightSchedule, count: usize, now: DateTime<Utc>) -> Vec<(usize, DateTime<Utc>)> {
+    let mut sessions: Vec<(usize, DateTime<Utc>)> = schedule
+        .nights
+        .iter()
+        .enumerate()
+        .flat_map(|(index, night)| upcoming_game_nights(night, now).take(count).map(move |start| (index, start)))
+        .collect();
+    sessions.sort_by_key(|(_, start)| *start);
+    sessions.truncate(count);
+    sessions
+}
+
+// Format for !nextgames - the next few sessions, with the theme of any
+// special ones
+#[cfg(feature = "prefix-commands")]
+pub fn format_next_game_nights(schedule: &GameNightSchedule, count: usize) -> String {
+    format_next_game_nights_at(schedule, count, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_next_game_nights_at(schedule: &GameNightSchedule, count: usize, now: DateTime<Utc>) -> String {
+    let lines: Vec<String> = upcoming_sessions(schedule, count, now)
+        .into_iter()
+        .map(|(index, start)| {
+            let night = &schedule.nights[index];
+            let line = session_line(night, start);
+            match get_special_game_night(night, start) {
+                Some(theme) => format!("{}\n{}", line, theme),
+                None => line,
+            }
+        })
+        .collect();
+    fill(tr(schedule.locale(), Text::NextGamesTitle), &[lines.len().to_string(), lines.join("\n")])
+}
+
+// Format for !lineup - the next `count` sessions with the game planned for
+// each. The game goes by the session's local date alone, so the lineup only
+// changes as sessions pass or the schedule does.
+#[cfg(feature = "prefix-commands")]
+pub fn format_lineup(schedule: &GameNightSchedule, count: usize) -> String {
+    format_lineup_at(schedule, count, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_lineup_at(schedule: &GameNightSchedule, count: usize, now: DateTime<Utc>) -> String {
+    let lines: Vec<String> = upcoming_sessions(schedule, count, now)
+        .into_iter()
+        .map(|(index, start)| {
+            let night = &schedule.nights[index];
+            with_emoji(
+                night,
+                &fill(
+                    tr(night.locale, Text::LineupLine),
+                    &[
+                        format_local(start, night, LocalFormat::DateWithoutYear),
+                        format_local(start, night, LocalFormat::Time),
+                        get_next_game_suggestion(local_date(night, start)).to_string(),
+                    ],
+                ),
+            )
+        })
+        .collect();
+    fill(tr(schedule.locale(), Text::LineupTitle), &[lines.join("\n")])
+}
+
+// Format for !gamenight +N - the session `offset` occurrences after the next
+// one of each night, with its theme and how long until it starts
+#[cfg(feature = "prefix-commands")]
+pub fn format_game_night_ahead(schedule: &GameNightSchedule, offset: usize) -> String {
+    format_game_night_ahead_at(schedule, offset, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_game_night_ahead_at(schedule: &GameNightSchedule, offset: usize, now: DateTime<Utc>) -> String {
+    let sections: Vec<String> = schedule
+        .nights
+        .iter()
+        .filter_map(|night| upcoming_game_nights(night, now).nth(offset).map(|start| (night, start)))
+        .map(|(night, start)| {
+            let countdown = if start <= now {
+                with_emoji(night, tr(night.locale, Text::LiveNow))
+            } else {
+                format_duration(start - now, night.locale)
+            };
+            let section = fill(
+                tr(night.locale, Text::GameNightAhead),
+                &[
+                    offset.to_string(),
+                    format_local(start, night, LocalFormat::Date),
+                    format_local(start, night, LocalFormat::Time),
+                    night.timezone.to_string(),
+                    countdown,
+                ],
+            );
+            let section = with_emoji(night, &section);
+            match get_special_game_night(night, start) {
+                Some(theme) => format!("{}\n{}", section, theme),
+                None => section,
+            }
+        })
+        .collect();
+    sections.join("\n\n")
+}
+
+// Format for !specialnights - themed nights coming up in the next few weeks
+#[cfg(feature = "prefix-commands")]
+pub fn format_special_nights(schedule: &GameNightSchedule, weeks: u32) -> String {
+    format_special_nights_at(schedule, weeks, Utc::now())
+}
+
+#[cfg(feature = "prefix-commands")]
+fn format_special_nights_at(schedule: &GameNightSchedule, weeks: u32, now: DateTime<Utc>) -> String {
+    let specials = upcoming_special_nights(schedule, weeks, now);
+    if specials.is_empty() {
+        return fill(tr(schedule.locale(), Text::NoSpecialNights), &[weeks.to_string()]);
+    }
+
+    let lines: Vec<String> = specials
+        .iter()
+        .map(|(index, start, theme)| {
+            format!("{}\n{}", session_line(&schedule.nights[*index], *start), theme)
+        })
+        .collect();
+    fill(tr(schedule.locale(), Text::SpecialNightsTitle), &[weeks.to_string(), lines.join("\n\n")])
+}
+
+// A session's date, start time and timezone, one line of !nextgames and
+// !specialnights
+#[cfg(feature = "prefix-commands")]
+fn session_line(night: &GameNightConfig, start: DateTime<Utc>) -> String {
+    fill(
+        tr(night.locale, Text::SessionLine),
+        &[
+            format_local(start, night, LocalFormat::Date),
+            format_local(start, night, LocalFormat::Time),
+            night.timezone.to_string(),
+        ],
+    )
+}
+
+// How the night handles `holiday`
+pub fn holiday_policy(config: &GameNightConfig, holiday: Holiday) -> HolidayPolicy {
+    config.holiday_policies.get(&holiday).copied().unwrap_or_default()
+}
+
+// Custom game night configurations for special events. Dates go by the
+// night's own calendar, not by UTC. The guild's own specials come first, then
+// holidays, which only get a theme when their policy says so.
+#[cfg(feature = "prefix-commands")]
+pub fn get_special_game_night(config: &GameNightConfig, date: DateTime<Utc>) -> Option<String> {
+    special_on(config, local_date(config, date))
+}
+
+fn special_on(config: &GameNightConfig, date: NaiveDate) -> Option<String> {
+    if let Some(message) = config.custom_specials.get(&(date.month(), date.day())) {
+        return Some(message.clone());
+    }
+    let holiday = Holiday::on(date)?;
+    match holiday_policy(config, holiday) {
+        HolidayPolicy::Theme => Some(holiday.theme().to_string()),
+        HolidayPolicy::Skip | HolidayPolicy::Ignore => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_upcoming_special_nights() {
+        // Christmas Eve 2027 is a Friday
+        let schedule = GameNightSchedule::default();
+        let now = eastern(2027, 12, 1, 12, 0);
+
+        let specials = upcoming_special_nights(&schedule, 5, now);
+        assert_eq!(specials.len(), 2);
+        assert_eq!(specials[0].1, eastern(2027, 12, 24, 20, 0));
+        assert!(specials[0].2.contains("Christmas Eve"));
+        assert_eq!(specials[1].1, eastern(2027, 12, 31, 20, 0));
+        assert!(specials[1].2.contains("New Year's Eve"));
+
+        // Outside the window
+        assert!(upcoming_special_nights(&schedule, 3, now).is_empty());
+    }
+
+    #[test]
+    fn test_dm_schedule() {
+        let default = GameNightSchedule {
+            nights: vec![GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..GameNightConfig::default() }],
+        };
+        let guild_id = GuildId::new(1);
+        let own = GameNightSchedule {
+            nights: vec![GameNightConfig { day_of_week: Weekday::Sat, ..GameNightConfig::default() }],
+        };
+        let schedules = HashMap::from([(guild_id, own)]);
+
+        assert_eq!(GameNightSchedule::for_guild(&default, &schedules, Some(guild_id)).nights[0].day_of_week, Weekday::Sat);
+        assert_eq!(GameNightSchedule::for_guild(&default, &schedules, Some(GuildId::new(2))).notify_roles(), [RoleId::new(42)]);
+
+        // DMs go by the default schedule and never ping
+        let dm = GameNightSchedule::for_guild(&default, &schedules, None);
+        assert_eq!(dm.nights[0].day_of_week, Weekday::Fri);
+        assert!(dm.notify_roles().is_empty());
+        assert!(!format_game_night_status_at(&dm.nights[0], eastern(2024, 6, 7, 21, 0)).unwrap().contains("<@&"));
+    }
+
+    #[test]
+    fn test_spanish_status() {
+        let config = GameNightConfig { locale: Locale::Spanish, ..GameNightConfig::default() };
+        // Tuesday June 4th, next one is Friday June 7th
+        let status = format_game_night_status_at(&config, eastern(2024, 6, 4, 12, 0)).unwrap();
+        assert_eq!(status, "📅 **Próxima noche de juegos:**\n🗓️ viernes, junio 07 (en 3 días)\n🕐 8:00 PM US/Eastern");
+
+        let live = format_game_night_status_at(&config, eastern(2024, 6, 7, 22, 30)).unwrap();
+        assert!(live.contains("⏰ Tiempo restante: 1 hora 30 minutos"));
+        assert_eq!(format_duration(chrono::Duration::seconds(90061), Locale::Spanish), "1 días, 1 horas, 1 minutos, 1 segundos");
+    }
+
+    #[test]
+    fn test_special_duration() {
+        // New Year's Eve 2027 is a Friday, and runs 6 hours instead of 4
+        let config = GameNightConfig {
+            special_durations: HashMap::from([((12, 31), 6 * 60)]),
+            ..GameNightConfig::default()
+        };
+        let past_normal_end = eastern(2028, 1, 1, 1, 0);
+        assert!(is_game_night_at(&config, past_normal_end));
+        assert!(!is_game_night_at(&config, eastern(2028, 1, 1, 2, 1)));
+        assert!(!is_game_night_at(&GameNightConfig::default(), past_normal_end));
+        assert!(format_next_game_night_at(&config, eastern(2027, 12, 30, 12, 0)).unwrap().contains("**Duration:** 6 hours"));
+
+        // Regular nights keep the usual length
+        assert!(!is_game_night_at(&config, eastern(2027, 12, 25, 1, 0)));
+        assert!(format_next_game_night_at(&config, eastern(2027, 12, 20, 12, 0)).unwrap().contains("**Duration:** 4 hours"));
+
+        // Only special nights get the longer window
+        let config = GameNightConfig {
+            holiday_policies: HashMap::from([(Holiday::NewYearsEve, HolidayPolicy::Ignore)]),
+            ..config
+        };
+        assert!(!is_game_night_at(&config, past_normal_end));
+    }
+
+    #[test]
+    fn test_live_announcement_min_players() {
+        let config = GameNightConfig { min_players: Some(4), ..GameNightConfig::default() };
+        let now = eastern(2024, 6, 7, 20, 0);
+        let status = format_game_night_status_at(&config, now).unwrap();
+        assert_eq!(format_live_announcement_at(&config, 4, now).unwrap(), status);
+        assert_eq!(format_live_announcement_at(&config, 6, now).unwrap(), status);
+
+        assert_eq!(
+            format_live_announcement_at(&config, 3, now).unwrap(),
+            "⏳ Game night needs 1 more player to get going! 3 of 4 going so far, hit **Going** below if you can make it."
+        );
+        let config = GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..config };
+        let nudge = format_live_announcement_at(&config, 0, now).unwrap();
+        assert!(nudge.starts_with("<@&42>\n⏳ Game night needs 4 more players"));
+
+        let config = GameNightConfig { min_players: None, ..config };
+        assert_eq!(format_live_announcement_at(&config, 0, now).unwrap(), format_game_night_status_at(&config, now).unwrap());
+    }
+
+    #[test]
+    fn test_config_fields() {
+        let config = GameNightConfig {
+            reminder_channel: Some(ChannelId::new(10)),
+            recurrence: Recurrence::MonthlyNth { week: 2, weekday: Weekday::Sat },
+            ..GameNightConfig::default()
+        };
+        let fields = config_fields(&config);
+        assert_eq!(fields[0], ("🗓️ Day", "Saturday, 2nd of the month".to_string()));
+        assert_eq!(fields[1].1, "8:00 PM");
+        assert_eq!(fields[2].1, "4 hours");
+        assert_eq!(fields[3].1, "US/Eastern");
+        assert_eq!(fields[4].1, "<#10>");
+        assert_eq!(fields[5].1, "None");
+
+        let fields = config_fields(&GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..GameNightConfig::default() });
+        assert_eq!(fields[0].1, "Friday, every week");
+        assert_eq!(fields[4].1, "Not set, reminders are off");
+        assert_eq!(fields[5].1, "<@&42>");
+        assert_eq!(fields[7], ("⏸️ Announcements", "On".to_string()));
+
+        let fields = config_fields(&GameNightConfig { announcements_enabled: false, ..GameNightConfig::default() });
+        assert_eq!(fields[7].1, "Paused, `!resume` turns them back on");
+
+        let fields = config_fields(&GameNightConfig { locale: Locale::Spanish, ..config });
+        assert_eq!(fields[0], ("🗓️ Día", "sábado, el 2.º del mes".to_string()));
+        assert_eq!(fields[7], ("⏸️ Anuncios", "Activados".to_string()));
+    }
+
+    #[test]
+    fn test_preview() {
+        let preview = format_preview_at(&GameNightSchedule::default(), eastern(2024, 6, 7, 21, 0));
+        assert!(preview.starts_with("--- !gamenight ---\n🔴 **GAME NIGHT IS LIVE NOW!**"));
+        assert!(preview.contains("--- !nextgame ---\n"));
+        assert!(preview.contains("--- !suggest ---\n"));
+    }
+
+    #[test]
+    fn test_custom_emoji() {
+        let config = GameNightConfig {
+            emoji: HashMap::from([(EmojiSlot::Live, "🚨".to_string()), (EmojiSlot::Date, "<:cal:42>".to_string())]),
+            ..GameNightConfig::default()
+        };
+        let live = format_game_night_status_at(&config, eastern(2024, 6, 7, 21, 0)).unwrap();
+        assert!(live.starts_with("🚨 **GAME NIGHT IS LIVE NOW!** 🚨\n"), "{}", live);
+        assert!(live.contains("🎮"));
+        let details = format_next_game_night_at(&config, eastern(2024, 6, 5, 12, 0)).unwrap();
+        assert!(details.starts_with("<:cal:42> **Next Game Night Details**"), "{}", details);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_custom_specials() {
+        let mut config = GameNightConfig::default();
+        config.custom_specials.insert((6, 7), "🎂 **Server Anniversary Game Night!**".to_string());
+        config.custom_specials.insert((12, 24), "🎁 **Secret Santa Game Night!**".to_string());
+        config.holiday_policies.insert(Holiday::ChristmasEve, HolidayPolicy::Ignore);
+
+        assert_eq!(
+            get_special_game_night(&config, eastern(2024, 6, 7, 20, 0)).as_deref(),
+            Some("🎂 **Server Anniversary Game Night!**")
+        );
+        // Ahead of the holiday on the same day, even with its theme off
+        assert!(get_special_game_night(&config, eastern(2027, 12, 24, 20, 0)).unwrap().contains("Secret Santa"));
+        assert_eq!(get_special_game_night(&config, eastern(2024, 6, 14, 20, 0)), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_holiday_skip_policy() {
+        // Christmas Eve 2027 is a Friday, so with Skip it rolls on to New Year's Eve
+        let mut config = GameNightConfig::default();
+        config.holiday_policies.insert(Holiday::ChristmasEve, HolidayPolicy::Skip);
+        let now = eastern(2027, 12, 20, 12, 0);
+        assert_eq!(get_next_game_night_at(&config, now).unwrap(), eastern(2027, 12, 31, 20, 0));
+        assert!(!is_game_night_at(&config, eastern(2027, 12, 24, 21, 0)));
+
+        // Themed by default
+        let config = GameNightConfig::default();
+        assert_eq!(get_next_game_night_at(&config, now).unwrap(), eastern(2027, 12, 24, 20, 0));
+        assert!(get_special_game_night(&config, eastern(2027, 12, 24, 20, 0)).is_some());
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_holiday_ignore_policy() {
+        let mut config = GameNightConfig::default();
+        config.holiday_policies.insert(Holiday::ChristmasEve, HolidayPolicy::Ignore);
+        let christmas_eve = eastern(2027, 12, 24, 20, 0);
+        assert_eq!(get_next_game_night_at(&config, eastern(2027, 12, 20, 12, 0)).unwrap(), christmas_eve);
+        assert_eq!(get_special_game_night(&config, christmas_eve), None);
+    }
+
+    #[test]
+    fn test_holiday_keys() {
+        for holiday in Holiday::ALL {
+            assert_eq!(Holiday::from_key(holiday.key()), Some(holiday));
+        }
+        assert_eq!(Holiday::from_key(" Halloween "), Some(Holiday::Halloween));
+        assert_eq!(Holiday::from_key("easter"), None);
+        assert_eq!(Holiday::on(NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()), Some(Holiday::IndependenceDay));
+        assert_eq!(Holiday::on_month_day(10, 31), Some(Holiday::Halloween));
+        assert_eq!(Holiday::on_month_day(3, 15), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_special_nights_use_config_timezone() {
+        let auckland = GameNightConfig {
+            timezone: chrono_tz::Pacific::Auckland, // UTC+13 in December
+            ..GameNightConfig::default()
+        };
+        let utc_config = GameNightConfig {
+            timezone: chrono_tz::UTC,
+            ..GameNightConfig::default()
+        };
+
+        // Christmas Eve in UTC is already Christmas Day in Auckland
+        let christmas_eve_utc = utc(2027, 12, 24, 12, 0);
+        assert!(get_special_game_night(&utc_config, christmas_eve_utc).unwrap().contains("Christmas Eve"));
+        assert_eq!(get_special_game_night(&auckland, christmas_eve_utc), None);
+
+        // Late on December 23 UTC is Christmas Eve in Auckland
+        let december_23_utc = utc(2027, 12, 23, 12, 0);
+        assert!(get_special_game_night(&auckland, december_23_utc).unwrap().contains("Christmas Eve"));
+        assert_eq!(get_special_game_night(&utc_config, december_23_utc), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_special_nights() {
+        let schedule = GameNightSchedule::default();
+        let now = eastern(2027, 12, 1, 12, 0);
+
+        let reply = format_special_nights_at(&schedule, 4, now);
+        assert!(reply.contains("Friday, December 24, 2027 at 8:00 PM"));
+        assert!(reply.contains("Christmas Eve Game Night"));
+        assert!(!reply.contains("New Year"));
+
+        let empty = format_special_nights_at(&schedule, 2, now);
+        assert_eq!(empty, "📅 No special game nights in the next 2 weeks.");
+    }
+
+    #[test]
+    fn test_biweekly_uses_anchor_parity() {
+        let config = GameNightConfig {
+            recurrence: Recurrence::Biweekly { anchor: NaiveDate::from_ymd_opt(2024, 6, 7).unwrap() },
+            ..GameNightConfig::default()
+        };
+
+        // June 7 is on, June 14 is the off week, June 21 is on again
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 3, 12, 0)).unwrap(), eastern(2024, 6, 7, 20, 0));
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 8, 12, 0)).unwrap(), eastern(2024, 6, 21, 20, 0));
+        // Weeks before the anchor line up too
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 5, 20, 12, 0)).unwrap(), eastern(2024, 5, 24, 20, 0));
+
+        assert!(is_game_night_at(&config, eastern(2024, 6, 21, 21, 0)));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 14, 21, 0)));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_week_parity_across_year_boundary() {
+        // 2026 has a week 53, which Friday January 1st 2027 falls in, and
+        // January 8th 2027 starts over at week 1
+        let friday = |y, m, d| eastern(y, m, d, 20, 0);
+        let odd = GameNightConfig { recurrence: Recurrence::OddWeeks, ..GameNightConfig::default() };
+        let starts: Vec<_> = upcoming_game_nights(&odd, friday(2026, 12, 14)).take(4).collect();
+        assert_eq!(starts, vec![friday(2026, 12, 18), friday(2027, 1, 1), friday(2027, 1, 8), friday(2027, 1, 22)]);
+
+        let even = GameNightConfig { recurrence: Recurrence::EvenWeeks, ..GameNightConfig::default() };
+        let starts: Vec<_> = upcoming_game_nights(&even, friday(2026, 12, 14)).take(3).collect();
+        assert_eq!(starts, vec![friday(2026, 12, 25), friday(2027, 1, 15), friday(2027, 1, 29)]);
+        assert_eq!(get_next_game_night_at(&even, eastern(2026, 12, 26, 12, 0)).unwrap(), friday(2027, 1, 15));
+        assert!(!is_game_night_at(&even, eastern(2027, 1, 1, 21, 0)));
+        assert!(is_game_night_at(&odd, eastern(2027, 1, 1, 21, 0)));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_upcoming_game_nights() {
+        let config = GameNightConfig::default();
+        let starts: Vec<_> = upcoming_game_nights(&config, eastern(2024, 6, 7, 20, 0)).take(3).collect();
+        assert_eq!(
+            starts,
+            vec![eastern(2024, 6, 7, 20, 0), eastern(2024, 6, 14, 20, 0), eastern(2024, 6, 21, 20, 0)]
+        );
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_lineup() {
+        let schedule = GameNightSchedule {
+            nights: vec![
+                GameNightConfig { day_of_week: Weekday::Tue, ..GameNightConfig::default() },
+                GameNightConfig::default(),
+            ],
+        };
+        let now = eastern(2024, 12, 23, 12, 0);
+        let lineup = format_lineup_at(&schedule, 3, now);
+        let games: Vec<&str> = [(2024, 12, 24), (2024, 12, 27), (2024, 12, 31)]
+            .into_iter()
+            .map(|(y, m, d)| get_next_game_suggestion(NaiveDate::from_ymd_opt(y, m, d).unwrap()))
+            .collect();
+        assert_eq!(
+            lineup,
+            format!(
+                "🎲 **Game Night Lineup**\n━━━━━━━━━━━━━━━━━━━━━\n\
+                🗓️ Tuesday, December 24 at 8:00 PM - 🎮 {}\n\
+                🗓️ Friday, December 27 at 8:00 PM - 🎮 {}\n\
+                🗓️ Tuesday, December 31 at 8:00 PM - 🎮 {}",
+                games[0], games[1], games[2]
+            )
+        );
+        // Asking again later, before the first one starts, gives the same lineup
+        assert_eq!(format_lineup_at(&schedule, 3, eastern(2024, 12, 24, 19, 59)), lineup);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_next_game_nights_across_nights() {
+        let schedule = GameNightSchedule {
+            nights: vec![
+                GameNightConfig { day_of_week: Weekday::Tue, ..GameNightConfig::default() },
+                GameNightConfig::default(),
+            ],
+        };
+        // Monday December 23rd, so Christmas Eve is the first one
+        let now = eastern(2024, 12, 23, 12, 0);
+        assert_eq!(
+            upcoming_sessions(&schedule, 3, now),
+            vec![(0, eastern(2024, 12, 24, 20, 0)), (1, eastern(2024, 12, 27, 20, 0)), (0, eastern(2024, 12, 31, 20, 0))]
+        );
+
+        let reply = format_next_game_nights_at(&schedule, 3, now);
+        assert!(reply.starts_with("📅 **Next 3 Game Nights**"));
+        assert!(reply.contains("🗓️ Tuesday, December 24, 2024 at 8:00 PM US/Eastern\n🎄 **Christmas Eve Game Night!** 🎅"));
+        assert!(reply.contains("🗓️ Friday, December 27, 2024 at 8:00 PM US/Eastern\n🗓️ Tuesday, December 31, 2024"));
+        assert!(reply.ends_with("🎊 **New Year's Eve Game Night!** 🥳"));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_game_night_ahead() {
+        let schedule = GameNightSchedule::default();
+        // Wednesday December 1st, so +3 is Christmas Eve
+        let now = eastern(2027, 12, 1, 12, 0);
+        assert_eq!(
+            format_game_night_ahead_at(&schedule, 3, now),
+            "📅 **Game Night +3**\n\
+            🗓️ Friday, December 24, 2027 at 8:00 PM US/Eastern\n\
+            ⏳ Starts in 23 days, 8 hours, 0 minutes, 0 seconds\n\
+            🎄 **Christmas Eve Game Night!** 🎅"
+        );
+        let next = format_game_night_ahead_at(&schedule, 0, now);
+        assert!(next.contains("Friday, December 03, 2027"), "{}", next);
+        assert!(!next.contains("Christmas"));
+    }
+
+    #[test]
+    fn test_monthly_third_thursday_across_month_boundary() {
+        let config = GameNightConfig {
+            recurrence: Recurrence::MonthlyNth { week: 3, weekday: Weekday::Thu },
+            ..GameNightConfig::default()
+        };
+
+        // The third Thursday of January 2025 is the 16th
+        assert_eq!(get_next_game_night_at(&config, eastern(2025, 1, 2, 12, 0)).unwrap(), eastern(2025, 1, 16, 20, 0));
+        // Once that's over, the next one is February 20th
+        assert_eq!(get_next_game_night_at(&config, eastern(2025, 1, 17, 1, 0)).unwrap(), eastern(2025, 2, 20, 20, 0));
+        assert_eq!(get_next_game_night_at(&config, eastern(2025, 1, 30, 12, 0)).unwrap(), eastern(2025, 2, 20, 20, 0));
+
+        assert!(is_game_night_at(&config, eastern(2025, 2, 20, 21, 0)));
+        assert!(!is_game_night_at(&config, eastern(2025, 2, 13, 21, 0)));
+    }
+
+    #[test]
+    fn test_time_format_setting() {
+        let start = eastern(2024, 6, 7, 20, 0);
+        let config = GameNightConfig::default();
+        assert_eq!(format_local(start, &config, LocalFormat::Time), "8:00 PM");
+
+        let config = GameNightConfig { time_format: TimeFormat::TwentyFour, ..GameNightConfig::default() };
+        assert_eq!(format_local(start, &config, LocalFormat::Time), "20:00");
+        let reminder = format_reminder(&config, start, start - chrono::Duration::minutes(15));
+        assert!(reminder.contains("Friday at 20:00"));
+    }
+
+    #[test]
+    fn test_date_style_setting() {
+        let start = eastern(2024, 6, 7, 20, 0);
+        let config = GameNightConfig::default();
+        assert_eq!(format_local(start, &config, LocalFormat::Date), "Friday, June 07, 2024");
+
+        let config = GameNightConfig { date_style: DateStyle::DayFirst, ..GameNightConfig::default() };
+        assert_eq!(format_local(start, &config, LocalFormat::Date), "Friday, 07/06/2024");
+        assert_eq!(format_local(start, &config, LocalFormat::DateWithoutYear), "Friday, 07/06");
+        assert!(format_next_game_night_at(&config, eastern(2024, 6, 5, 12, 0)).unwrap().contains("**Date:** Friday, 07/06/2024"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        let duration = chrono::Duration::days(2) + chrono::Duration::hours(3) + chrono::Duration::seconds(65);
+        assert_eq!(format_duration(duration, Locale::English), "2 days, 3 hours, 1 minutes, 5 seconds");
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_until() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 12, 20, 12, 0);
+
+        let reply = format_until_at(&config, "2024-12-24", Some("18:30"), now).unwrap();
+        assert!(reply.contains("Tuesday, December 24, 2024 at 6:30 PM"));
+        assert!(reply.contains("4 days, 6 hours, 30 minutes, 0 seconds"));
+
+        // Midnight in the config's timezone without a time
+        let reply = format_until_at(&config, "2024-12-24", None, now).unwrap();
+        assert!(reply.contains("3 days, 12 hours, 0 minutes, 0 seconds"));
+
+        assert!(format_until_at(&config, "2024-12-01", None, now).unwrap_err().contains("past"));
+        assert!(format_until_at(&config, "24/12/2024", None, now).is_err());
+        assert!(format_until_at(&config, "2024-12-24", Some("6pm"), now).is_err());
+    }
+
+    #[test]
+    fn test_override_replaces_that_weeks_session() {
+        // Saturday the 8th instead of Friday the 7th
+        let config = GameNightConfig {
+            override_date: Some(eastern(2024, 6, 8, 18, 0)),
+            ..GameNightConfig::default()
+        };
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 3, 12, 0)).unwrap(), eastern(2024, 6, 8, 18, 0));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 7, 21, 0)));
+        assert!(is_game_night_at(&config, eastern(2024, 6, 8, 19, 0)));
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 8, 19, 0)).unwrap(), eastern(2024, 6, 8, 18, 0));
+
+        // Back to Fridays afterwards, and a past override is ignored
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 9, 12, 0)).unwrap(), eastern(2024, 6, 14, 20, 0));
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 7, 1, 12, 0)).unwrap(), eastern(2024, 7, 5, 20, 0));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_override_later_than_regular_session() {
+        // An override late in the week still comes after any session before it
+        let config = GameNightConfig {
+            day_of_week: Weekday::Mon,
+            override_date: Some(eastern(2024, 6, 15, 18, 0)),
+            ..GameNightConfig::default()
+        };
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 5, 12, 0)).unwrap(), eastern(2024, 6, 15, 18, 0));
+        assert_eq!(
+            upcoming_game_nights(&config, eastern(2024, 6, 5, 12, 0)).take(2).collect::<Vec<_>>(),
+            vec![eastern(2024, 6, 15, 18, 0), eastern(2024, 6, 17, 20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_skipped_date_rolls_to_following_week() {
+        let mut config = GameNightConfig::default();
+        config.skipped_dates.insert(NaiveDate::from_ymd_opt(2024, 6, 7).unwrap());
+
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 5, 12, 0)).unwrap(), eastern(2024, 6, 14, 20, 0));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 7, 21, 0)));
+        assert!(is_game_night_at(&config, eastern(2024, 6, 14, 21, 0)));
+
+        // Skipping the override's day cancels the override too
+        config.override_date = Some(eastern(2024, 6, 15, 18, 0));
+        config.skipped_dates.insert(NaiveDate::from_ymd_opt(2024, 6, 15).unwrap());
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 12, 12, 0)).unwrap(), eastern(2024, 6, 21, 20, 0));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_skip_next_during_live_session() {
+        let mut schedule = GameNightSchedule::default();
+        schedule.nights[0].skipped_dates.insert(NaiveDate::from_ymd_opt(2024, 5, 31).unwrap());
+
+        // Tonight's session is already going, so next week's is the one skipped
+        assert_eq!(schedule.skip_next_at(eastern(2024, 6, 7, 21, 0)).unwrap(), eastern(2024, 6, 14, 20, 0));
+        assert_eq!(schedule.nights[0].skipped_dates, HashSet::from([NaiveDate::from_ymd_opt(2024, 6, 14).unwrap()]));
+
+        // Before it starts, tonight's is
+        let mut schedule = GameNightSchedule::default();
+        assert_eq!(schedule.skip_next_at(eastern(2024, 6, 7, 19, 0)).unwrap(), eastern(2024, 6, 7, 20, 0));
+    }
+
+    #[test]
+    fn test_status_embed_colour_and_fields() {
+        let config = GameNightConfig::default();
+
+        let live = serde_json::to_value(game_night_status_embed_at(&config, eastern(2024, 6, 7, 21, 30)).unwrap()).unwrap();
+        assert_eq!(live["color"], LIVE_COLOUR.0);
+        assert_eq!(live["fields"][0]["value"], "2 hours 30 minutes");
+
+        let upcoming = serde_json::to_value(game_night_status_embed_at(&config, eastern(2024, 6, 5, 20, 0)).unwrap()).unwrap();
+        assert_eq!(upcoming["color"], UPCOMING_COLOUR.0);
+        let themed = GameNightConfig { embed_color_upcoming: Colour::new(0x123456), ..GameNightConfig::default() };
+        let themed = serde_json::to_value(game_night_status_embed_at(&themed, eastern(2024, 6, 5, 20, 0)).unwrap()).unwrap();
+        assert_eq!(themed["color"], 0x123456);
+        assert_eq!(upcoming["fields"][0]["value"], "Friday, June 07");
+        assert_eq!(upcoming["fields"][1]["value"], "8:00 PM US/Eastern");
+        assert_eq!(upcoming["fields"][3]["value"], "2 days, 0 hours, 0 minutes, 0 seconds");
+    }
+
+    #[test]
+    fn test_next_game_night_embed() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 6, 6, 19, 0);
+        let start = get_next_game_night_at(&config, now).unwrap();
+        let embed = serde_json::to_value(next_game_night_embed_at(&config, start, None, None, now)).unwrap();
+        assert_eq!(embed["color"], UPCOMING_COLOUR.0);
+        assert_eq!(embed["fields"][0]["value"], "Friday, June 07, 2024");
+        assert_eq!(embed["fields"][2]["value"], "4 hours");
+        assert_eq!(embed["fields"][3]["value"], "1 days, 1 hours, 0 minutes, 0 seconds");
+    }
+
+    #[test]
+    fn test_timezone_label_follows_dst() {
+        let new_york = chrono_tz::US::Eastern;
+        assert_eq!(timezone_label(new_york, Utc.with_ymd_and_hms(2024, 7, 4, 16, 0, 0).unwrap()), "US/Eastern (EDT)");
+        assert_eq!(timezone_label(new_york, Utc.with_ymd_and_hms(2024, 1, 15, 17, 0, 0).unwrap()), "US/Eastern (EST)");
+        // Some zones only have numeric abbreviations, and UTC is its own
+        assert_eq!(timezone_label(chrono_tz::Asia::Dubai, Utc.with_ymd_and_hms(2024, 7, 4, 16, 0, 0).unwrap()), "Asia/Dubai");
+        assert_eq!(timezone_label(chrono_tz::UTC, Utc.with_ymd_and_hms(2024, 7, 4, 16, 0, 0).unwrap()), "UTC");
+
+        let details = format_next_game_night_at(&GameNightConfig::default(), eastern(2024, 1, 15, 12, 0)).unwrap();
+        assert!(details.contains("8:00 PM US/Eastern (EST)"), "{}", details);
+    }
+
+    #[test]
+    fn test_next_game_night_embed_in_user_timezone() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 6, 6, 19, 0);
+        let start = get_next_game_night_at(&config, now).unwrap();
+
+        let embed = serde_json::to_value(next_game_night_embed_at(&config, start, Some(chrono_tz::Asia::Tokyo), None, now)).unwrap();
+        assert_eq!(embed["fields"][3]["name"], "🌍 Your Time");
+        assert_eq!(embed["fields"][3]["value"], "Saturday, June 08, 9:00 AM Asia/Tokyo");
+
+        // Nothing extra for someone already in the night's timezone
+        let embed = serde_json::to_value(next_game_night_embed_at(&config, start, Some(config.timezone), None, now)).unwrap();
+        assert_eq!(embed["fields"][3]["name"], "⏳ Countdown");
+    }
+
+    #[test]
+    fn test_next_game_night_embed_shown_in_timezone() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 6, 6, 19, 0);
+        let start = get_next_game_night_at(&config, now).unwrap();
+
+        // Friday 8 PM in New York is Saturday morning in Tokyo
+        let tokyo = Some(chrono_tz::Asia::Tokyo);
+        let embed = serde_json::to_value(next_game_night_embed_at(&config, start, None, tokyo, now)).unwrap();
+        assert_eq!(embed["fields"][0]["value"], "Saturday, June 08, 2024");
+        assert_eq!(embed["fields"][1]["value"], "9:00 AM Asia/Tokyo (JST)");
+        assert_eq!(embed["fields"][2]["value"], "4 hours");
+        assert_eq!(embed["fields"][3]["value"], "1 days, 1 hours, 0 minutes, 0 seconds");
+
+        // Already showing the member's own timezone, so no extra field for it
+        let embed = serde_json::to_value(next_game_night_embed_at(&config, start, tokyo, tokyo, now)).unwrap();
+        assert_eq!(embed["fields"][3]["name"], "⏳ Countdown");
+    }
+
+    #[test]
+    fn test_next_game_night_text_matches_embed() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 6, 6, 19, 0);
+        let start = get_next_game_night_at(&config, now).unwrap();
+        let tokyo = Some(chrono_tz::Asia::Tokyo);
+
+        let details = format_next_game_night_details(&config, start, None, tokyo, now);
+        assert!(details.contains("**Date:** Saturday, June 08, 2024"), "{}", details);
+        assert!(details.contains("**Start Time:** 9:00 AM Asia/Tokyo (JST)"), "{}", details);
+        assert!(!details.contains("Your time"));
+
+        let details = format_next_game_night_details(&config, start, tokyo, None, now);
+        assert!(details.contains("**Start Time:** 8:00 PM US/Eastern (EDT)"), "{}", details);
+        assert!(details.contains("**Your time:** Saturday, June 08, 9:00 AM Asia/Tokyo"), "{}", details);
+    }
+
+    #[test]
+    fn test_format_multi_tz() {
+        // Friday evening in New York is already Saturday further east
+        let start = eastern(2024, 6, 7, 20, 0);
+        let zones = [chrono_tz::America::Los_Angeles, chrono_tz::UTC, chrono_tz::Asia::Tokyo];
+        assert_eq!(
+            format_multi_tz(start, &zones),
+            "America/Los_Angeles  Fri Jun 07, 17:00\n\
+            UTC                  Sat Jun 08, 00:00\n\
+            Asia/Tokyo           Sat Jun 08, 09:00"
+        );
+        assert_eq!(format_multi_tz(start, &[]), "");
+    }
+
+    #[test]
+    fn test_next_game_night_lists_display_timezones() {
+        let config = GameNightConfig {
+            display_timezones: vec![chrono_tz::UTC],
+            ..GameNightConfig::default()
+        };
+        let message = format_next_game_night_at(&config, eastern(2024, 6, 6, 19, 0)).unwrap();
+        assert!(message.contains("🌐 **In other timezones:**\n```\nUTC  Sat Jun 08, 00:00\n```"));
+
+        let message = format_next_game_night_at(&GameNightConfig::default(), eastern(2024, 6, 6, 19, 0)).unwrap();
+        assert!(!message.contains("In other timezones"));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_timezone_conversion() {
+        let now = eastern(2024, 6, 7, 12, 0);
+        let eight_pm = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+
+        let convert = |time, from, to, locale| format_timezone_conversion_at(time, from, to, locale, now).unwrap();
+
+        assert_eq!(
+            convert(eight_pm, chrono_tz::America::New_York, chrono_tz::America::Los_Angeles, Locale::English),
+            "🕐 **20:00** in America/New_York is **17:00** in America/Los_Angeles"
+        );
+        assert_eq!(
+            convert(eight_pm, chrono_tz::America::New_York, chrono_tz::Europe::Berlin, Locale::English),
+            "🕐 **20:00** in America/New_York is **02:00** in Europe/Berlin (the next day)"
+        );
+        assert_eq!(
+            convert(NaiveTime::from_hms_opt(1, 0, 0).unwrap(), chrono_tz::Asia::Tokyo, chrono_tz::UTC, Locale::English),
+            "🕐 **01:00** in Asia/Tokyo is **16:00** in UTC (the day before)"
+        );
+        assert_eq!(
+            convert(eight_pm, chrono_tz::America::New_York, chrono_tz::Europe::Berlin, Locale::Spanish),
+            "🕐 Las **20:00** en America/New_York son las **02:00** en Europe/Berlin (del día siguiente)"
+        );
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_session_length_conversions() {
+        let eight_pm = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+        let midnight = NaiveTime::MIN;
+        let one_thirty = NaiveTime::from_hms_opt(1, 30, 0).unwrap();
+
+        assert_eq!(SessionLength::Minutes(4 * 60).end_time(eight_pm), (midnight, true));
+        assert_eq!(SessionLength::Minutes(2 * 60).end_time(eight_pm), (NaiveTime::from_hms_opt(22, 0, 0).unwrap(), false));
+
+        let overnight = SessionLength::EndTime { end_time: one_thirty, next_day: true };
+        assert_eq!(overnight.duration(eight_pm), chrono::Duration::minutes(330));
+        assert_eq!(overnight.end_time(eight_pm), (one_thirty, true));
+
+        // Converting back and forth lands on the same session
+        let (end_time, next_day) = SessionLength::Minutes(4 * 60).end_time(eight_pm);
+        assert_eq!(SessionLength::EndTime { end_time, next_day }.duration(eight_pm), chrono::Duration::hours(4));
+    }
+
+    #[test]
+    fn test_end_time_session_window() {
+        let config = GameNightConfig {
+            length: SessionLength::EndTime { end_time: NaiveTime::from_hms_opt(1, 30, 0).unwrap(), next_day: true },
+            ..GameNightConfig::default()
+        };
+        let (start, end) = session_window(&config, NaiveDate::from_ymd_opt(2024, 6, 7).unwrap()).unwrap();
+        assert_eq!(start, eastern(2024, 6, 7, 20, 0));
+        assert_eq!(end, eastern(2024, 6, 8, 1, 30));
+
+        assert!(is_game_night_at(&config, eastern(2024, 6, 8, 1, 0)));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 8, 1, 45)));
+        assert_eq!(format_session_length(&config), "until 1:30 AM the next day");
+    }
+
+    #[test]
+    fn test_end_time_across_dst_change() {
+        // Clocks fall back at 2 AM on 2024-11-03, so 8 PM to 2 AM runs seven hours
+        let config = GameNightConfig {
+            day_of_week: Weekday::Sat,
+            length: SessionLength::EndTime { end_time: NaiveTime::from_hms_opt(2, 0, 0).unwrap(), next_day: true },
+            ..GameNightConfig::default()
+        };
+        let (start, end) = session_window(&config, NaiveDate::from_ymd_opt(2024, 11, 2).unwrap()).unwrap();
+        assert_eq!(end - start, chrono::Duration::hours(7));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_next_game_night_json() {
+        let json = next_game_night_json_at(&GameNightConfig::default(), eastern(2024, 6, 6, 19, 0)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "start_utc": "2024-06-08T00:00:00Z",
+                "start_local": "2024-06-07T20:00:00-04:00",
+                "timezone": "US/Eastern",
+                "duration_hours": 4.0,
+                "seconds_until": 90000,
+            })
+        );
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_debug() {
+        let config = GameNightConfig::default();
+
+        let upcoming = format_debug_at(&config, eastern(2024, 6, 6, 19, 0));
+        assert!(upcoming.contains("next (UTC): 2024-06-08T00:00:00+00:00"));
+        assert!(upcoming.contains("next (local): 2024-06-07 20:00:00 EDT (-04:00)"));
+        assert!(upcoming.contains("time_until_game_night: 90000 s"));
+        assert!(upcoming.contains("live: false"));
+
+        let live = format_debug_at(&config, eastern(2024, 6, 7, 21, 0));
+        assert!(live.contains("time_until_game_night: -3600 s"));
+        assert!(live.contains("live: true"));
+    }
+
+    #[test]
+    fn test_days_until_next_weekday() {
+        for include_today in [true, false] {
+            assert_eq!(days_until_next_weekday(Weekday::Mon, Weekday::Fri, include_today), 4);
+            assert_eq!(days_until_next_weekday(Weekday::Fri, Weekday::Mon, include_today), 3);
+            assert_eq!(days_until_next_weekday(Weekday::Sun, Weekday::Mon, include_today), 1);
+        }
+        assert_eq!(days_until_next_weekday(Weekday::Wed, Weekday::Wed, true), 0);
+        assert_eq!(days_until_next_weekday(Weekday::Wed, Weekday::Wed, false), 7);
+    }
+
+    #[test]
+    fn test_days_until_next_weekday_all_pairs() {
+        const WEEK: [Weekday; 7] =
+            [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+        // June 3rd 2024 is a Monday, so this walks a whole week of `from` days
+        for from_date in NaiveDate::from_ymd_opt(2024, 6, 3).unwrap().iter_days().take(7) {
+            for to in WEEK {
+                for include_today in [true, false] {
+                    let first = if include_today { 0 } else { 1 };
+                    let expected = (first..)
+                        .find(|days| (from_date + chrono::Duration::days(*days)).weekday() == to)
+                        .unwrap() as u32;
+                    let days = days_until_next_weekday(from_date.weekday(), to, include_today);
+                    assert_eq!(days, expected, "{} to {}, include_today: {}", from_date.weekday(), to, include_today);
+                    assert!(if include_today { days <= 6 } else { (1..=7).contains(&days) });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_game_night_same_day() {
+        let config = GameNightConfig::default();
+        // Friday before and right at the start: tonight
+        assert_eq!(next_start_at(&config, eastern(2024, 6, 7, 19, 0)).unwrap(), eastern(2024, 6, 7, 20, 0));
+        assert_eq!(next_start_at(&config, eastern(2024, 6, 7, 20, 0)).unwrap(), eastern(2024, 6, 7, 20, 0));
+        // Once it's started, next week
+        assert_eq!(next_start_at(&config, eastern(2024, 6, 7, 20, 1)).unwrap(), eastern(2024, 6, 14, 20, 0));
+    }
 
     #[test]
     fn test_game_night_config() {
         let config = GameNightConfig::default();
         assert_eq!(config.day_of_week, Weekday::Fri);
         assert_eq!(config.start_time.hour(), 20);
-        assert_eq!(config.duration_hours, 4);
+        assert_eq!(config.length, SessionLength::Minutes(4 * 60));
+    }
+
+    #[test]
+    fn test_is_game_night_at_session_edges() {
+        let config = late_night_config();
+        let start = eastern(2024, 6, 7, 22, 0);
+        assert!(!is_game_night_at(&config, start - chrono::Duration::seconds(1)));
+        assert!(is_game_night_at(&config, start));
+        // Runs past midnight until 2 AM, the end included
+        let end = eastern(2024, 6, 8, 2, 0);
+        assert!(is_game_night_at(&config, end));
+        assert!(!is_game_night_at(&config, end + chrono::Duration::seconds(1)));
+    }
+
+    fn late_night_config() -> GameNightConfig {
+        GameNightConfig {
+            start_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(), // 10:00 PM
+            length: SessionLength::Minutes(4 * 60),
+            ..GameNightConfig::default()
+        }
+    }
+
+    fn eastern(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        chrono_tz::US::Eastern
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_is_game_night_after_midnight() {
+        let config = late_night_config();
+        // Friday June 7th 2024 at 10PM runs until Saturday 2AM
+        assert!(is_game_night_at(&config, eastern(2024, 6, 7, 23, 0)));
+        assert!(is_game_night_at(&config, eastern(2024, 6, 8, 1, 0)));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 8, 3, 0)));
+    }
+
+    #[test]
+    fn test_is_not_game_night_early_on_game_day() {
+        let config = late_night_config();
+        // Early Friday morning belongs to no session
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 7, 1, 0)));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 7, 21, 59)));
+    }
+
+    #[test]
+    fn test_schedule_next_occurrence() {
+        let schedule = GameNightSchedule {
+            nights: vec![
+                GameNightConfig { day_of_week: Weekday::Tue, ..GameNightConfig::default() },
+                GameNightConfig::default(),
+            ],
+        };
+        let (index, start) = schedule.next_occurrence().unwrap();
+        assert_eq!(start, get_next_game_night(&schedule.nights[index]).unwrap());
+        for night in &schedule.nights {
+            assert!(start <= get_next_game_night(night).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_role_ping_only_when_configured() {
+        let config = GameNightConfig::default();
+        assert_eq!(with_role_ping(&config, "hi".to_string()), "hi");
+
+        let config = GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..config };
+        assert_eq!(with_role_ping(&config, "hi".to_string()), "<@&42>\nhi");
+    }
+
+    #[test]
+    fn test_schedule_notify_roles() {
+        let schedule = GameNightSchedule {
+            nights: vec![
+                GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..GameNightConfig::default() },
+                GameNightConfig { notify_role_id: Some(RoleId::new(42)), ..GameNightConfig::default() },
+                GameNightConfig::default(),
+            ],
+        };
+        assert_eq!(schedule.notify_roles(), vec![RoleId::new(42)]);
+    }
+
+    #[test]
+    fn test_next_game_suggestion_is_stable_per_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        assert_eq!(get_next_game_suggestion(date), get_next_game_suggestion(date));
+
+        // The following week's night gets a different game
+        let next_week = date + chrono::Duration::weeks(1);
+        assert_ne!(get_next_game_suggestion(date), get_next_game_suggestion(next_week));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_game_library_add_and_remove() {
+        let mut library = Vec::new();
+        assert!(add_to_library(&mut library, "Minecraft"));
+        assert!(!add_to_library(&mut library, "minecraft"));
+        assert_eq!(library, vec![LibraryGame::new("Minecraft")]);
+
+        assert!(set_game_weight(&mut library, "MINECRAFT", 5));
+        assert_eq!(library[0].weight, 5);
+        assert!(!set_game_weight(&mut library, "Terraria", 5));
+
+        assert!(!remove_from_library(&mut library, "Terraria"));
+        assert!(remove_from_library(&mut library, "MINECRAFT"));
+        assert!(library.is_empty());
+    }
+
+    #[test]
+    fn test_library_loads_plain_names() {
+        let library: Vec<LibraryGame> =
+            serde_json::from_str(r#"["Minecraft", {"name": "Terraria", "weight": 3}]"#).unwrap();
+        assert_eq!(library, vec![
+            LibraryGame::new("Minecraft"),
+            LibraryGame { name: "Terraria".to_string(), weight: 3 },
+        ]);
+        let saved = serde_json::to_string(&library).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<LibraryGame>>(&saved).unwrap(), library);
+    }
+
+    #[test]
+    fn test_game_suggestion_uses_library() {
+        let library = vec![LibraryGame::new("Minecraft"), LibraryGame::new("Terraria")];
+        let suggestion = get_game_suggestion(&library, Some(7));
+        assert!(suggestion.contains("• Minecraft"));
+        assert!(suggestion.contains("• Terraria"));
+        assert_eq!(get_game_suggestion(&[], None).lines().count(), 4);
+    }
+
+    #[test]
+    fn test_game_suggestion_never_empty() {
+        // A library saved with only weightless games still gets suggestions
+        let retired = [LibraryGame { name: "Retired".to_string(), weight: 0 }];
+        for library in [&[][..], &retired[..]] {
+            for seed in [None, Some(0), Some(1)] {
+                let suggestion = get_game_suggestion(library, seed);
+                let games: Vec<&str> = suggestion.lines().skip(1).collect();
+                assert_eq!(games.len(), 3, "{}", suggestion);
+                assert!(games.iter().all(|game| game.len() > "• ".len() && !game.contains("Retired")));
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_suggestion_same_for_same_seed() {
+        assert_eq!(get_game_suggestion(&[], Some(42)), get_game_suggestion(&[], Some(42)));
+    }
+
+    #[test]
+    fn test_pick_weighted_favours_heavier_games() {
+        let pool = vec![
+            LibraryGame { name: "Popular".to_string(), weight: 9 },
+            LibraryGame::new("Niche"),
+            LibraryGame { name: "Retired".to_string(), weight: 0 },
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        let popular = (0..1000)
+            .filter(|_| pick_weighted(pool.clone(), 1, &mut rng)[0].name == "Popular")
+            .count();
+        // Expect about 900
+        assert!((850..950).contains(&popular), "{}", popular);
+
+        // Every game with a weight gets picked once, and never a weightless one
+        let picks = pick_weighted(pool, 3, &mut rng);
+        assert_eq!(picks.len(), 2);
+        assert!(picks.iter().all(|game| game.name != "Retired"));
+    }
+
+    #[test]
+    fn test_planned_game_comes_from_library() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        assert_eq!(planned_game(&[], date), get_next_game_suggestion(date));
+
+        let library = vec![
+            LibraryGame::new("Minecraft"),
+            LibraryGame::new("Terraria"),
+            LibraryGame { name: "Retired".to_string(), weight: 0 },
+        ];
+        let game = planned_game(&library, date);
+        assert!(game == "Minecraft" || game == "Terraria", "{}", game);
+        assert_eq!(planned_game(&library, date), game);
+
+        let retired = [LibraryGame { name: "Retired".to_string(), weight: 0 }];
+        assert_eq!(planned_game(&retired, date), get_next_game_suggestion(date));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_vote_candidates() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        assert_eq!(vote_candidates(&[], date).len(), 5);
+
+        let library: Vec<LibraryGame> = (0..12).map(|i| LibraryGame::new(&format!("Game {}", i))).collect();
+        let candidates = vote_candidates(&library, date);
+        assert_eq!(candidates.len(), 10);
+        assert_eq!(candidates[0], "Game 0");
+    }
+
+    #[test]
+    fn test_next_game_night_from_thursday_evening() {
+        let config = GameNightConfig::default();
+        let thursday = eastern(2024, 6, 6, 19, 0);
+        // Friday June 7th at 8PM Eastern
+        assert_eq!(get_next_game_night_at(&config, thursday).unwrap(), eastern(2024, 6, 7, 20, 0));
+        assert_eq!(time_until_game_night_at(&config, thursday).unwrap(), chrono::Duration::hours(25));
+    }
+
+    #[test]
+    fn test_next_game_night_after_session_rolls_to_next_week() {
+        let config = GameNightConfig::default();
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 7, 19, 0)).unwrap(), eastern(2024, 6, 7, 20, 0));
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 8, 0, 30)).unwrap(), eastern(2024, 6, 14, 20, 0));
+    }
+
+    #[test]
+    fn test_next_game_night_just_after_start() {
+        // Joining late still gets tonight's start, not next week's
+        let config = GameNightConfig::default();
+        let start = eastern(2024, 6, 7, 20, 0);
+        assert_eq!(get_next_game_night_at(&config, start + chrono::Duration::minutes(5)).unwrap(), start);
+        assert_eq!(get_next_game_night_at(&config, start + chrono::Duration::minutes(1)).unwrap(), start);
+    }
+
+    #[test]
+    fn test_late_join_grace_shorter_than_session() {
+        let config = GameNightConfig { late_join_grace_minutes: Some(30), ..GameNightConfig::default() };
+        let start = eastern(2024, 6, 7, 20, 0);
+        assert_eq!(get_next_game_night_at(&config, start + chrono::Duration::minutes(5)).unwrap(), start);
+        assert_eq!(get_next_game_night_at(&config, start + chrono::Duration::minutes(30)).unwrap(), start);
+
+        // Past the grace !nextgame moves on while the session keeps going
+        let late = start + chrono::Duration::minutes(45);
+        assert_eq!(get_next_game_night_at(&config, late).unwrap(), eastern(2024, 6, 14, 20, 0));
+        assert!(is_game_night_at(&config, late));
+
+        // No grace at all rolls over right at the start
+        let config = GameNightConfig { late_join_grace_minutes: Some(0), ..config };
+        assert_eq!(get_next_game_night_at(&config, start).unwrap(), start);
+        assert_eq!(get_next_game_night_at(&config, start + chrono::Duration::minutes(1)).unwrap(), eastern(2024, 6, 14, 20, 0));
+    }
+
+    #[test]
+    fn test_time_remaining_in_session() {
+        let config = GameNightConfig::default();
+        assert_eq!(
+            time_remaining_in_session(&config, eastern(2024, 6, 7, 21, 30)),
+            Some(chrono::Duration::minutes(150))
+        );
+        assert_eq!(time_remaining_in_session(&config, eastern(2024, 6, 7, 19, 0)), None);
+
+        // 10 PM to 2 AM, asked before and after midnight
+        let config = late_night_config();
+        assert_eq!(time_remaining_in_session(&config, eastern(2024, 6, 7, 23, 0)), Some(chrono::Duration::hours(3)));
+        assert_eq!(time_remaining_in_session(&config, eastern(2024, 6, 8, 1, 15)), Some(chrono::Duration::minutes(45)));
+
+        let status = format_game_night_status_at(&config, eastern(2024, 6, 8, 1, 15)).unwrap();
+        assert!(status.contains("Time remaining: 0 hours 45 minutes"));
+    }
+
+    #[test]
+    fn test_time_remaining_after_midnight() {
+        // 10 PM with 4 hours runs until 2 AM, an hour after asking
+        let config = late_night_config();
+        let status = format_game_night_status_at(&config, eastern(2024, 6, 8, 1, 0)).unwrap();
+        assert!(status.contains("Time remaining: 1 hour 0 minutes"), "{}", status);
+    }
+
+    #[test]
+    fn test_status_after_tonights_session() {
+        // 10 PM to 2 AM, asked at 3 AM after it ended
+        let config = late_night_config();
+        let status = format_game_night_status_at(&config, eastern(2024, 6, 8, 3, 0)).unwrap();
+        assert!(status.starts_with("🏁 **Game Night is Over for Today!**"), "{}", status);
+        assert!(status.contains("Next one: Friday, June 14 (in 6 days)"), "{}", status);
+
+        // Ended earlier the same evening
+        let config = GameNightConfig { length: SessionLength::Minutes(2 * 60), ..GameNightConfig::default() };
+        let status = format_game_night_status_at(&config, eastern(2024, 6, 7, 23, 0)).unwrap();
+        assert!(status.contains("Next one: Friday, June 14 (in 7 days)"), "{}", status);
+
+        // Not on a day without a session, and not while it's live
+        assert!(!format_game_night_status_at(&config, eastern(2024, 6, 9, 12, 0)).unwrap().contains("Over for Today"));
+        assert!(format_game_night_status_at(&config, eastern(2024, 6, 7, 21, 0)).unwrap().contains("LIVE NOW"));
+        assert_eq!(session_over_today_at(&config, eastern(2024, 6, 7, 19, 0)), None);
+    }
+
+    #[test]
+    fn test_ninety_minute_session() {
+        let config = GameNightConfig { length: SessionLength::Minutes(90), ..GameNightConfig::default() };
+        // 8:00 PM to 9:30 PM
+        assert!(is_game_night_at(&config, eastern(2024, 6, 7, 21, 29)));
+        assert!(is_game_night_at(&config, eastern(2024, 6, 7, 21, 30)));
+        assert!(!is_game_night_at(&config, eastern(2024, 6, 7, 21, 31)));
+        assert_eq!(config.session_end(eastern(2024, 6, 7, 20, 0)).unwrap(), eastern(2024, 6, 7, 21, 30));
+        assert_eq!(
+            time_remaining_in_session(&config, eastern(2024, 6, 7, 20, 15)),
+            Some(chrono::Duration::minutes(75))
+        );
+        assert_eq!(format_session_length(&config), "1 hour 30 minutes");
+    }
+
+    #[test]
+    fn test_format_minutes() {
+        assert_eq!(format_minutes(150, Locale::English), "2 hours 30 minutes");
+        assert_eq!(format_minutes(240, Locale::English), "4 hours");
+        assert_eq!(format_minutes(60, Locale::English), "1 hour");
+        assert_eq!(format_minutes(45, Locale::English), "45 minutes");
+        assert_eq!(format_minutes(61, Locale::English), "1 hour 1 minute");
+    }
+
+    #[test]
+    fn test_format_hours_minutes() {
+        assert_eq!(format_hours_minutes(chrono::Duration::minutes(61), Locale::English), "1 hour 1 minute");
+        assert_eq!(format_hours_minutes(chrono::Duration::minutes(150), Locale::English), "2 hours 30 minutes");
+        assert_eq!(format_hours_minutes(chrono::Duration::seconds(59), Locale::English), "0 hours 0 minutes");
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_next_game_night_during_live_session() {
+        let config = GameNightConfig::default();
+        let friday_night = eastern(2024, 6, 7, 21, 0);
+        assert!(is_game_night_at(&config, friday_night));
+        assert_eq!(get_next_game_night_at(&config, friday_night).unwrap(), eastern(2024, 6, 7, 20, 0));
+
+        // Still this session past midnight for a late night
+        let config = late_night_config();
+        assert_eq!(get_next_game_night_at(&config, eastern(2024, 6, 8, 1, 0)).unwrap(), eastern(2024, 6, 7, 22, 0));
+
+        // Sessions after the live one are still listed
+        let config = GameNightConfig::default();
+        let starts: Vec<_> = upcoming_game_nights(&config, friday_night).take(2).collect();
+        assert_eq!(starts, vec![eastern(2024, 6, 7, 20, 0), eastern(2024, 6, 14, 20, 0)]);
+
+        let reply = format_next_game_night_at(&GameNightConfig::default(), friday_night).unwrap();
+        assert!(reply.contains("Game night is live right now!"));
+    }
+
+    #[test]
+    fn test_time_until_matches_next_game_night() {
+        let config = GameNightConfig::default();
+        let now = eastern(2024, 6, 6, 19, 0) + chrono::Duration::milliseconds(1234);
+        assert_eq!(now + time_until_game_night_at(&config, now).unwrap(), get_next_game_night_at(&config, now).unwrap());
+    }
+
+    #[test]
+    fn test_countdown_uses_a_single_now() {
+        let config = GameNightConfig::default();
+        // One second before a minute boundary, 25 hours out
+        let now = eastern(2024, 6, 6, 18, 59) + chrono::Duration::seconds(59);
+        let details = format_next_game_night_at(&config, now).unwrap();
+        assert!(details.contains("1 days, 1 hours, 0 minutes, 1 seconds"));
+    }
+
+    #[test]
+    fn test_reminder_rounds_up_minutes() {
+        let config = GameNightConfig::default();
+        let start = eastern(2024, 6, 7, 20, 0);
+        let reminder = format_reminder(&config, start, start - chrono::Duration::seconds(14 * 60 + 50));
+        assert!(reminder.contains("starts in 15 minutes"));
+    }
+
+    #[test]
+    fn test_reminder_template() {
+        let start = eastern(2024, 6, 7, 20, 0);
+        let now = start - chrono::Duration::minutes(60);
+        let config = GameNightConfig {
+            notify_role_id: Some(RoleId::new(42)),
+            reminder_template: "{game} on {date} at {time}, {countdown} to go! {role}".to_string(),
+            ..GameNightConfig::default()
+        };
+        let game = get_next_game_suggestion(local_date(&config, start));
+        assert_eq!(
+            format_reminder(&config, start, now),
+            format!("{} on Friday, June 07 at 8:00 PM, 1 hour to go! <@&42>", game)
+        );
+
+        // Without {role} the ping goes in front, like the default reminder
+        let config = GameNightConfig { reminder_template: "Soon!".to_string(), ..config };
+        assert_eq!(format_reminder(&config, start, now), "<@&42>\nSoon!");
+        let config = GameNightConfig { reminder_template: DEFAULT_REMINDER_TEMPLATE.to_string(), ..config };
+        assert!(format_reminder(&config, start, now).contains("🕐 Friday at 8:00 PM US/Eastern"));
+    }
+
+    #[test]
+    fn test_last_call() {
+        let end = eastern(2024, 6, 8, 0, 0);
+        let now = end - chrono::Duration::seconds(29 * 60 + 30);
+        assert_eq!(format_last_call(&GameNightConfig::default(), end, now), "🔔 **Last call!** Game night ends in 30 minutes.");
+        let config = GameNightConfig { locale: Locale::Spanish, ..GameNightConfig::default() };
+        assert!(format_last_call(&config, end, now).contains("30 minutos"));
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_local_spring_forward_gap() {
+        // 2:30AM on March 10th 2024 doesn't exist in US/Eastern
+        let local = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        // Rolls forward to 3:00AM EDT
+        assert_eq!(resolve_local(chrono_tz::US::Eastern, local).unwrap(), utc(2024, 3, 10, 7, 0));
+    }
+
+    #[test]
+    fn test_resolve_local_fall_back_ambiguity() {
+        // 1:30AM on November 3rd 2024 happens twice in US/Eastern
+        let local = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap().and_hms_opt(1, 30, 0).unwrap();
+        // Picks the first occurrence, 1:30AM EDT
+        assert_eq!(resolve_local(chrono_tz::US::Eastern, local).unwrap(), utc(2024, 11, 3, 5, 30));
+    }
+
+    #[test]
+    fn test_session_window_on_dst_transition() {
+        let config = GameNightConfig {
+            day_of_week: Weekday::Sun,
+            start_time: NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+            length: SessionLength::Minutes(2 * 60),
+            ..GameNightConfig::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let (start, end) = session_window(&config, date).unwrap();
+        assert_eq!(start, utc(2024, 3, 10, 7, 0));
+        assert_eq!(end, utc(2024, 3, 10, 9, 0));
+    }
+
+    #[test]
+    fn test_formatting_in_dst_gap() {
+        // 2:30 AM doesn't exist on March 10th 2024, so that night starts at 3:00 AM
+        let config = GameNightConfig {
+            day_of_week: Weekday::Sun,
+            start_time: NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+            ..GameNightConfig::default()
+        };
+        let now = eastern(2024, 3, 9, 12, 0);
+        let details = format_next_game_night_at(&config, now).unwrap();
+        assert!(details.contains("Sunday, March 10, 2024") && details.contains("3:00 AM"), "{}", details);
+        let status = format_game_night_status_at(&config, now).unwrap();
+        assert!(status.contains("3:00 AM"), "{}", status);
+    }
+
+    #[test]
+    fn test_resolve_local_skipped_day() {
+        // Samoa jumped the date line and skipped December 30th 2011 entirely
+        let local = NaiveDate::from_ymd_opt(2011, 12, 30).unwrap().and_hms_opt(20, 0, 0).unwrap();
+        assert_eq!(
+            resolve_local(chrono_tz::Pacific::Apia, local),
+            Err(ScheduleError::NoLocalTime { timezone: chrono_tz::Pacific::Apia, local })
+        );
+    }
+
+    #[test]
+    fn test_formatting_on_skipped_day() {
+        // The next Friday never happens on the clock, which fails instead of panicking
+        let config = GameNightConfig { timezone: chrono_tz::Pacific::Apia, ..GameNightConfig::default() };
+        let now = utc(2011, 12, 29, 12, 0);
+        let local = NaiveDate::from_ymd_opt(2011, 12, 30).unwrap().and_time(config.start_time);
+        let error = ScheduleError::NoLocalTime { timezone: chrono_tz::Pacific::Apia, local };
+        assert_eq!(get_next_game_night_at(&config, now), Err(error.clone()));
+        assert_eq!(format_next_game_night_at(&config, now), Err(error.clone()));
+        assert_eq!(format_game_night_status_at(&config, now), Err(error.clone()));
+        assert_eq!(error.to_string(), "Friday, December 30, 2011 at 20:00 never happens in Pacific/Apia");
+
+        // A week later it's back to normal
+        assert!(format_next_game_night_at(&config, utc(2012, 1, 2, 12, 0)).is_ok());
     }
 }
@@ -0,0 +1,127 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::json;
+use serenity::gateway::{ConnectionStage, ShardManager};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use tracing::{error, info};
+
+use crate::game_night::next_game_night_json;
+use crate::state::{DefaultScheduleKey, GuildSchedulesKey};
+
+// Port the server listens on when HEALTH_PORT isn't set
+pub const DEFAULT_PORT: u16 = 8080;
+
+// What the server needs to answer: the bot's state for /next, and the shards
+// for whether it's connected
+#[derive(Clone)]
+struct ServerState {
+    data: Arc<RwLock<TypeMap>>,
+    shard_manager: Arc<ShardManager>,
+}
+
+// The endpoints there are
+#[derive(Debug, PartialEq)]
+enum Route {
+    Health,
+    // Next game night of the given guild's schedule, or the default one
+    Next(Option<GuildId>),
+    NotFound,
+}
+
+fn route(method: &Method, path: &str, query: Option<&str>) -> Route {
+    if method != Method::GET {
+        return Route::NotFound;
+    }
+    match path {
+        "/health" => Route::Health,
+        "/next" => {
+            let guild = query
+                .into_iter()
+                .flat_map(|query| query.split('&'))
+                .find_map(|pair| pair.strip_prefix("guild="));
+            match guild.map(str::parse::<u64>) {
+                None => Route::Next(None),
+                Some(Ok(guild_id)) if guild_id != 0 => Route::Next(Some(GuildId::new(guild_id))),
+                Some(_) => Route::NotFound,
+            }
+        }
+        _ => Route::NotFound,
+    }
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("response parts are valid")
+}
+
+async fn respond(state: ServerState, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match route(request.method(), request.uri().path(), request.uri().query()) {
+        Route::Health => {
+            let runners = state.shard_manager.runners.lock().await;
+            let connected = runners.values().filter(|runner| runner.stage == ConnectionStage::Connected).count();
+            json_response(
+                StatusCode::OK,
+                json!({ "status": "ok", "connected": connected > 0 && connected == runners.len(), "shards": runners.len() }),
+            )
+        }
+        Route::Next(guild_id) => {
+            let data = state.data.read().await;
+            let default = data.get::<DefaultScheduleKey>().expect("default schedule is inserted at startup");
+            let schedules = data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup");
+            let schedules = schedules.read().await;
+            let schedule = guild_id.and_then(|guild_id| schedules.get(&guild_id)).unwrap_or(default);
+
+            match schedule.next_occurrence().and_then(|(index, _)| next_game_night_json(&schedule.nights[index])) {
+                Ok(json) => json_response(StatusCode::OK, json),
+                Err(why) => json_response(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": why.to_string() })),
+            }
+        }
+        Route::NotFound => json_response(StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+    };
+    Ok(response)
+}
+
+// Serve GET /health and GET /next on `port` for as long as the bot runs
+pub async fn serve(port: u16, data: Arc<RwLock<TypeMap>>, shard_manager: Arc<ShardManager>) {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let state = ServerState { data, shard_manager };
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| respond(state.clone(), request))) }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(why) => {
+            error!("Error starting the health server on {}: {}", addr, why);
+            return;
+        }
+    };
+    info!("Health server listening on {}", addr);
+    if let Err(why) = server.serve(make_service).await {
+        error!("Health server error: {}", why);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route(&Method::GET, "/health", None), Route::Health);
+        assert_eq!(route(&Method::GET, "/next", None), Route::Next(None));
+        assert_eq!(route(&Method::GET, "/next", Some("guild=42")), Route::Next(Some(GuildId::new(42))));
+        assert_eq!(route(&Method::GET, "/next", Some("guild=abc")), Route::NotFound);
+        assert_eq!(route(&Method::POST, "/health", None), Route::NotFound);
+        assert_eq!(route(&Method::GET, "/", None), Route::NotFound);
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+#[cfg(feature = "prefix-commands")]
+use serenity::model::mention::Mentionable;
+
+#[cfg(feature = "prefix-commands")]
+use crate::i18n::{Locale, Text, fill, long_date, plural, tr};
+#[cfg(feature = "prefix-commands")]
+use crate::messaging::to_discord_timestamp;
+use crate::rsvp::{GuildRsvps, RsvpStatus};
+
+// Most sessions a guild's history keeps, oldest dropped first
+const MAX_RECORDS: usize = 100;
+
+// A game night that actually happened
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct GameNightRecord {
+    pub start: DateTime<Utc>,
+    pub game: String,
+    // Members who RSVP'd yes
+    pub attendees: usize,
+    // Who they were. Sessions recorded before members were kept have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attendee_ids: Option<Vec<UserId>>,
+}
+
+impl GameNightRecord {
+    // Record of the session starting at `start`, counting whoever RSVP'd yes
+    // for that session
+    pub fn new(start: DateTime<Utc>, game: &str, rsvps: Option<&GuildRsvps>) -> Self {
+        let attendee_ids = attendees(start, rsvps);
+        Self {
+            start,
+            game: game.to_string(),
+            attendees: attendee_ids.len(),
+            attendee_ids: Some(attendee_ids),
+        }
+    }
+}
+
+// Add a finished session to the guild's history. The same session is only
+// recorded once.
+pub fn record_completed_session(
+    history: &mut HashMap<GuildId, Vec<GameNightRecord>>,
+    guild_id: GuildId,
+    record: GameNightRecord,
+) -> bool {
+    let records = history.entry(guild_id).or_default();
+    if records.iter().any(|existing| existing.start == record.start) {
+        return false;
+    }
+    records.push(record);
+    records.sort_by_key(|record| record.start);
+    if records.len() > MAX_RECORDS {
+        records.drain(..records.len() - MAX_RECORDS);
+    }
+    true
+}
+
+// Yes RSVPs for the session starting at `start`, the members counted as
+// having attended it
+pub fn attendees(start: DateTime<Utc>, rsvps: Option<&GuildRsvps>) -> Vec<UserId> {
+    let mut attendees: Vec<UserId> = rsvps
+        .filter(|rsvps| rsvps.session_start == start)
+        .map(|rsvps| {
+            rsvps.responses
+                .iter()
+                .filter(|(_, status)| **status == RsvpStatus::Yes)
+                .map(|(user_id, _)| *user_id)
+                .collect()
+        })
+        .unwrap_or_default();
+    attendees.sort();
+    attendees
+}
+
+// A member's run of game nights attended back to back
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Streak {
+    pub last_attended: NaiveDate,
+    pub streak: u32,
+}
+
+// Carry everyone's streaks over to the session on `date`. `previous` is the
+// session recorded before it, so nights that were skipped or never happened
+// don't break anyone's streak. Members who were at the previous one keep
+// going, everyone else who attended starts over at 1, and whoever had a
+// streak but didn't attend loses it.
+pub fn update_streaks(
+    streaks: &mut HashMap<UserId, Streak>,
+    previous: Option<NaiveDate>,
+    date: NaiveDate,
+    attendees: &[UserId],
+) {
+    for (user_id, streak) in streaks.iter_mut() {
+        if streak.last_attended < date && !attendees.contains(user_id) {
+            streak.streak = 0;
+        }
+    }
+    for user_id in attendees {
+        let streak = streaks.entry(*user_id).or_insert(Streak {
+            last_attended: date,
+            streak: 0,
+        });
+        if streak.last_attended == date && streak.streak > 0 {
+            continue;
+        }
+        streak.streak = if previous.is_some() && Some(streak.last_attended) == previous {
+            streak.streak + 1
+        } else {
+            1
+        };
+        streak.last_attended = date;
+    }
+}
+
+// Format for !streak
+#[cfg(feature = "prefix-commands")]
+pub fn format_streak(user_id: UserId, streak: Option<&Streak>, locale: Locale) -> String {
+    let member = user_id.mention().to_string();
+    match streak {
+        Some(streak) if streak.streak > 0 => fill(
+            tr(locale, Text::StreakGoing),
+            &[
+                member,
+                plural(locale, streak.streak.into(), Text::GameNight, Text::GameNights),
+                long_date(locale, streak.last_attended),
+            ],
+        ),
+        Some(streak) => fill(tr(locale, Text::StreakBroken), &[member, long_date(locale, streak.last_attended)]),
+        None => fill(tr(locale, Text::StreakNever), &[member]),
+    }
+}
+
+// Format for !attendancerate - how many of the last `count` sessions the
+// member RSVP'd yes to. Only sessions that happened are recorded, so skipped
+// weeks never count against anyone, and neither do sessions recorded before
+// members were kept.
+#[cfg(feature = "prefix-commands")]
+pub fn format_attendance_rate(user_id: UserId, records: &[GameNightRecord], count: usize, locale: Locale) -> String {
+    let recent: Vec<&Vec<UserId>> = records.iter().rev().filter_map(|record| record.attendee_ids.as_ref()).take(count).collect();
+    if recent.is_empty() {
+        return tr(locale, Text::NoAttendance).to_string();
+    }
+    let total = recent.len();
+    let attended = recent.iter().filter(|attendees| attendees.contains(&user_id)).count();
+    if attended == 0 {
+        return fill(tr(locale, Text::AttendanceNone), &[user_id.mention().to_string(), total.to_string()]);
+    }
+    fill(
+        tr(locale, Text::AttendanceRate),
+        &[
+            user_id.mention().to_string(),
+            attended.to_string(),
+            plural(locale, total as i64, Text::RecentGameNight, Text::RecentGameNights),
+            ((attended * 100 + total / 2) / total).to_string(),
+        ],
+    )
+}
+
+// Format for !history - the last `count` sessions, newest first
+#[cfg(feature = "prefix-commands")]
+pub fn format_history(records: &[GameNightRecord], count: usize, locale: Locale) -> String {
+    if records.is_empty() {
+        return tr(locale, Text::NoHistory).to_string();
+    }
+
+    let lines: Vec<String> = records
+        .iter()
+        .rev()
+        .take(count)
+        .map(|record| {
+            fill(
+                tr(locale, Text::HistoryLine),
+                &[to_discord_timestamp(record.start, 'D'), record.game.clone(), record.attendees.to_string()],
+            )
+        })
+        .collect();
+    fill(tr(locale, Text::HistoryTitle), &[lines.join("\n")])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serenity::model::id::UserId;
+
+    fn start(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_record_counts_yes_rsvps_for_that_session() {
+        let mut responses = HashMap::new();
+        responses.insert(UserId::new(1), RsvpStatus::Yes);
+        responses.insert(UserId::new(2), RsvpStatus::Maybe);
+        responses.insert(UserId::new(3), RsvpStatus::Yes);
+        let rsvps = GuildRsvps {
+            session_start: start(8),
+            session_end: start(8) + chrono::Duration::hours(4),
+            responses,
+        };
+
+        assert_eq!(GameNightRecord::new(start(8), "Valheim", Some(&rsvps)).attendees, 2);
+        // RSVPs for another session don't count
+        assert_eq!(GameNightRecord::new(start(15), "Valheim", Some(&rsvps)).attendees, 0);
+        assert_eq!(GameNightRecord::new(start(8), "Valheim", None).attendees, 0);
+    }
+
+    #[test]
+    fn test_record_completed_session_once() {
+        let mut history = HashMap::new();
+        let guild_id = GuildId::new(1);
+        let record = GameNightRecord::new(start(8), "CS2", None);
+
+        assert!(record_completed_session(&mut history, guild_id, record.clone()));
+        assert!(!record_completed_session(&mut history, guild_id, record));
+        assert_eq!(history[&guild_id].len(), 1);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_history_newest_first() {
+        let records = vec![
+            GameNightRecord::new(start(1), "Minecraft", None),
+            GameNightRecord::new(start(8), "Terraria", None),
+            GameNightRecord::new(start(15), "Valheim", None),
+        ];
+
+        let reply = format_history(&records, 2, Locale::English);
+        assert!(reply.find("Valheim").unwrap() < reply.find("Terraria").unwrap());
+        assert!(!reply.contains("Minecraft"));
+        assert_eq!(format_history(&[], 5, Locale::English), "📜 No game nights recorded yet.");
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    #[test]
+    fn test_attendees_are_yes_rsvps_for_that_session() {
+        let mut responses = HashMap::new();
+        responses.insert(UserId::new(3), RsvpStatus::Yes);
+        responses.insert(UserId::new(2), RsvpStatus::No);
+        responses.insert(UserId::new(1), RsvpStatus::Yes);
+        let rsvps = GuildRsvps {
+            session_start: start(8),
+            session_end: start(8) + chrono::Duration::hours(4),
+            responses,
+        };
+
+        assert_eq!(attendees(start(8), Some(&rsvps)), vec![UserId::new(1), UserId::new(3)]);
+        assert!(attendees(start(15), Some(&rsvps)).is_empty());
+    }
+
+    #[test]
+    fn test_streaks_grow_and_reset() {
+        let mut streaks = HashMap::new();
+        let alice = UserId::new(1);
+        let bob = UserId::new(2);
+
+        update_streaks(&mut streaks, None, date(1), &[alice, bob]);
+        update_streaks(&mut streaks, Some(date(1)), date(8), &[alice]);
+        assert_eq!(streaks[&alice], Streak { last_attended: date(8), streak: 2 });
+        assert_eq!(streaks[&bob], Streak { last_attended: date(1), streak: 0 });
+
+        // Back after missing one starts over
+        update_streaks(&mut streaks, Some(date(8)), date(15), &[alice, bob]);
+        assert_eq!(streaks[&alice].streak, 3);
+        assert_eq!(streaks[&bob], Streak { last_attended: date(15), streak: 1 });
+
+        // Recording the same session twice doesn't count it twice
+        update_streaks(&mut streaks, Some(date(8)), date(15), &[alice, bob]);
+        assert_eq!(streaks[&alice].streak, 3);
+    }
+
+    #[test]
+    fn test_skipped_week_keeps_streak() {
+        let mut streaks = HashMap::new();
+        let alice = UserId::new(1);
+
+        update_streaks(&mut streaks, None, date(1), &[alice]);
+        // The 8th was skipped, so the session before the 15th was the 1st
+        update_streaks(&mut streaks, Some(date(1)), date(15), &[alice]);
+        assert_eq!(streaks[&alice], Streak { last_attended: date(15), streak: 2 });
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_attendance_rate() {
+        let alice = UserId::new(1);
+        let record = |day, ids: &[u64]| GameNightRecord {
+            start: start(day),
+            game: "Valheim".to_string(),
+            attendees: ids.len(),
+            attendee_ids: Some(ids.iter().map(|id| UserId::new(*id)).collect()),
+        };
+        let records = [
+            // Recorded before members were kept, so it doesn't count either way
+            GameNightRecord { attendee_ids: None, ..record(1, &[1]) },
+            record(8, &[1, 2]),
+            record(15, &[2]),
+            record(22, &[1]),
+        ];
+
+        assert_eq!(
+            format_attendance_rate(alice, &records, 10, Locale::English),
+            "📊 <@1> RSVP'd yes to 2/3 recent game nights, **67%**."
+        );
+        assert_eq!(
+            format_attendance_rate(alice, &records, 1, Locale::English),
+            "📊 <@1> RSVP'd yes to 1/1 recent game night, **100%**."
+        );
+        assert_eq!(
+            format_attendance_rate(UserId::new(3), &records, 10, Locale::English),
+            "📊 <@3> hasn't RSVP'd yes to any recent game night yet (0/3)."
+        );
+        assert_eq!(
+            format_attendance_rate(alice, &records[..1], 10, Locale::English),
+            "📊 No game nights recorded yet."
+        );
+        assert_eq!(format_attendance_rate(alice, &[], 10, Locale::English), "📊 No game nights recorded yet.");
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_streak() {
+        let alice = UserId::new(1);
+        let streak = Streak { last_attended: date(15), streak: 3 };
+
+        assert_eq!(
+            format_streak(alice, Some(&streak), Locale::English),
+            "🔥 <@1> has been to 3 game nights in a row, most recently on June 15, 2024."
+        );
+        let lost = Streak { last_attended: date(1), streak: 0 };
+        assert!(format_streak(alice, Some(&lost), Locale::English).contains("no streak going"));
+        assert_eq!(format_streak(alice, None, Locale::English), "🧊 <@1> hasn't been to a game night yet.");
+        assert_eq!(
+            format_streak(alice, Some(&streak), Locale::Spanish),
+            "🔥 <@1> lleva una racha de 3 noches de juegos, la última el 15 de junio de 2024."
+        );
+    }
+}
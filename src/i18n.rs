@@ -0,0 +1,791 @@
+use chrono::Weekday;
+#[cfg(feature = "prefix-commands")]
+use chrono::{Datelike, NaiveDate};
+
+// Language the bot replies in, picked per guild with !setlocale
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::Spanish];
+
+    // Code used in config files and !setlocale, e.g. "es"
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Locale> {
+        Locale::ALL.into_iter().find(|locale| locale.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    // The language's name in itself, for confirming a change
+    #[cfg(feature = "prefix-commands")]
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::Spanish => "Español",
+        }
+    }
+}
+
+// Every translated message. Templates fill their `{}` in order with fill().
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Text {
+    #[cfg(feature = "prefix-commands")]
+    Help,
+    // Units, each taking the count
+    Hour,
+    Hours,
+    Minute,
+    Minutes,
+    // Countdown of days, hours, minutes and seconds
+    Countdown,
+    // Session length of a night with an end time, and what follows a next-day one
+    Until,
+    NextDay,
+    // !gamenight as text
+    StatusLive,
+    StatusOver,
+    Tomorrow,
+    InDays,
+    StatusSoon,
+    StatusToday,
+    StatusTomorrow,
+    StatusLater,
+    // !gamenight and !nextgame embeds
+    LiveTitle,
+    LiveDescription,
+    LiveNow,
+    OverTitle,
+    NextTitle,
+    DetailsTitle,
+    FieldDate,
+    FieldStartTime,
+    FieldDuration,
+    FieldCountdown,
+    FieldTimeRemaining,
+    FieldEndsAt,
+    FieldYourTime,
+    FieldOtherTimezones,
+    FieldPlannedGame,
+    ProTip,
+    // !nextgame as text, and the caller's own and the other timezones parts of it
+    NextGameDetails,
+    YourTime,
+    OtherTimezones,
+    // !timeleft
+    #[cfg(feature = "prefix-commands")]
+    TimeLeft,
+    #[cfg(feature = "prefix-commands")]
+    NotRunning,
+    // Posted instead of the live announcement when too few are going
+    NeedMorePlayers,
+    MorePlayer,
+    MorePlayers,
+    // Posted near the end of a live session
+    LastCall,
+    // /help
+    SlashHelp,
+    // /settings
+    SettingsOutsideGuild,
+    UnknownSetting,
+    SettingsNeedManageServer,
+    SettingsChanged,
+    // !isgamenight
+    IsGameNightYes,
+    IsGameNightNo,
+    // !gamenightconfig, with which days a night falls on
+    ConfigTitle,
+    ConfigTitleOf,
+    ConfigDay,
+    ConfigTimezone,
+    ConfigChannel,
+    ConfigChannelNotSet,
+    ConfigNotifyRole,
+    ConfigMinPlayers,
+    ConfigNone,
+    ConfigAnnouncements,
+    ConfigOn,
+    ConfigPaused,
+    EveryWeek,
+    EveryOtherWeek,
+    EvenWeeks,
+    OddWeeks,
+    NthOfMonth,
+    // !countdown
+    #[cfg(feature = "prefix-commands")]
+    CountdownLive,
+    #[cfg(feature = "prefix-commands")]
+    CountdownNext,
+    // !until
+    #[cfg(feature = "prefix-commands")]
+    UntilPast,
+    #[cfg(feature = "prefix-commands")]
+    UntilCountdown,
+    // !tz, and how far apart the two days are
+    #[cfg(feature = "prefix-commands")]
+    TimezoneConversion,
+    #[cfg(feature = "prefix-commands")]
+    TheNextDay,
+    #[cfg(feature = "prefix-commands")]
+    TheDayBefore,
+    #[cfg(feature = "prefix-commands")]
+    DaysLater,
+    #[cfg(feature = "prefix-commands")]
+    DaysEarlier,
+    // !gamenight +n
+    #[cfg(feature = "prefix-commands")]
+    GameNightAhead,
+    // !nextgames, !lineup and !specialnights, with a line for each session
+    #[cfg(feature = "prefix-commands")]
+    NextGamesTitle,
+    #[cfg(feature = "prefix-commands")]
+    LineupTitle,
+    #[cfg(feature = "prefix-commands")]
+    SpecialNightsTitle,
+    #[cfg(feature = "prefix-commands")]
+    NoSpecialNights,
+    #[cfg(feature = "prefix-commands")]
+    SessionLine,
+    #[cfg(feature = "prefix-commands")]
+    LineupLine,
+    // !history, !streak and !attendancerate
+    #[cfg(feature = "prefix-commands")]
+    HistoryTitle,
+    #[cfg(feature = "prefix-commands")]
+    HistoryLine,
+    #[cfg(feature = "prefix-commands")]
+    NoHistory,
+    #[cfg(feature = "prefix-commands")]
+    StreakGoing,
+    #[cfg(feature = "prefix-commands")]
+    StreakBroken,
+    #[cfg(feature = "prefix-commands")]
+    StreakNever,
+    #[cfg(feature = "prefix-commands")]
+    GameNight,
+    #[cfg(feature = "prefix-commands")]
+    GameNights,
+    #[cfg(feature = "prefix-commands")]
+    NoAttendance,
+    #[cfg(feature = "prefix-commands")]
+    AttendanceNone,
+    #[cfg(feature = "prefix-commands")]
+    AttendanceRate,
+    #[cfg(feature = "prefix-commands")]
+    RecentGameNight,
+    #[cfg(feature = "prefix-commands")]
+    RecentGameNights,
+}
+
+pub fn tr(locale: Locale, text: Text) -> &'static str {
+    match locale {
+        Locale::English => english(text),
+        Locale::Spanish => spanish(text),
+    }
+}
+
+// Fill a template's `{}` with `args`, in order
+pub fn fill(template: &str, args: &[String]) -> String {
+    let mut pieces = template.split("{}");
+    let mut filled = pieces.next().unwrap_or_default().to_string();
+    for (index, piece) in pieces.enumerate() {
+        filled.push_str(args.get(index).map(String::as_str).unwrap_or_default());
+        filled.push_str(piece);
+    }
+    filled
+}
+
+// A count with its unit, e.g. "1 hour" or "2 hours"
+pub fn plural(locale: Locale, count: i64, one: Text, many: Text) -> String {
+    fill(tr(locale, if count == 1 { one } else { many }), &[count.to_string()])
+}
+
+pub fn weekday_name(locale: Locale, weekday: Weekday) -> &'static str {
+    const SPANISH: [&str; 7] = ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"];
+    const ENGLISH: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+    let index = weekday.num_days_from_monday() as usize;
+    match locale {
+        Locale::English => ENGLISH[index],
+        Locale::Spanish => SPANISH[index],
+    }
+}
+
+// Name of a month from 1 to 12
+pub fn month_name(locale: Locale, month: u32) -> &'static str {
+    const ENGLISH: [&str; 12] = [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+        "December",
+    ];
+    const SPANISH: [&str; 12] = [
+        "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre", "noviembre",
+        "diciembre",
+    ];
+    let index = month.clamp(1, 12) as usize - 1;
+    match locale {
+        Locale::English => ENGLISH[index],
+        Locale::Spanish => SPANISH[index],
+    }
+}
+
+// Which week of the month, from 1 to 5, e.g. "3rd" or "3.º"
+pub fn ordinal(locale: Locale, week: u8) -> String {
+    match locale {
+        Locale::English => {
+            let suffix = match week {
+                1 => "st",
+                2 => "nd",
+                3 => "rd",
+                _ => "th",
+            };
+            format!("{}{}", week, suffix)
+        }
+        Locale::Spanish => format!("{}.º", week),
+    }
+}
+
+// A date written out, e.g. "June 15, 2024" or "15 de junio de 2024"
+#[cfg(feature = "prefix-commands")]
+pub fn long_date(locale: Locale, date: NaiveDate) -> String {
+    let month = month_name(locale, date.month());
+    match locale {
+        Locale::English => format!("{} {:02}, {}", month, date.day(), date.year()),
+        Locale::Spanish => format!("{} de {} de {}", date.day(), month, date.year()),
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        #[cfg(feature = "prefix-commands")]
+        Text::Help => "**Available Commands:**\n\
+            `!ping` - Test if bot is responsive\n\
+            `!about` - Show the bot's version, uptime and how many servers it's in\n\
+            `!gamenight [+n]` - Show game night status, or the night n after the next one\n\
+            `!gamenightconfig` - Show this server's game night settings\n\
+            `!nextgame [timezone]` - Show when the next game night is, optionally in another timezone\n\
+            `!nextgames [n]` - List the next few game nights\n\
+            `!lineup [n]` - See which game is planned for each of the next few game nights\n\
+            `!isgamenight` - Check if game night is happening now\n\
+            `!nextgamejson` - The next game night as JSON, for dashboards and other bots\n\
+            `!countdown` - Live countdown to the next game night\n\
+            `!livecountdown` - Post a countdown that updates itself every minute for up to an hour\n\
+            `!remindme <minutes>` - Get a DM that many minutes before the next game night\n\
+            `!timeleft` - How long the current game night has left\n\
+            `!until <YYYY-MM-DD> [HH:MM]` - Count down to any date\n\
+            `!setgamenight <day> <HH:MM> <hours> [timezone]` - Move game night (admins)\n\
+            `!setchannel [#channel]` - Choose where reminders and announcements are posted (Manage Channels)\n\
+            `!setnotifyrole @Role` - Choose the role pinged for game night (admins)\n\
+            `!pause` / `!resume` - Hold back reminders and announcements, or let them go out again (admins)\n\
+            `!setreminder <template>` - Write your own reminder text with {countdown}, {game}, {date} and more (admins)\n\
+            `!setlocale <code>` - Choose the language the bot replies in, e.g. `!setlocale es` (admins)\n\
+            `!setemoji <slot> <emoji>` - Use your own emoji for live, game or date in status replies, or `default` (admins)\n\
+            `!setcolor live|upcoming <#hex>` - Change the colour of the live or upcoming embeds, or `default` (admins)\n\
+            `!override <YYYY-MM-DD> <HH:MM>` - Move this week's game night, just once (admins)\n\
+            `!clearoverride` - Undo `!override` (admins)\n\
+            `!skip` - Cancel the next game night (admins)\n\
+            `!unskip` - Bring back the soonest skipped game night (admins)\n\
+            `!addgame <name>` - Add a game to the server's game library\n\
+            `!removegame <name>` - Remove a game from the server's game library\n\
+            `!gameweight <name> <weight>` - Make a library game come up more or less often, from 1 to 10\n\
+            `!suggest` - Pick a few games to play\n\
+            `!vote [minutes]` - Vote on tonight's game\n\
+            `!poll \"Question\" option 1 | option 2 | ...` - Ask the channel anything, with up to 10 options\n\
+            `!specialnights [weeks]` - List upcoming holiday-themed game nights\n\
+            `!holiday <holiday> theme|skip|ignore` - Choose what game night does on a holiday (admins)\n\
+            `!addspecial <MM-DD> <message>` - Announce game nights on a date with your own message (admins)\n\
+            `!disablespecial <MM-DD>` / `!enablespecial <MM-DD>` - Turn the special night on a date off or back on (admins)\n\
+            `!calendar` - Get game night as a calendar file\n\
+            `!exportconfig` - Get the schedule as a gamenight.toml file, to keep or reuse elsewhere\n\
+            `!setprefix <prefix>` - Change the command prefix for this server (admins)\n\
+            `!history [n]` - Show the last few game nights\n\
+            `!streak [@user]` - How many game nights you or someone else attended in a row\n\
+            `!attendancerate [@user] [n]` - How many of the last n game nights (10 by default) you or someone else RSVP'd yes to\n\
+            `!tz <HH:MM> <from> <to>` - Convert a time between two timezones\n\
+            `!tzsearch <query>` - Find timezone names to use with `!mytz` and `!tz`, e.g. `!tzsearch berlin`\n\
+            `!mytz <timezone>` - Also show game night times in your own timezone, e.g. `!mytz Europe/Berlin`\n\
+            `!subscribe` / `!unsubscribe` - Start or stop getting game night reminders by DM\n\
+            `!rsvp yes|no|maybe` - Let everyone know if you're coming to the next game night\n\
+            `!attendance` - See who's coming to the next game night\n\
+            `!whoisplaying` - See who's in the game night voice channel\n\
+            `!debug` - Show the schedule exactly as the bot worked it out (bot owner)\n\
+            `!preview <time>` - Show !gamenight and !nextgame as of an RFC 3339 time (bot owner)\n\
+            `!stats` - See how often each command was used since the bot started (bot owner)\n\
+            `!help` - Show this help message",
+        Text::Hour => "{} hour",
+        Text::Hours => "{} hours",
+        Text::Minute => "{} minute",
+        Text::Minutes => "{} minutes",
+        Text::Countdown => "{} days, {} hours, {} minutes, {} seconds",
+        Text::Until => "until {}{}",
+        Text::NextDay => " the next day",
+        Text::StatusLive => "🔴 **GAME NIGHT IS LIVE NOW!** 🔴\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            🎮 We're currently playing!\n\
+            ⏰ Time remaining: {}\n\
+            🔗 Hop in the voice channel!\n\
+            \n\
+            Use `!suggest` to see what we're playing!",
+        Text::StatusOver => "🏁 **Game Night is Over for Today!**\n\
+            🎮 Thanks for playing!\n\
+            📅 Next one: {} ({})\n\
+            🕐 {} {}",
+        Text::Tomorrow => "tomorrow",
+        Text::InDays => "in {} days",
+        Text::StatusSoon => "⏰ **Game Night Starting Soon!**\n\
+            🎮 Tonight at {} {}\n\
+            ⏳ Only {} away!\n\
+            🔔 Get ready to game!",
+        Text::StatusToday => "📅 **Game Night is Today!**\n\
+            🕐 Starting at {} {}\n\
+            ⏳ In {}",
+        Text::StatusTomorrow => "📅 **Game Night is Tomorrow!**\n\
+            🕐 {} at {} {}",
+        Text::StatusLater => "📅 **Next Game Night:**\n\
+            🗓️ {} (in {} days)\n\
+            🕐 {} {}",
+        Text::LiveTitle => "🔴 GAME NIGHT IS LIVE NOW! 🔴",
+        Text::LiveDescription => "🎮 We're currently playing! 🔗 Hop in the voice channel!",
+        Text::LiveNow => "🔴 Game night is live right now!",
+        Text::OverTitle => "🏁 Game Night is Over for Today",
+        Text::NextTitle => "📅 Next Game Night",
+        Text::DetailsTitle => "📅 Next Game Night Details",
+        Text::FieldDate => "🗓️ Date",
+        Text::FieldStartTime => "🕐 Start Time",
+        Text::FieldDuration => "⏱️ Duration",
+        Text::FieldCountdown => "⏳ Countdown",
+        Text::FieldTimeRemaining => "⏰ Time remaining",
+        Text::FieldEndsAt => "🏁 Ends at",
+        Text::FieldYourTime => "🌍 Your Time",
+        Text::FieldOtherTimezones => "🌐 Other Timezones",
+        Text::FieldPlannedGame => "🎮 Planned Game",
+        Text::ProTip => "💡 Pro tip: Set a reminder so you don't miss it!",
+        Text::NextGameDetails => "📅 **Next Game Night Details**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            🗓️ **Date:** {}\n\
+            🕐 **Start Time:** {} {}\n\
+            ⏱️ **Duration:** {}\n\
+            \n\
+            ⏳ **Countdown:**\n\
+            ```\n\
+            {}\n\
+            ```\n\
+            \n\
+            🎮 **Planned Game:** {}\n\
+            {}\
+            \n\
+            💡 **Pro tip:** Set a reminder so you don't miss it!",
+        Text::YourTime => "\n🌍 **Your time:** {}, {} {}\n",
+        Text::OtherTimezones => "\n🌐 **In other timezones:**\n```\n{}\n```\n",
+        #[cfg(feature = "prefix-commands")]
+        Text::TimeLeft => "⏰ Game night has {} left!",
+        #[cfg(feature = "prefix-commands")]
+        Text::NotRunning => "Game night isn't running right now. Use !countdown to see when the next one starts.",
+        Text::NeedMorePlayers => "⏳ Game night needs {} to get going! {} of {} going so far, hit **Going** below if you can make it.",
+        Text::MorePlayer => "{} more player",
+        Text::MorePlayers => "{} more players",
+        Text::LastCall => "🔔 **Last call!** Game night ends in {}.",
+        Text::SlashHelp => "**Available Commands:**\n\
+            `/gamenight` - Show game night status\n\
+            `/nextgame` - Show when the next game night is\n\
+            `/isgamenight` - Check if game night is happening now\n\
+            `/settings view|day|time|timezone` - See or change when game night is (admins)\n\
+            `/help` - Show this help message",
+        Text::SettingsOutsideGuild => "❌ Game night settings can only be changed from inside a server.",
+        Text::UnknownSetting => "❌ Unknown setting.",
+        Text::SettingsNeedManageServer => "❌ You need the Manage Server permission to change game night settings.",
+        Text::SettingsChanged => "✅ Game night is now {} at {} {}.",
+        Text::IsGameNightYes => "Yes! Game night is happening now! 🎮",
+        Text::IsGameNightNo => "No, it's not game night yet. Use !gamenight to see when the next one is.",
+        Text::ConfigTitle => "⚙️ Game Night Settings",
+        Text::ConfigTitleOf => "⚙️ Game Night Settings ({} of {})",
+        Text::ConfigDay => "🗓️ Day",
+        Text::ConfigTimezone => "🌍 Timezone",
+        Text::ConfigChannel => "📣 Announcement Channel",
+        Text::ConfigChannelNotSet => "Not set, reminders are off",
+        Text::ConfigNotifyRole => "🔔 Notify Role",
+        Text::ConfigMinPlayers => "👥 Minimum Players",
+        Text::ConfigNone => "None",
+        Text::ConfigAnnouncements => "⏸️ Announcements",
+        Text::ConfigOn => "On",
+        Text::ConfigPaused => "Paused, `!resume` turns them back on",
+        Text::EveryWeek => "{}, every week",
+        Text::EveryOtherWeek => "{}, every other week",
+        Text::EvenWeeks => "{}, even weeks",
+        Text::OddWeeks => "{}, odd weeks",
+        Text::NthOfMonth => "{}, {} of the month",
+        #[cfg(feature = "prefix-commands")]
+        Text::CountdownLive => "🔴 **Game night is live!** It started {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::CountdownNext => "⏳ **Next game night:** {}\n\
+            🕐 Starts {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::UntilPast => "❌ That's already in the past, pick a date that's still coming up.",
+        #[cfg(feature = "prefix-commands")]
+        Text::UntilCountdown => "⏳ **Countdown to {} at {} {}**\n\
+            ```\n\
+            {}\n\
+            ```",
+        #[cfg(feature = "prefix-commands")]
+        Text::TimezoneConversion => "🕐 **{}** in {} is **{}** in {}{}",
+        #[cfg(feature = "prefix-commands")]
+        Text::TheNextDay => " (the next day)",
+        #[cfg(feature = "prefix-commands")]
+        Text::TheDayBefore => " (the day before)",
+        #[cfg(feature = "prefix-commands")]
+        Text::DaysLater => " ({} days later)",
+        #[cfg(feature = "prefix-commands")]
+        Text::DaysEarlier => " ({} days earlier)",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNightAhead => "📅 **Game Night +{}**\n\
+            🗓️ {} at {} {}\n\
+            ⏳ Starts in {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::NextGamesTitle => "📅 **Next {} Game Nights**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::LineupTitle => "🎲 **Game Night Lineup**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::SpecialNightsTitle => "✨ **Special Game Nights (next {} weeks)**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoSpecialNights => "📅 No special game nights in the next {} weeks.",
+        #[cfg(feature = "prefix-commands")]
+        Text::SessionLine => "🗓️ {} at {} {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::LineupLine => "🗓️ {} at {} - 🎮 {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::HistoryTitle => "📜 **Past Game Nights**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::HistoryLine => "🗓️ {} - 🎮 {} - 👥 {} going",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoHistory => "📜 No game nights recorded yet.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakGoing => "🔥 {} has been to {} in a row, most recently on {}.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakBroken => "🧊 {} has no streak going. Their last game night was on {}.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakNever => "🧊 {} hasn't been to a game night yet.",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNight => "{} game night",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNights => "{} game nights",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoAttendance => "📊 No game nights recorded yet.",
+        #[cfg(feature = "prefix-commands")]
+        Text::AttendanceNone => "📊 {} hasn't RSVP'd yes to any recent game night yet (0/{}).",
+        #[cfg(feature = "prefix-commands")]
+        Text::AttendanceRate => "📊 {} RSVP'd yes to {}/{}, **{}%**.",
+        #[cfg(feature = "prefix-commands")]
+        Text::RecentGameNight => "{} recent game night",
+        #[cfg(feature = "prefix-commands")]
+        Text::RecentGameNights => "{} recent game nights",
+    }
+}
+
+fn spanish(text: Text) -> &'static str {
+    match text {
+        #[cfg(feature = "prefix-commands")]
+        Text::Help => "**Comandos disponibles:**\n\
+            `!ping` - Comprueba si el bot responde\n\
+            `!about` - Muestra la versión del bot, cuánto lleva encendido y en cuántos servidores está\n\
+            `!gamenight [+n]` - Muestra el estado de la noche de juegos, o la noche n después de la próxima\n\
+            `!gamenightconfig` - Muestra la configuración de la noche de juegos del servidor\n\
+            `!nextgame [timezone]` - Muestra cuándo es la próxima noche de juegos, si quieres en otra zona horaria\n\
+            `!nextgames [n]` - Lista las próximas noches de juegos\n\
+            `!lineup [n]` - Mira qué juego toca en cada una de las próximas noches de juegos\n\
+            `!isgamenight` - Comprueba si la noche de juegos es ahora\n\
+            `!nextgamejson` - La próxima noche de juegos en JSON, para paneles y otros bots\n\
+            `!countdown` - Cuenta atrás hasta la próxima noche de juegos\n\
+            `!livecountdown` - Publica una cuenta atrás que se actualiza cada minuto durante hasta una hora\n\
+            `!remindme <minutos>` - Recibe un mensaje directo esos minutos antes de la próxima noche de juegos\n\
+            `!timeleft` - Cuánto le queda a la noche de juegos en curso\n\
+            `!until <YYYY-MM-DD> [HH:MM]` - Cuenta atrás hasta cualquier fecha\n\
+            `!setgamenight <día> <HH:MM> <horas> [zona horaria]` - Cambia la noche de juegos (admins)\n\
+            `!setchannel [#canal]` - Elige dónde se publican los recordatorios y anuncios (Gestionar canales)\n\
+            `!setnotifyrole @Rol` - Elige el rol al que se avisa de la noche de juegos (admins)\n\
+            `!pause` / `!resume` - Pausa los recordatorios y anuncios, o vuelve a activarlos (admins)\n\
+            `!setreminder <plantilla>` - Escribe tu propio texto de recordatorio con {countdown}, {game}, {date} y más (admins)\n\
+            `!setlocale <código>` - Elige el idioma del bot, p. ej. `!setlocale en` (admins)\n\
+            `!setemoji <ranura> <emoji>` - Usa tu propio emoji para live, game o date en los estados, o `default` (admins)\n\
+            `!setcolor live|upcoming <#hex>` - Cambia el color de los embeds en vivo o próximos, o `default` (admins)\n\
+            `!override <YYYY-MM-DD> <HH:MM>` - Mueve la noche de juegos de esta semana, solo una vez (admins)\n\
+            `!clearoverride` - Deshace `!override` (admins)\n\
+            `!skip` - Cancela la próxima noche de juegos (admins)\n\
+            `!unskip` - Recupera la noche de juegos cancelada más cercana (admins)\n\
+            `!addgame <nombre>` - Añade un juego a la biblioteca del servidor\n\
+            `!removegame <nombre>` - Quita un juego de la biblioteca del servidor\n\
+            `!gameweight <nombre> <peso>` - Hace que un juego salga más o menos a menudo, de 1 a 10\n\
+            `!suggest` - Propone algunos juegos\n\
+            `!vote [minutos]` - Vota el juego de esta noche\n\
+            `!poll \"Pregunta\" opción 1 | opción 2 | ...` - Pregunta lo que quieras al canal, con hasta 10 opciones\n\
+            `!specialnights [semanas]` - Lista las próximas noches de juegos temáticas\n\
+            `!holiday <festivo> theme|skip|ignore` - Elige qué pasa con la noche de juegos en un festivo (admins)\n\
+            `!addspecial <MM-DD> <mensaje>` - Anuncia las noches de juegos de una fecha con tu propio mensaje (admins)\n\
+            `!disablespecial <MM-DD>` / `!enablespecial <MM-DD>` - Desactiva o reactiva la noche especial de una fecha (admins)\n\
+            `!calendar` - Descarga la noche de juegos como archivo de calendario\n\
+            `!exportconfig` - Descarga el horario como archivo gamenight.toml, para guardarlo o usarlo en otro sitio\n\
+            `!setprefix <prefijo>` - Cambia el prefijo de los comandos en este servidor (admins)\n\
+            `!history [n]` - Muestra las últimas noches de juegos\n\
+            `!streak [@usuario]` - Cuántas noches de juegos seguidas has venido tú u otra persona\n\
+            `!attendancerate [@usuario] [n]` - A cuántas de las últimas n noches (10 por defecto) dijiste que sí tú u otra persona\n\
+            `!tz <HH:MM> <desde> <hasta>` - Convierte una hora entre dos zonas horarias\n\
+            `!tzsearch <búsqueda>` - Busca nombres de zonas horarias para `!mytz` y `!tz`, p. ej. `!tzsearch madrid`\n\
+            `!mytz <zona horaria>` - Muestra también las horas en tu zona horaria, p. ej. `!mytz Europe/Madrid`\n\
+            `!subscribe` / `!unsubscribe` - Empieza o deja de recibir los recordatorios por mensaje directo\n\
+            `!rsvp yes|no|maybe` - Avisa si vienes a la próxima noche de juegos\n\
+            `!attendance` - Mira quién viene a la próxima noche de juegos\n\
+            `!whoisplaying` - Mira quién está en el canal de voz de la noche de juegos\n\
+            `!debug` - Muestra el horario tal como lo calcula el bot (dueño del bot)\n\
+            `!preview <time>` - Muestra !gamenight y !nextgame en una hora RFC 3339 (dueño del bot)\n\
+            `!stats` - Cuántas veces se usó cada comando desde que arrancó el bot (dueño del bot)\n\
+            `!help` - Muestra esta ayuda",
+        Text::Hour => "{} hora",
+        Text::Hours => "{} horas",
+        Text::Minute => "{} minuto",
+        Text::Minutes => "{} minutos",
+        Text::Countdown => "{} días, {} horas, {} minutos, {} segundos",
+        Text::Until => "hasta las {}{}",
+        Text::NextDay => " del día siguiente",
+        Text::StatusLive => "🔴 **¡LA NOCHE DE JUEGOS ESTÁ EN MARCHA!** 🔴\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            🎮 ¡Estamos jugando!\n\
+            ⏰ Tiempo restante: {}\n\
+            🔗 ¡Únete al canal de voz!\n\
+            \n\
+            ¡Usa `!suggest` para ver a qué jugamos!",
+        Text::StatusOver => "🏁 **¡La noche de juegos ha terminado por hoy!**\n\
+            🎮 ¡Gracias por jugar!\n\
+            📅 La próxima: {} ({})\n\
+            🕐 {} {}",
+        Text::Tomorrow => "mañana",
+        Text::InDays => "en {} días",
+        Text::StatusSoon => "⏰ **¡La noche de juegos empieza pronto!**\n\
+            🎮 Esta noche a las {} {}\n\
+            ⏳ ¡Solo faltan {}!\n\
+            🔔 ¡Prepárate para jugar!",
+        Text::StatusToday => "📅 **¡Hoy hay noche de juegos!**\n\
+            🕐 Empieza a las {} {}\n\
+            ⏳ En {}",
+        Text::StatusTomorrow => "📅 **¡Mañana hay noche de juegos!**\n\
+            🕐 El {} a las {} {}",
+        Text::StatusLater => "📅 **Próxima noche de juegos:**\n\
+            🗓️ {} (en {} días)\n\
+            🕐 {} {}",
+        Text::LiveTitle => "🔴 ¡LA NOCHE DE JUEGOS ESTÁ EN MARCHA! 🔴",
+        Text::LiveDescription => "🎮 ¡Estamos jugando! 🔗 ¡Únete al canal de voz!",
+        Text::LiveNow => "🔴 ¡La noche de juegos está en marcha!",
+        Text::OverTitle => "🏁 La noche de juegos ha terminado por hoy",
+        Text::NextTitle => "📅 Próxima noche de juegos",
+        Text::DetailsTitle => "📅 Detalles de la próxima noche de juegos",
+        Text::FieldDate => "🗓️ Fecha",
+        Text::FieldStartTime => "🕐 Hora de inicio",
+        Text::FieldDuration => "⏱️ Duración",
+        Text::FieldCountdown => "⏳ Cuenta atrás",
+        Text::FieldTimeRemaining => "⏰ Tiempo restante",
+        Text::FieldEndsAt => "🏁 Termina a las",
+        Text::FieldYourTime => "🌍 Tu hora",
+        Text::FieldOtherTimezones => "🌐 Otras zonas horarias",
+        Text::FieldPlannedGame => "🎮 Juego previsto",
+        Text::ProTip => "💡 Consejo: ¡ponte un recordatorio para no perdértela!",
+        Text::NextGameDetails => "📅 **Detalles de la próxima noche de juegos**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            🗓️ **Fecha:** {}\n\
+            🕐 **Hora de inicio:** {} {}\n\
+            ⏱️ **Duración:** {}\n\
+            \n\
+            ⏳ **Cuenta atrás:**\n\
+            ```\n\
+            {}\n\
+            ```\n\
+            \n\
+            🎮 **Juego previsto:** {}\n\
+            {}\
+            \n\
+            💡 **Consejo:** ¡ponte un recordatorio para no perdértela!",
+        Text::YourTime => "\n🌍 **Tu hora:** {}, {} {}\n",
+        Text::OtherTimezones => "\n🌐 **En otras zonas horarias:**\n```\n{}\n```\n",
+        #[cfg(feature = "prefix-commands")]
+        Text::TimeLeft => "⏰ ¡A la noche de juegos le quedan {}!",
+        #[cfg(feature = "prefix-commands")]
+        Text::NotRunning => "Ahora no hay noche de juegos. Usa !countdown para ver cuándo empieza la próxima.",
+        Text::NeedMorePlayers => "⏳ ¡Necesitamos {} para arrancar la noche de juegos! Van {} de {}, pulsa **Going** abajo si puedes venir.",
+        Text::MorePlayer => "{} jugador más",
+        Text::MorePlayers => "{} jugadores más",
+        Text::LastCall => "🔔 **¡Última ronda!** La noche de juegos termina en {}.",
+        Text::SlashHelp => "**Comandos disponibles:**\n\
+            `/gamenight` - Muestra el estado de la noche de juegos\n\
+            `/nextgame` - Muestra cuándo es la próxima noche de juegos\n\
+            `/isgamenight` - Comprueba si la noche de juegos es ahora\n\
+            `/settings view|day|time|timezone` - Mira o cambia cuándo es la noche de juegos (admins)\n\
+            `/help` - Muestra esta ayuda",
+        Text::SettingsOutsideGuild => "❌ La configuración de la noche de juegos solo se puede cambiar desde un servidor.",
+        Text::UnknownSetting => "❌ Ajuste desconocido.",
+        Text::SettingsNeedManageServer => "❌ Necesitas el permiso Gestionar servidor para cambiar la configuración de la noche de juegos.",
+        Text::SettingsChanged => "✅ La noche de juegos pasa a ser el {} a las {} {}.",
+        Text::IsGameNightYes => "¡Sí! ¡La noche de juegos es ahora! 🎮",
+        Text::IsGameNightNo => "No, todavía no es la noche de juegos. Usa !gamenight para ver cuándo es la próxima.",
+        Text::ConfigTitle => "⚙️ Ajustes de la noche de juegos",
+        Text::ConfigTitleOf => "⚙️ Ajustes de la noche de juegos ({} de {})",
+        Text::ConfigDay => "🗓️ Día",
+        Text::ConfigTimezone => "🌍 Zona horaria",
+        Text::ConfigChannel => "📣 Canal de anuncios",
+        Text::ConfigChannelNotSet => "Sin elegir, los recordatorios están apagados",
+        Text::ConfigNotifyRole => "🔔 Rol avisado",
+        Text::ConfigMinPlayers => "👥 Mínimo de jugadores",
+        Text::ConfigNone => "Ninguno",
+        Text::ConfigAnnouncements => "⏸️ Anuncios",
+        Text::ConfigOn => "Activados",
+        Text::ConfigPaused => "En pausa, `!resume` los vuelve a activar",
+        Text::EveryWeek => "{}, todas las semanas",
+        Text::EveryOtherWeek => "{}, semana sí, semana no",
+        Text::EvenWeeks => "{}, semanas pares",
+        Text::OddWeeks => "{}, semanas impares",
+        Text::NthOfMonth => "{}, el {} del mes",
+        #[cfg(feature = "prefix-commands")]
+        Text::CountdownLive => "🔴 **¡La noche de juegos está en marcha!** Empezó {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::CountdownNext => "⏳ **Próxima noche de juegos:** {}\n\
+            🕐 Empieza {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::UntilPast => "❌ Eso ya ha pasado, elige una fecha que aún esté por llegar.",
+        #[cfg(feature = "prefix-commands")]
+        Text::UntilCountdown => "⏳ **Cuenta atrás hasta el {} a las {} {}**\n\
+            ```\n\
+            {}\n\
+            ```",
+        #[cfg(feature = "prefix-commands")]
+        Text::TimezoneConversion => "🕐 Las **{}** en {} son las **{}** en {}{}",
+        #[cfg(feature = "prefix-commands")]
+        Text::TheNextDay => " (del día siguiente)",
+        #[cfg(feature = "prefix-commands")]
+        Text::TheDayBefore => " (del día anterior)",
+        #[cfg(feature = "prefix-commands")]
+        Text::DaysLater => " ({} días después)",
+        #[cfg(feature = "prefix-commands")]
+        Text::DaysEarlier => " ({} días antes)",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNightAhead => "📅 **Noche de juegos +{}**\n\
+            🗓️ {} a las {} {}\n\
+            ⏳ Empieza en {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::NextGamesTitle => "📅 **Próximas {} noches de juegos**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::LineupTitle => "🎲 **Programa de las noches de juegos**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::SpecialNightsTitle => "✨ **Noches de juegos especiales (próximas {} semanas)**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoSpecialNights => "📅 No hay noches de juegos especiales en las próximas {} semanas.",
+        #[cfg(feature = "prefix-commands")]
+        Text::SessionLine => "🗓️ {} a las {} {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::LineupLine => "🗓️ {} a las {} - 🎮 {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::HistoryTitle => "📜 **Noches de juegos pasadas**\n\
+            ━━━━━━━━━━━━━━━━━━━━━\n\
+            {}",
+        #[cfg(feature = "prefix-commands")]
+        Text::HistoryLine => "🗓️ {} - 🎮 {} - 👥 {} van",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoHistory => "📜 Aún no hay noches de juegos guardadas.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakGoing => "🔥 {} lleva una racha de {}, la última el {}.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakBroken => "🧊 {} no lleva ninguna racha. Su última noche de juegos fue el {}.",
+        #[cfg(feature = "prefix-commands")]
+        Text::StreakNever => "🧊 {} aún no ha venido a ninguna noche de juegos.",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNight => "{} noche de juegos",
+        #[cfg(feature = "prefix-commands")]
+        Text::GameNights => "{} noches de juegos",
+        #[cfg(feature = "prefix-commands")]
+        Text::NoAttendance => "📊 Aún no hay noches de juegos guardadas.",
+        #[cfg(feature = "prefix-commands")]
+        Text::AttendanceNone => "📊 {} aún no ha dicho que sí a ninguna noche de juegos reciente (0/{}).",
+        #[cfg(feature = "prefix-commands")]
+        Text::AttendanceRate => "📊 {} dijo que sí a {}/{}, **{}%**.",
+        #[cfg(feature = "prefix-commands")]
+        Text::RecentGameNight => "{} noche de juegos reciente",
+        #[cfg(feature = "prefix-commands")]
+        Text::RecentGameNights => "{} noches de juegos recientes",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(fill("{} days, {} hours", &["2".to_string(), "3".to_string()]), "2 days, 3 hours");
+        assert_eq!(fill("no placeholders", &[]), "no placeholders");
+        // Missing arguments leave their spot empty
+        assert_eq!(fill("until {}{}", &["1:30 AM".to_string()]), "until 1:30 AM");
+    }
+
+    #[test]
+    fn test_plural() {
+        assert_eq!(plural(Locale::English, 1, Text::Hour, Text::Hours), "1 hour");
+        assert_eq!(plural(Locale::Spanish, 2, Text::Hour, Text::Hours), "2 horas");
+    }
+
+    #[test]
+    fn test_locale_codes() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::from_code(locale.code()), Some(locale));
+        }
+        assert_eq!(Locale::from_code(" ES "), Some(Locale::Spanish));
+        assert_eq!(Locale::from_code("fr"), None);
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_every_help_lists_the_same_commands() {
+        let commands = |help: &str| -> Vec<String> {
+            help.lines().skip(1).filter_map(|line| line.split_whitespace().next().map(str::to_string)).collect()
+        };
+        assert_eq!(commands(tr(Locale::Spanish, Text::Help)), commands(tr(Locale::English, Text::Help)));
+    }
+
+    #[test]
+    fn test_every_slash_help_lists_the_same_commands() {
+        let commands = |help: &str| -> Vec<String> {
+            help.lines().skip(1).filter_map(|line| line.split_whitespace().next().map(str::to_string)).collect()
+        };
+        assert_eq!(commands(tr(Locale::Spanish, Text::SlashHelp)), commands(tr(Locale::English, Text::SlashHelp)));
+    }
+
+    #[test]
+    fn test_ordinal() {
+        assert_eq!(ordinal(Locale::English, 1), "1st");
+        assert_eq!(ordinal(Locale::English, 3), "3rd");
+        assert_eq!(ordinal(Locale::English, 5), "5th");
+        assert_eq!(ordinal(Locale::Spanish, 2), "2.º");
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_long_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+        assert_eq!(long_date(Locale::English, date), "June 05, 2024");
+        assert_eq!(long_date(Locale::Spanish, date), "5 de junio de 2024");
+    }
+}
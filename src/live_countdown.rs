@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serenity::builder::EditMessage;
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::*;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+use crate::messaging::to_discord_timestamp;
+use crate::state::LiveCountdownsKey;
+
+// How often a live countdown gets edited. Once a minute per message stays
+// well clear of Discord's edit rate limits.
+const EDIT_EVERY: Duration = Duration::from_secs(60);
+
+// Longest a countdown keeps editing, in minutes. After that it's left on a
+// timestamp Discord keeps current by itself.
+const MAX_RUNTIME_MINUTES: i64 = 60;
+
+// How many may run at once across every guild, one per channel at most
+const MAX_RUNNING: usize = 10;
+
+struct Running {
+    message_id: MessageId,
+    start: DateTime<Utc>,
+    task: JoinHandle<()>,
+}
+
+// Countdowns started with !livecountdown that are still being edited, by the
+// channel they're in
+#[derive(Default)]
+pub struct LiveCountdowns {
+    running: HashMap<ChannelId, Running>,
+}
+
+// Text of a countdown to `start` as of `now`, to the minute. Minutes round up
+// so it never says 0 before the start.
+pub fn format_live_countdown(start: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = (((start - now).num_seconds() + 59) / 60).max(0);
+    format!(
+        "⏳ **Game night starts in {} days, {} hours, {} minutes**\n\
+        🕐 {}\n\
+        _Updates every minute_",
+        minutes / (24 * 60),
+        minutes / 60 % 24,
+        minutes % 60,
+        to_discord_timestamp(start, 'F')
+    )
+}
+
+// What a countdown is left showing once it stops being edited
+pub fn format_finished(start: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    if now >= start {
+        format!("🔴 **Game night is live!** It started {}", to_discord_timestamp(start, 'R'))
+    } else {
+        format!("⏳ **Next game night:** {}\n🕐 Starts {}", to_discord_timestamp(start, 'F'), to_discord_timestamp(start, 'R'))
+    }
+}
+
+// When a countdown to `start` posted at `posted` stops being edited
+fn stop_at(start: DateTime<Utc>, posted: DateTime<Utc>) -> DateTime<Utc> {
+    start.min(posted + chrono::Duration::minutes(MAX_RUNTIME_MINUTES))
+}
+
+// Post a countdown to `start` in `channel_id` and keep it current until game
+// night starts or MAX_RUNTIME_MINUTES are up. The error is the reply
+// explaining why it didn't start.
+pub async fn start(
+    http: Arc<Http>,
+    data: &RwLock<TypeMap>,
+    channel_id: ChannelId,
+    start: DateTime<Utc>,
+) -> Result<(), String> {
+    let countdowns = {
+        let data = data.read().await;
+        data.get::<LiveCountdownsKey>().expect("live countdowns are inserted at startup").clone()
+    };
+    let mut running = countdowns.lock().await;
+    if running.running.contains_key(&channel_id) {
+        return Err("⏳ There's already a live countdown in this channel.".to_string());
+    }
+    if running.running.len() >= MAX_RUNNING {
+        return Err("⏳ Too many live countdowns are running right now, try again later.".to_string());
+    }
+
+    let now = Utc::now();
+    let message = channel_id.say(&http, format_live_countdown(start, now)).await.map_err(|why| {
+        error!(%channel_id, "Error posting live countdown: {:?}", why);
+        "❌ Couldn't post the countdown.".to_string()
+    })?;
+    let task = tokio::spawn(keep_current(http, countdowns.clone(), channel_id, message.id, start, stop_at(start, now)));
+    running.running.insert(channel_id, Running { message_id: message.id, start, task });
+    Ok(())
+}
+
+async fn keep_current(
+    http: Arc<Http>,
+    countdowns: Arc<Mutex<LiveCountdowns>>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    start: DateTime<Utc>,
+    stop: DateTime<Utc>,
+) {
+    loop {
+        // Wake up right at the stop rather than up to a minute after it
+        let wait = (stop - Utc::now()).to_std().unwrap_or_default().min(EDIT_EVERY);
+        tokio::time::sleep(wait).await;
+        let now = Utc::now();
+        if now >= stop {
+            break;
+        }
+        // Most likely the message got deleted, so there's nothing left to edit
+        if let Err(why) = edit(&http, channel_id, message_id, format_live_countdown(start, now)).await {
+            error!(%channel_id, "Error editing live countdown, stopping it: {:?}", why);
+            countdowns.lock().await.running.remove(&channel_id);
+            return;
+        }
+    }
+    if let Err(why) = edit(&http, channel_id, message_id, format_finished(start, Utc::now())).await {
+        error!(%channel_id, "Error finishing live countdown: {:?}", why);
+    }
+    countdowns.lock().await.running.remove(&channel_id);
+}
+
+async fn edit(http: &Http, channel_id: ChannelId, message_id: MessageId, content: String) -> serenity::Result<()> {
+    channel_id.edit_message(http, message_id, EditMessage::new().content(content)).await.map(|_| ())
+}
+
+// Stop every countdown still running, e.g. on shutdown, leaving each message
+// on timestamps that stay current without the bot
+pub async fn stop_all(http: &Http, data: &RwLock<TypeMap>) {
+    let countdowns = {
+        let data = data.read().await;
+        data.get::<LiveCountdownsKey>().expect("live countdowns are inserted at startup").clone()
+    };
+    let running = std::mem::take(&mut countdowns.lock().await.running);
+    let now = Utc::now();
+    for (channel_id, countdown) in running {
+        countdown.task.abort();
+        if let Err(why) = edit(http, channel_id, countdown.message_id, format_finished(countdown.start, now)).await {
+            error!(%channel_id, "Error finishing live countdown: {:?}", why);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_format_live_countdown() {
+        let now = start() - chrono::Duration::seconds((25 * 60 + 42) * 60 + 30);
+        assert_eq!(
+            format_live_countdown(start(), now),
+            "⏳ **Game night starts in 1 days, 1 hours, 43 minutes**\n🕐 <t:1717804800:F>\n_Updates every minute_"
+        );
+        // Rounds up on the last minute, and never goes below 0
+        assert!(format_live_countdown(start(), start() - chrono::Duration::seconds(5)).contains("0 hours, 1 minutes"));
+        assert!(format_live_countdown(start(), start() + chrono::Duration::minutes(2)).contains("0 hours, 0 minutes"));
+    }
+
+    #[test]
+    fn test_format_finished() {
+        assert!(format_finished(start(), start()).starts_with("🔴 **Game night is live!**"));
+        assert!(format_finished(start(), start() - chrono::Duration::hours(3)).contains("Starts <t:1717804800:R>"));
+    }
+
+    #[test]
+    fn test_stop_at() {
+        // Stops at the start when that's within the hour, otherwise after an hour
+        let posted = start() - chrono::Duration::minutes(20);
+        assert_eq!(stop_at(start(), posted), start());
+        let posted = start() - chrono::Duration::hours(5);
+        assert_eq!(stop_at(start(), posted), posted + chrono::Duration::hours(1));
+    }
+}
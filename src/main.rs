@@ -1,100 +1,2503 @@
+
 use serenity::async_trait;
+use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage};
+#[cfg(feature = "prefix-commands")]
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::gateway::GatewayError;
 use serenity::prelude::*;
-use serenity::model::channel::Message;
+#[cfg(feature = "prefix-commands")]
+use serenity::model::channel::{Channel, Message};
 use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, UnavailableGuild};
+use serenity::model::application::{ComponentInteraction, Interaction};
+use serenity::model::id::{GuildId, UserId};
+#[cfg(feature = "prefix-commands")]
+use serenity::model::id::ChannelId;
+#[cfg(feature = "prefix-commands")]
+use serenity::model::Permissions;
+use serenity::model::voice::VoiceState;
 use dotenv::dotenv;
+use std::collections::{HashMap, HashSet};
+use std::env::VarError;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use tracing::{error, info};
+#[cfg(feature = "prefix-commands")]
+use tracing::{Instrument, info_span};
+use tracing_subscriber::EnvFilter;
 
+mod cache;
+#[cfg(feature = "prefix-commands")]
+mod calendar;
+mod commands;
+mod config;
+#[cfg(feature = "prefix-commands")]
+mod cooldown;
+mod emoji;
 mod game_night;
-use game_night::{GameNightConfig, format_game_night_status, format_next_game_night, is_game_night_now};
+#[cfg(feature = "health-server")]
+mod health;
+mod history;
+mod i18n;
+#[cfg(feature = "prefix-commands")]
+mod live_countdown;
+mod messaging;
+mod metrics;
+#[cfg(feature = "prefix-commands")]
+mod personal_reminder;
+#[cfg(feature = "prefix-commands")]
+mod poll;
+mod rsvp;
+mod scheduler;
+mod slash;
+mod state;
+mod storage;
+mod template;
+mod voice;
+use game_night::{GameNightSchedule, ScheduleError, next_game_night_embed, schedule_status_embeds};
+#[cfg(feature = "prefix-commands")]
+use game_night::{
+    add_to_library, format_debug, format_next_game_night, format_schedule_status, format_special_nights,
+    get_game_suggestion, remove_from_library, set_game_weight, time_remaining_in_session, vote_candidates,
+};
+use commands::CommandArgs;
+use messaging::EmbedReply;
+#[cfg(feature = "prefix-commands")]
+use messaging::to_discord_timestamp;
+use rsvp::RsvpStatus;
+use state::{
+    DefaultScheduleKey, GameLibraryKey, GuildSchedulesKey, HistoryKey, NextOccurrenceCacheKey, CommandMetricsKey,
+    ConnectedGuildsKey, PrefixKey, SaveLockKey, SchedulerStartedKey, StoreKey, RsvpKey, StreakKey, SubscribersKey,
+    UserTimezonesKey, VoiceStatesKey,
+};
+#[cfg(feature = "prefix-commands")]
+use state::{ActivePollsKey, CooldownsKey, LiveCountdownsKey, PersonalRemindersKey, StartedAtKey};
+
+// Default schedule read at startup, relative to the working directory
+const CONFIG_PATH: &str = "gamenight.toml";
+// Directory the runtime state is saved in without STATE_DB: one JSON file
+// each, and the per-guild schedules set with !setgamenight in guilds.toml
+const STATE_DIR: &str = ".";
+
+// How long !vote collects votes unless told otherwise, and the longest it may run
+#[cfg(feature = "prefix-commands")]
+const DEFAULT_VOTE_MINUTES: u64 = 5;
+#[cfg(feature = "prefix-commands")]
+const MAX_VOTE_MINUTES: u64 = 60;
+
+// How many past sessions !history lists unless told otherwise, and the most it will list
+#[cfg(feature = "prefix-commands")]
+const DEFAULT_HISTORY_SESSIONS: usize = 5;
+#[cfg(feature = "prefix-commands")]
+const MAX_HISTORY_SESSIONS: usize = 25;
+
+// How many past sessions !attendancerate looks at unless told otherwise, and the most it
+// will look at
+#[cfg(feature = "prefix-commands")]
+const DEFAULT_ATTENDANCE_SESSIONS: usize = 10;
+#[cfg(feature = "prefix-commands")]
+const MAX_ATTENDANCE_SESSIONS: usize = 100;
+
+// How many sessions !nextgames lists unless told otherwise, and the most it will list
+#[cfg(feature = "prefix-commands")]
+const DEFAULT_NEXT_GAMES: usize = 4;
+#[cfg(feature = "prefix-commands")]
+const MAX_NEXT_GAMES: usize = 20;
+
+// Furthest ahead !gamenight +N looks, about a year of weekly nights
+#[cfg(feature = "prefix-commands")]
+const MAX_GAME_NIGHT_OFFSET: usize = 52;
+
+// How far ahead !specialnights looks unless told otherwise, and the furthest it may look
+#[cfg(feature = "prefix-commands")]
+const DEFAULT_SPECIAL_WEEKS: u32 = 12;
+#[cfg(feature = "prefix-commands")]
+const MAX_SPECIAL_WEEKS: u32 = 52;
+
+// Longest announcement !addspecial takes, so it fits in status replies and embeds
+#[cfg(feature = "prefix-commands")]
+const MAX_SPECIAL_MESSAGE_LENGTH: usize = 200;
+// Longest template !setreminder takes, leaving room under Discord's limit
+#[cfg(feature = "prefix-commands")]
+const MAX_REMINDER_TEMPLATE_LENGTH: usize = 1000;
+
+// How many upcoming sessions of each night !calendar exports
+#[cfg(feature = "prefix-commands")]
+const CALENDAR_SESSIONS: usize = 12;
+
+const DM_DEFAULT_NOTE: &str = "\n\n_No server here, so this is the default schedule._";
+
+#[cfg(feature = "prefix-commands")]
+const SET_GAME_NIGHT_USAGE: &str = "Usage: `!setgamenight <day> <HH:MM> <hours> [timezone]`, where the time can also be \
+    like 8pm and hours can be like 2.5\n\
+    Example: `!setgamenight friday 20:00 4 America/New_York`";
 
 struct Handler;
 
+// Why the bot couldn't start, worded for whoever is running it
+struct StartupError(String);
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Returning an error from main prints it with Debug, so keep that readable too
+impl fmt::Debug for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for StartupError {}
+
+// The bot token from the environment, as long as it's there and looks like one
+fn discord_token() -> Result<String, StartupError> {
+    let token = match std::env::var("DISCORD_TOKEN") {
+        Ok(token) => token,
+        Err(VarError::NotPresent) => {
+            return Err(StartupError(
+                "DISCORD_TOKEN is not set; copy .env.example to .env and put your bot token in it".to_string(),
+            ));
+        }
+        Err(VarError::NotUnicode(_)) => {
+            return Err(StartupError("DISCORD_TOKEN contains invalid characters; paste the token again".to_string()));
+        }
+    };
+
+    let token = token.trim().to_string();
+    if serenity::utils::validate_token(&token).is_err() {
+        return Err(StartupError(
+            "DISCORD_TOKEN doesn't look like a bot token; copy it again from the Bot page of the Discord developer portal"
+                .to_string(),
+        ));
+    }
+    Ok(token)
+}
+
+// Command cooldowns from COOLDOWN_SECONDS, the default for every command, and
+// COMMAND_COOLDOWNS for commands that need their own
+#[cfg(feature = "prefix-commands")]
+fn command_cooldowns() -> Result<cooldown::Cooldowns, StartupError> {
+    let default = match std::env::var("COOLDOWN_SECONDS") {
+        Ok(seconds) => std::time::Duration::from_secs(
+            seconds
+                .trim()
+                .parse()
+                .map_err(|_| StartupError(format!("COOLDOWN_SECONDS must be a number of seconds, got '{}'", seconds)))?,
+        ),
+        Err(_) => cooldown::DEFAULT_COOLDOWN,
+    };
+    let per_command = match std::env::var("COMMAND_COOLDOWNS") {
+        Ok(value) => cooldown::parse_cooldowns(&value)
+            .map_err(|why| StartupError(format!("could not read COMMAND_COOLDOWNS: {}", why)))?,
+        Err(_) => HashMap::new(),
+    };
+    Ok(cooldown::Cooldowns::new(default, per_command))
+}
+
+// Where runtime state is kept: the SQLite database at STATE_DB when set, JSON
+// files in STATE_DIR otherwise. Opening the database migrates it.
+fn open_state_store() -> Result<Arc<dyn storage::Store>, StartupError> {
+    let Ok(path) = std::env::var("STATE_DB") else {
+        return Ok(Arc::new(storage::JsonFiles::new(STATE_DIR)));
+    };
+    #[cfg(feature = "sqlite")]
+    {
+        let store = storage::SqliteStore::open(&path)
+            .map_err(|why| StartupError(format!("could not open STATE_DB {}: {}", path, why)))?;
+        import_guild_schedules(&store)?;
+        Ok(Arc::new(store))
+    }
+    #[cfg(not(feature = "sqlite"))]
+    Err(StartupError(format!("STATE_DB is set to {}, but this build doesn't have the sqlite feature", path)))
+}
+
+// Bring guild schedules a bot without STATE_DB saved in guilds.toml into a
+// database that doesn't have any yet
+#[cfg(feature = "sqlite")]
+fn import_guild_schedules(store: &storage::SqliteStore) -> Result<(), StartupError> {
+    use storage::Store;
+
+    let failed = |why: std::io::Error| StartupError(format!("could not import guild schedules into STATE_DB: {}", why));
+    if !store.load_guild_schedules().map_err(failed)?.is_empty() {
+        return Ok(());
+    }
+    let schedules = storage::JsonFiles::new(STATE_DIR).load_guild_schedules().map_err(failed)?;
+    if !schedules.is_empty() {
+        store.save_guild_schedules(&schedules).map_err(failed)?;
+        info!("Imported {} guild schedules into STATE_DB", schedules.len());
+    }
+    Ok(())
+}
+
+// Command line arguments. `--dry-run` prints what the commands would say
+// instead of connecting to Discord, as of `--at <RFC 3339 timestamp>` or now.
+// Gives the time to preview at for a dry run, none to start the bot.
+fn dry_run_time(mut args: impl Iterator<Item = String>) -> Result<Option<chrono::DateTime<chrono::Utc>>, StartupError> {
+    let usage = "usage: tannius-bot [--dry-run [--at <RFC 3339 time, e.g. 2024-06-07T20:00:00-04:00>]]";
+    let mut dry_run = false;
+    let mut at = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--at" => {
+                let value = args.next().ok_or_else(|| StartupError(format!("--at needs a time; {}", usage)))?;
+                let time = chrono::DateTime::parse_from_rfc3339(value.trim())
+                    .map_err(|_| StartupError(format!("--at '{}' isn't an RFC 3339 time; {}", value, usage)))?;
+                at = Some(time.with_timezone(&chrono::Utc));
+            }
+            _ => return Err(StartupError(format!("unknown argument '{}'; {}", arg, usage))),
+        }
+    }
+    match (dry_run, at) {
+        (false, Some(_)) => Err(StartupError(format!("--at only works with --dry-run; {}", usage))),
+        (false, None) => Ok(None),
+        (true, at) => Ok(Some(at.unwrap_or_else(chrono::Utc::now))),
+    }
+}
+
+// Save the shared state behind `state` with `save`, see storage::save_shared.
+// `name` is what failures get logged as. The caller must have let go of its own
+// lock on `state` first.
+async fn save_shared_state<T: Clone + Send + 'static>(
+    ctx: &Context,
+    name: &str,
+    state: &RwLock<T>,
+    save: impl FnOnce(&dyn storage::Store, &T) -> std::io::Result<()> + Send + 'static,
+) -> bool {
+    let (store, saves) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<StoreKey>().expect("the store is inserted at startup").clone(),
+            data.get::<SaveLockKey>().expect("the save lock is inserted at startup").clone(),
+        )
+    };
+    match storage::save_shared(store, &saves, state, save).await {
+        Ok(()) => true,
+        Err(why) => {
+            error!("Error saving {}: {}", name, why);
+            false
+        }
+    }
+}
+
+// Schedule for the guild a message came from, see GameNightSchedule::for_guild
+async fn guild_schedule(ctx: &Context, guild_id: Option<GuildId>) -> GameNightSchedule {
+    let data = ctx.data.read().await;
+    let default = data.get::<DefaultScheduleKey>().expect("default schedule is inserted at startup");
+    let schedules = data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup");
+    GameNightSchedule::for_guild(default, &*schedules.read().await, guild_id)
+}
+
+// Soonest night of the guild's schedule and its start, cached between calls
+async fn next_occurrence(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    schedule: &GameNightSchedule,
+) -> Result<(usize, chrono::DateTime<chrono::Utc>), ScheduleError> {
+    let cache = {
+        let data = ctx.data.read().await;
+        data.get::<NextOccurrenceCacheKey>().expect("next occurrence cache is inserted at startup").clone()
+    };
+    let mut cache = cache.lock().await;
+    cache.next_occurrence(guild_id, schedule, chrono::Utc::now())
+}
+
+// Reply for a schedule that can't be worked out, see ScheduleError
+fn schedule_error_reply(why: &ScheduleError) -> String {
+    format!("❌ I can't work out when game night is: {}. An admin can move it with `!setgamenight`.", why)
+}
+
+// Mention that DMs only ever see the default schedule
+fn with_dm_note(mut reply: String, guild_id: Option<GuildId>) -> String {
+    if guild_id.is_none() {
+        reply.push_str(DM_DEFAULT_NOTE);
+    }
+    reply
+}
+
+// Reply for !gamenight. A live night pings its notify role.
+async fn game_night_status_reply(ctx: &Context, guild_id: Option<GuildId>) -> EmbedReply {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let (embeds, live_roles) = match schedule_status_embeds(&schedule) {
+        Ok(status) => status,
+        Err(why) => {
+            return EmbedReply {
+                content: schedule_error_reply(&why),
+                embeds: Vec::new(),
+                roles: Vec::new(),
+                #[cfg(feature = "prefix-commands")]
+                fallback: None,
+            };
+        }
+    };
+    let pings: Vec<String> = live_roles.iter().map(|role_id| role_id.mention().to_string()).collect();
+    EmbedReply {
+        content: with_dm_note(pings.join(" "), guild_id).trim().to_string(),
+        embeds,
+        roles: schedule.notify_roles(),
+        #[cfg(feature = "prefix-commands")]
+        fallback: format_schedule_status(&schedule).ok().map(|status| with_dm_note(status, guild_id)),
+    }
+}
+
+// Reply for !gamenightconfig. Mentions the notify role without pinging it.
+async fn game_night_config_reply(ctx: &Context, guild_id: Option<GuildId>) -> EmbedReply {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    EmbedReply {
+        content: with_dm_note(String::new(), guild_id).trim().to_string(),
+        embeds: game_night::config_embeds(&schedule),
+        roles: Vec::new(),
+        #[cfg(feature = "prefix-commands")]
+        fallback: None,
+    }
+}
+
+// Reply for !nextgame, with the start time in the caller's timezone too
+async fn next_game_reply(ctx: &Context, guild_id: Option<GuildId>, user_id: UserId, args: &[String]) -> EmbedReply {
+    let text_reply = |content: String| EmbedReply {
+        content,
+        embeds: Vec::new(),
+        roles: Vec::new(),
+        #[cfg(feature = "prefix-commands")]
+        fallback: None,
+    };
+    let parsed = CommandArgs::new("Usage: `!nextgame [timezone]`, e.g. `!nextgame Asia/Tokyo`", args).and_then(|mut args| {
+        let name = args.next_optional();
+        args.finish()?;
+        Ok(name)
+    });
+    let shown_in = match parsed {
+        Ok(None) => None,
+        Ok(Some(name)) => match config::parse_timezone(&name) {
+            Ok(timezone) => Some(timezone),
+            Err(suggestions) => return text_reply(unknown_timezone_reply(&name, &suggestions)),
+        },
+        Err(reply) => return text_reply(reply),
+    };
+
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let user_timezone = {
+        let data = ctx.data.read().await;
+        let timezones = data.get::<UserTimezonesKey>().expect("user timezones are inserted at startup");
+        timezones.read().await.get(&user_id).copied()
+    };
+    // Report whichever configured night comes up first
+    let (index, start) = match next_occurrence(ctx, guild_id, &schedule).await {
+        Ok(next) => next,
+        Err(why) => return text_reply(schedule_error_reply(&why)),
+    };
+    EmbedReply {
+        content: with_dm_note(String::new(), guild_id).trim().to_string(),
+        embeds: vec![next_game_night_embed(&schedule.nights[index], start, user_timezone, shown_in)],
+        roles: Vec::new(),
+        #[cfg(feature = "prefix-commands")]
+        fallback: Some(with_dm_note(
+            format_next_game_night(&schedule.nights[index], start, user_timezone, shown_in),
+            guild_id,
+        )),
+    }
+}
+
+// Reply for !nextgamejson
+#[cfg(feature = "prefix-commands")]
+async fn next_game_json_reply(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let next = next_occurrence(ctx, guild_id, &schedule).await;
+    let json = match next.and_then(|(index, _)| game_night::next_game_night_json(&schedule.nights[index])) {
+        Ok(json) => json,
+        Err(why) => return schedule_error_reply(&why),
+    };
+    let json = serde_json::to_string_pretty(&json).expect("JSON values serialize");
+    format!("```json\n{}\n```", json)
+}
+
+// Reply for !countdown. Discord renders the timestamps itself, so the
+// countdown stays live without the bot editing anything.
+#[cfg(feature = "prefix-commands")]
+async fn countdown_reply(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let (index, start) = match next_occurrence(ctx, guild_id, &schedule).await {
+        Ok(next) => next,
+        Err(why) => return schedule_error_reply(&why),
+    };
+    let locale = schedule.nights[index].locale;
+    // The next game night can be the one that's live right now
+    let reply = if start <= chrono::Utc::now() {
+        i18n::fill(i18n::tr(locale, i18n::Text::CountdownLive), &[to_discord_timestamp(start, 'R')])
+    } else {
+        i18n::fill(
+            i18n::tr(locale, i18n::Text::CountdownNext),
+            &[to_discord_timestamp(start, 'F'), to_discord_timestamp(start, 'R')],
+        )
+    };
+    with_dm_note(game_night::with_emoji(&schedule.nights[index], &reply), guild_id)
+}
+
+// !livecountdown - post a countdown to the next game night that edits itself
+// every minute. Nothing to reply once it's posted, the countdown says it all.
+#[cfg(feature = "prefix-commands")]
+async fn live_countdown_reply(ctx: &Context, msg: &Message) -> Option<String> {
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let (_, start) = match next_occurrence(ctx, msg.guild_id, &schedule).await {
+        Ok(next) => next,
+        Err(why) => return Some(schedule_error_reply(&why)),
+    };
+    if start <= chrono::Utc::now() {
+        return Some("🔴 **Game night is live!** No need to count down.".to_string());
+    }
+    live_countdown::start(ctx.http.clone(), &ctx.data, msg.channel_id, start).await.err()
+}
+
+// !remindme - DM the caller a while before the next game night
+#[cfg(feature = "prefix-commands")]
+async fn remind_me(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let usage = format!(
+        "Usage: `!remindme <minutes>`, up to {} minutes before game night",
+        personal_reminder::MAX_LEAD_MINUTES
+    );
+    let parsed = CommandArgs::new(&usage, args).and_then(|mut args| {
+        let minutes = args.next_required("minutes")?;
+        args.finish()?;
+        Ok(minutes)
+    });
+    let minutes: u32 = match parsed.map(|minutes| minutes.parse()) {
+        Ok(Ok(minutes)) => minutes,
+        Ok(Err(_)) => return usage,
+        Err(reply) => return reply,
+    };
+
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let (_, start) = match next_occurrence(ctx, msg.guild_id, &schedule).await {
+        Ok(next) => next,
+        Err(why) => return schedule_error_reply(&why),
+    };
+    let now = chrono::Utc::now();
+    if start <= now {
+        return "🔴 **Game night is live!** Too late for a reminder.".to_string();
+    }
+    let at = match personal_reminder::remind_at(start, now, minutes) {
+        Ok(at) => at,
+        Err(reply) => return reply,
+    };
+
+    let replaced = personal_reminder::schedule(ctx.http.clone(), &ctx.data, msg.author.id, at, start).await;
+    let mut reply = format!(
+        "⏰ I'll DM you {} ({}), {} minutes before game night.",
+        to_discord_timestamp(at, 'F'),
+        to_discord_timestamp(at, 'R'),
+        minutes
+    );
+    if replaced {
+        reply.push_str(" That replaces the reminder you had before.");
+    }
+    reply.push_str("\nMake sure DMs from server members are allowed. Reminders don't survive a bot restart.");
+    reply
+}
+
+// Reply for !timeleft
+#[cfg(feature = "prefix-commands")]
+async fn time_left_reply(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let locale = schedule.locale();
+    let now = chrono::Utc::now();
+    let response = match schedule.nights.iter().find_map(|night| time_remaining_in_session(night, now)) {
+        Some(remaining) => i18n::fill(
+            i18n::tr(locale, i18n::Text::TimeLeft),
+            &[game_night::format_hours_minutes(remaining, locale)],
+        ),
+        None => i18n::tr(locale, i18n::Text::NotRunning).to_string(),
+    };
+    with_dm_note(response, guild_id)
+}
+
+// Reply for !until, counting down in the timezone of the guild's primary night
+#[cfg(feature = "prefix-commands")]
+async fn until_reply(ctx: &Context, guild_id: Option<GuildId>, args: &[String]) -> String {
+    let parsed = CommandArgs::new("Usage: `!until <YYYY-MM-DD> [HH:MM]`", args).and_then(|mut args| {
+        let date = args.next_required("YYYY-MM-DD")?;
+        let time = args.next_optional();
+        args.finish()?;
+        Ok((date, time))
+    });
+    let (date, time) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reply) => return reply,
+    };
+    let schedule = guild_schedule(ctx, guild_id).await;
+    match game_night::format_until(&schedule.nights[0], &date, time.as_deref()) {
+        Ok(reply) => with_dm_note(reply, guild_id),
+        Err(reply) => reply,
+    }
+}
+
+// Reply for !isgamenight
+async fn is_game_night_reply(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let schedule = guild_schedule(ctx, guild_id).await;
+    let response = if schedule.is_game_night_now() { i18n::Text::IsGameNightYes } else { i18n::Text::IsGameNightNo };
+    with_dm_note(i18n::tr(schedule.locale(), response).to_string(), guild_id)
+}
+
+// Reply for !nextgames, listing `count` sessions when given
+#[cfg(feature = "prefix-commands")]
+async fn next_games_reply(ctx: &Context, guild_id: Option<GuildId>, count: Option<&str>) -> String {
+    let count = match count {
+        None => DEFAULT_NEXT_GAMES,
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(count) if (1..=MAX_NEXT_GAMES).contains(&count) => count,
+            _ => return format!("Usage: `!nextgames [n]` with 1 to {} game nights", MAX_NEXT_GAMES),
+        },
+    };
+    let schedule = guild_schedule(ctx, guild_id).await;
+    with_dm_note(game_night::format_next_game_nights(&schedule, count), guild_id)
+}
+
+// Reply for !lineup, with the game planned for each of `count` sessions
+#[cfg(feature = "prefix-commands")]
+async fn lineup_reply(ctx: &Context, guild_id: Option<GuildId>, count: Option<&str>) -> String {
+    let count = match count {
+        None => DEFAULT_NEXT_GAMES,
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(count) if (1..=MAX_NEXT_GAMES).contains(&count) => count,
+            _ => return format!("Usage: `!lineup [n]` with 1 to {} game nights", MAX_NEXT_GAMES),
+        },
+    };
+    let schedule = guild_schedule(ctx, guild_id).await;
+    with_dm_note(game_night::format_lineup(&schedule, count), guild_id)
+}
+
+// Reply for !gamenight +N. Offsets past MAX_GAME_NIGHT_OFFSET look that far
+// ahead and no further.
+#[cfg(feature = "prefix-commands")]
+async fn game_night_ahead_reply(ctx: &Context, guild_id: Option<GuildId>, args: &[String]) -> String {
+    let parsed = CommandArgs::new("Usage: `!gamenight [+n]`, e.g. `!gamenight +2` for two nights after the next one", args)
+        .and_then(|mut args| {
+            let offset = args.next_required("+n")?;
+            args.finish()?;
+            Ok(offset)
+        });
+    let offset = match parsed {
+        Ok(offset) => offset,
+        Err(reply) => return reply,
+    };
+    let offset = match offset.parse::<usize>() {
+        Ok(offset) => offset.min(MAX_GAME_NIGHT_OFFSET),
+        Err(_) => return format!("❌ '{}' isn't a number of game nights ahead, try something like `+2`", offset),
+    };
+    let schedule = guild_schedule(ctx, guild_id).await;
+    with_dm_note(game_night::format_game_night_ahead(&schedule, offset), guild_id)
+}
+
+// Reply for !specialnights, looking `weeks` ahead when given
+#[cfg(feature = "prefix-commands")]
+async fn special_nights_reply(ctx: &Context, guild_id: Option<GuildId>, weeks: Option<&str>) -> String {
+    let weeks = match weeks {
+        None => DEFAULT_SPECIAL_WEEKS,
+        Some(arg) => match arg.parse::<u32>() {
+            Ok(weeks) if (1..=MAX_SPECIAL_WEEKS).contains(&weeks) => weeks,
+            _ => return format!("Usage: `!specialnights [weeks]` with 1 to {} weeks", MAX_SPECIAL_WEEKS),
+        },
+    };
+    let schedule = guild_schedule(ctx, guild_id).await;
+    with_dm_note(format_special_nights(&schedule, weeks), guild_id)
+}
+
+// !calendar - upload the schedule as an .ics file to import into a calendar app
+#[cfg(feature = "prefix-commands")]
+async fn send_calendar(ctx: &Context, msg: &Message) -> serenity::Result<Message> {
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let ics = calendar::export_ics(&schedule, CALENDAR_SESSIONS);
+    let message = CreateMessage::new()
+        .content(with_dm_note("📅 Import this into your calendar app to never miss game night!".to_string(), msg.guild_id))
+        .add_file(CreateAttachment::bytes(ics.into_bytes(), "gamenight.ics"));
+    msg.channel_id.send_message(&ctx.http, message).await
+}
+
+// !exportconfig - upload the schedule as a gamenight.toml to keep or to set
+// up another server or bot with
+#[cfg(feature = "prefix-commands")]
+async fn send_config_export(ctx: &Context, msg: &Message) -> serenity::Result<Message> {
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let toml = config::export_schedule(&schedule);
+    let message = CreateMessage::new()
+        .content(with_dm_note("⚙️ Here's the game night schedule as a config file.".to_string(), msg.guild_id))
+        .add_file(CreateAttachment::bytes(toml.into_bytes(), "gamenight.toml"));
+    msg.channel_id.send_message(&ctx.http, message).await
+}
+
+// Command prefix for the guild a message came from. DMs always use the default.
+#[cfg(feature = "prefix-commands")]
+async fn guild_prefix(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let Some(guild_id) = guild_id else {
+        return commands::DEFAULT_PREFIX.to_string();
+    };
+    let data = ctx.data.read().await;
+    let prefixes = data.get::<PrefixKey>().expect("prefixes are inserted at startup");
+    let prefixes = prefixes.read().await;
+    prefixes.get(&guild_id).cloned().unwrap_or_else(|| commands::DEFAULT_PREFIX.to_string())
+}
+
+// Reply for !history, listing `count` sessions when given
+#[cfg(feature = "prefix-commands")]
+async fn history_reply(ctx: &Context, guild_id: Option<GuildId>, count: Option<&str>) -> String {
+    let Some(guild_id) = guild_id else {
+        return "❌ Game night history is only kept inside a server.".to_string();
+    };
+    let count = match count {
+        None => DEFAULT_HISTORY_SESSIONS,
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(count) if (1..=MAX_HISTORY_SESSIONS).contains(&count) => count,
+            _ => return format!("Usage: `!history [n]` with 1 to {} sessions", MAX_HISTORY_SESSIONS),
+        },
+    };
+
+    let locale = guild_schedule(ctx, Some(guild_id)).await.locale();
+    let data = ctx.data.read().await;
+    let history = data.get::<HistoryKey>().expect("history is inserted at startup");
+    let history = history.read().await;
+    history::format_history(history.get(&guild_id).map(Vec::as_slice).unwrap_or_default(), count, locale)
+}
+
+// Reply for !streak, about the first member mentioned or else the author
+#[cfg(feature = "prefix-commands")]
+async fn streak_reply(ctx: &Context, msg: &Message) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ Streaks are only kept inside a server.".to_string();
+    };
+    let user_id = msg.mentions.first().map_or(msg.author.id, |user| user.id);
+
+    let locale = guild_schedule(ctx, Some(guild_id)).await.locale();
+    let data = ctx.data.read().await;
+    let streaks = data.get::<StreakKey>().expect("streaks are inserted at startup");
+    let streaks = streaks.read().await;
+    history::format_streak(user_id, streaks.get(&guild_id).and_then(|streaks| streaks.get(&user_id)), locale)
+}
+
+// Reply for !attendancerate, about the first member mentioned or else the
+// author, over the last few sessions
+#[cfg(feature = "prefix-commands")]
+async fn attendance_rate_reply(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ Game night history is only kept inside a server.".to_string();
+    };
+    let user_id = msg.mentions.first().map_or(msg.author.id, |user| user.id);
+    // The mention is in the arguments too
+    let mut rest = args.iter().filter(|arg| !arg.starts_with("<@"));
+    let usage = format!("Usage: `!attendancerate [@user] [n]` with 1 to {} sessions", MAX_ATTENDANCE_SESSIONS);
+    let count = match (rest.next(), rest.next()) {
+        (None, _) => DEFAULT_ATTENDANCE_SESSIONS,
+        (Some(arg), None) => match arg.parse::<usize>() {
+            Ok(count) if (1..=MAX_ATTENDANCE_SESSIONS).contains(&count) => count,
+            _ => return usage,
+        },
+        (Some(_), Some(_)) => return usage,
+    };
+
+    let locale = guild_schedule(ctx, Some(guild_id)).await.locale();
+    let data = ctx.data.read().await;
+    let history = data.get::<HistoryKey>().expect("history is inserted at startup");
+    let history = history.read().await;
+    let records = history.get(&guild_id).map(Vec::as_slice).unwrap_or_default();
+    history::format_attendance_rate(user_id, records, count, locale)
+}
+
+// Reply for !whoisplaying, listing who's in the schedule's voice channel
+#[cfg(feature = "prefix-commands")]
+async fn who_is_playing_reply(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let Some(guild_id) = guild_id else {
+        return "❌ Voice channels only exist inside a server.".to_string();
+    };
+    let schedule = guild_schedule(ctx, Some(guild_id)).await;
+    let Some(channel_id) = voice::voice_channel(&schedule) else {
+        return "🔇 No voice channel is set for game night. Add `voice_channel_id` to the schedule to use this.".to_string();
+    };
+
+    let data = ctx.data.read().await;
+    let states = data.get::<VoiceStatesKey>().expect("voice states are inserted at startup");
+    let states = states.read().await;
+    voice::format_who_is_playing(channel_id, &voice::members_in(&states, guild_id, channel_id))
+}
+
+// Whether the author of `msg` holds `permission` in the channel it was sent in.
+// Always false outside of guilds.
+#[cfg(feature = "prefix-commands")]
+async fn author_has_permission(ctx: &Context, msg: &Message, permission: Permissions) -> bool {
+    let Some(guild_id) = msg.guild_id else {
+        return false;
+    };
+    let (Ok(guild), Ok(member), Ok(Channel::Guild(channel))) = (
+        guild_id.to_partial_guild(&ctx.http).await,
+        guild_id.member(&ctx.http, msg.author.id).await,
+        msg.channel(&ctx.http).await,
+    ) else {
+        return false;
+    };
+
+    guild.user_permissions_in(&channel, &member).contains(permission)
+}
+
+// The guild a message was sent in, provided its author holds `permission`
+// there. Otherwise the reply explaining why they can't do that.
+#[cfg(feature = "prefix-commands")]
+async fn require_guild_permission(
+    ctx: &Context,
+    msg: &Message,
+    permission: Permissions,
+    permission_name: &str,
+) -> Result<GuildId, String> {
+    let Some(guild_id) = msg.guild_id else {
+        return Err("❌ Game night settings can only be changed from inside a server.".to_string());
+    };
+    if !author_has_permission(ctx, msg, permission).await {
+        return Err(format!("❌ You need the {} permission to change game night settings.", permission_name));
+    }
+    Ok(guild_id)
+}
+
+// Apply `change` to a copy of the guild's schedule (starting from the default
+// one if the guild has none yet), then store it and save every guild schedule
+// to disk. The stored schedule is left alone if `change` fails.
+async fn update_guild_schedule(
+    ctx: &Context,
+    guild_id: GuildId,
+    change: impl FnOnce(&mut GameNightSchedule) -> Result<String, String>,
+) -> String {
+    let (reply, schedules) = {
+        let data = ctx.data.read().await;
+        let default = data.get::<DefaultScheduleKey>().expect("default schedule is inserted at startup");
+        let schedules = data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup");
+        let mut guard = schedules.write().await;
+
+        let mut schedule = guard.get(&guild_id).unwrap_or(default).clone();
+        let reply = match change(&mut schedule) {
+            Ok(reply) => reply,
+            Err(reply) => return reply,
+        };
+        guard.insert(guild_id, schedule);
+        let cache = data.get::<NextOccurrenceCacheKey>().expect("next occurrence cache is inserted at startup");
+        cache.lock().await.invalidate();
+
+        (reply, schedules.clone())
+    };
+
+    let saved = save_shared_state(ctx, storage::GUILD_SCHEDULES, &schedules, |store, schedules| {
+        store.save_guild_schedules(schedules)
+    })
+    .await;
+    if !saved {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// !setgamenight - move the guild's primary game night
+#[cfg(feature = "prefix-commands")]
+async fn set_game_night(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let parsed = CommandArgs::new(SET_GAME_NIGHT_USAGE, args).and_then(|mut args| {
+        let day = args.next_required("day")?;
+        let mut time = args.next_required("HH:MM")?;
+        let mut hours = args.next_required("hours")?;
+        // "8:00 PM" comes in as two words
+        if hours.eq_ignore_ascii_case("am") || hours.eq_ignore_ascii_case("pm") {
+            time = format!("{} {}", time, hours);
+            hours = args.next_required("hours")?;
+        }
+        let mut parsed = vec![day, time, hours];
+        parsed.extend(args.next_optional());
+        args.finish()?;
+        Ok(parsed)
+    });
+    let args = match parsed {
+        Ok(args) => args,
+        Err(reply) => return reply,
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        let night = config::parse_set_game_night_args(&args, &schedule.nights[0])
+            .map_err(|why| format!("❌ {}\n{}", why, SET_GAME_NIGHT_USAGE))?;
+        let reply = format!(
+            "✅ Game night is now {} at {} {} for {}.",
+            night.day_of_week,
+            night.start_time.format(night.time_format.pattern()),
+            night.timezone,
+            game_night::format_session_length(&night)
+        );
+        schedule.nights[0] = night;
+        Ok(reply)
+    })
+    .await
+}
+
+// !override / !clearoverride - move a single session of the guild's primary
+// night, or go back to the regular schedule
+#[cfg(feature = "prefix-commands")]
+async fn set_override(ctx: &Context, msg: &Message, args: &[String], clear: bool) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+    let usage = "Usage: `!override <YYYY-MM-DD> <HH:MM>`, e.g. `!override 2024-06-08 18:00`";
+    let parsed = CommandArgs::new(usage, args).and_then(|mut args| {
+        if clear {
+            return Ok(None);
+        }
+        let date = args.next_required("YYYY-MM-DD")?;
+        let time = args.next_required("HH:MM")?;
+        args.finish()?;
+        Ok(Some((date, time)))
+    });
+    let date_time = match parsed {
+        Ok(date_time) => date_time,
+        Err(reply) => return reply,
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        let night = &mut schedule.nights[0];
+        let Some((date, time)) = date_time else {
+            night.override_date = None;
+            return Ok("✅ Back to the regular schedule.".to_string());
+        };
+
+        let override_date = game_night::parse_local_datetime(night, &date, Some(&time))?;
+        if override_date <= chrono::Utc::now() {
+            return Err("❌ That's already in the past, pick a time that's still coming up.".to_string());
+        }
+        night.override_date = Some(override_date);
+        Ok(format!(
+            "✅ Game night that week is moved to {}.",
+            to_discord_timestamp(override_date, 'F')
+        ))
+    })
+    .await
+}
+
+// !skip / !unskip - cancel the next game night, or bring back the soonest one
+// that was skipped
+#[cfg(feature = "prefix-commands")]
+async fn skip_game_night(ctx: &Context, msg: &Message, skip: bool) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        if skip {
+            let start = schedule.skip_next().map_err(|why| schedule_error_reply(&why))?;
+            return Ok(format!(
+                "✅ Game night on {} is skipped. See you the time after!",
+                to_discord_timestamp(start, 'D')
+            ));
+        }
+
+        let now = chrono::Utc::now();
+        let soonest = schedule.nights
+            .iter_mut()
+            .filter_map(|night| {
+                let today = game_night::local_date(night, now);
+                let date = night.skipped_dates.iter().copied().filter(|date| *date >= today).min()?;
+                Some((night, date))
+            })
+            .min_by_key(|(_, date)| *date);
+        match soonest {
+            Some((night, date)) => {
+                night.skipped_dates.remove(&date);
+                Ok(format!("✅ Game night on {} is back on.", date.format("%A, %B %d")))
+            }
+            None => Err("❌ No game nights are skipped.".to_string()),
+        }
+    })
+    .await
+}
+
+// Whether the bot can post in `channel_id`, which has to be in the guild.
+// Otherwise the reply explaining what's wrong.
+#[cfg(feature = "prefix-commands")]
+async fn check_bot_can_post(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> Result<(), String> {
+    let Ok(Channel::Guild(channel)) = channel_id.to_channel(&ctx.http).await else {
+        return Err(format!("❌ I can't see {}, is it a channel in this server?", channel_id.mention()));
+    };
+    if channel.guild_id != guild_id {
+        return Err(format!("❌ {} isn't in this server.", channel_id.mention()));
+    }
+    let (Ok(guild), Ok(bot)) = (
+        guild_id.to_partial_guild(&ctx.http).await,
+        ctx.http.get_current_user_guild_member(guild_id).await,
+    ) else {
+        return Err(format!("❌ Couldn't check my permissions in {}, try again in a bit.", channel_id.mention()));
+    };
+    if !guild.user_permissions_in(&channel, &bot).contains(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES) {
+        return Err(format!(
+            "❌ I can't send messages in {}. Give me the View Channel and Send Messages permissions there first.",
+            channel_id.mention()
+        ));
+    }
+    Ok(())
+}
+
+// !setchannel - pick the channel reminders and the live announcement are
+// posted in, the one the command was sent in unless another is mentioned
+#[cfg(feature = "prefix-commands")]
+async fn set_channel(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_CHANNELS, "Manage Channels").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let channel_id = match args {
+        [] => msg.channel_id,
+        [channel] => match serenity::utils::parse_channel_mention(channel) {
+            Some(channel_id) => channel_id,
+            None => return "Usage: `!setchannel [#channel]`, the current channel when left out".to_string(),
+        },
+        _ => return "Usage: `!setchannel [#channel]`, the current channel when left out".to_string(),
+    };
+    if let Err(reply) = check_bot_can_post(ctx, guild_id, channel_id).await {
+        return reply;
+    }
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.reminder_channel = Some(channel_id);
+        }
+        Ok(format!("✅ Reminders and game night announcements will be posted in {}.", channel_id.mention()))
+    })
+    .await
+}
+
+// !setnotifyrole - choose the role pinged for every game night in the guild
+#[cfg(feature = "prefix-commands")]
+async fn set_notify_role(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let clear = args.first().is_some_and(|arg| arg.eq_ignore_ascii_case("none"));
+    let role_id = msg.mention_roles.first().copied();
+    if role_id.is_none() && !clear {
+        return "Usage: `!setnotifyrole @Role` or `!setnotifyrole none`".to_string();
+    }
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.notify_role_id = role_id;
+        }
+        Ok(match role_id {
+            Some(role_id) => format!("✅ Game night announcements will now ping {}.", role_id.mention()),
+            None => "✅ Game night announcements won't ping a role anymore.".to_string(),
+        })
+    })
+    .await
+}
+
+// !holiday - choose whether every game night in the guild plays a holiday
+// themed, skips it, or treats it like any other night
+#[cfg(feature = "prefix-commands")]
+async fn set_holiday_policy(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let holidays = game_night::Holiday::ALL.map(game_night::Holiday::key).join(", ");
+    let usage = format!("Usage: `!holiday <holiday> theme|skip|ignore`, where the holiday is one of {}", holidays);
+    let parsed = CommandArgs::new(&usage, args).and_then(|mut args| {
+        let parsed = (args.next_required("holiday")?, args.next_required("theme|skip|ignore")?);
+        args.finish()?;
+        Ok(parsed)
+    });
+    let (holiday, policy) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reply) => return reply,
+    };
+    let (Some(holiday), Some(policy)) = (game_night::Holiday::from_key(&holiday), config::parse_holiday_policy(&policy))
+    else {
+        return usage;
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.holiday_policies.insert(holiday, policy);
+        }
+        Ok(match policy {
+            game_night::HolidayPolicy::Theme => format!("✅ Game nights on {} will be themed.", holiday.key()),
+            game_night::HolidayPolicy::Skip => format!("✅ Game nights on {} will be skipped.", holiday.key()),
+            game_night::HolidayPolicy::Ignore => {
+                format!("✅ Game nights on {} will be played like any other night.", holiday.key())
+            }
+        })
+    })
+    .await
+}
+
+// !disablespecial / !enablespecial - turn the special night on a date off or
+// back on, e.g. for servers that don't celebrate July 4th. Holidays keep their
+// theme around to enable again, while the guild's own specials are removed.
+#[cfg(feature = "prefix-commands")]
+async fn toggle_special(ctx: &Context, msg: &Message, args: &[String], enable: bool) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let command = if enable { "enablespecial" } else { "disablespecial" };
+    let usage = format!("Usage: `!{} <MM-DD>`, e.g. `!{} 07-04`", command, command);
+    let parsed = CommandArgs::new(&usage, args).and_then(|mut args| {
+        let date = args.next_required("MM-DD")?;
+        args.finish()?;
+        Ok(date)
+    });
+    let date = match parsed {
+        Ok(date) => date,
+        Err(reply) => return reply,
+    };
+    let Some((month, day)) = config::parse_month_day(&date) else {
+        return format!("❌ '{}' isn't a date, expected MM-DD like 07-04", date);
+    };
+    let holiday = game_night::Holiday::on_month_day(month, day);
+    let policy = if enable { game_night::HolidayPolicy::Theme } else { game_night::HolidayPolicy::Ignore };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        let mut removed_custom = false;
+        for night in &mut schedule.nights {
+            if !enable {
+                removed_custom |= night.custom_specials.remove(&(month, day)).is_some();
+            }
+            if let Some(holiday) = holiday {
+                night.holiday_policies.insert(holiday, policy);
+            }
+        }
+        match (holiday, removed_custom) {
+            (Some(holiday), _) if enable => Ok(format!("✅ Game nights on {} will be themed again.", holiday.key())),
+            (Some(holiday), _) => Ok(format!("✅ Game nights on {} won't be themed anymore.", holiday.key())),
+            (None, true) => Ok(format!("✅ Removed the special game night on {:02}-{:02}.", month, day)),
+            (None, false) => Err(format!("❌ There's no special game night on {:02}-{:02}.", month, day)),
+        }
+    })
+    .await
+}
+
+// !addspecial - announce game nights on a date with the guild's own message,
+// e.g. for a server anniversary or a member's birthday
+#[cfg(feature = "prefix-commands")]
+async fn add_special(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let usage = "Usage: `!addspecial <MM-DD> <message>`, e.g. `!addspecial 06-07 🎂 Server Anniversary Game Night!`";
+    let [date, message @ ..] = args else {
+        return usage.to_string();
+    };
+    let message = message.join(" ");
+    if message.is_empty() {
+        return usage.to_string();
+    }
+    if message.chars().count() > MAX_SPECIAL_MESSAGE_LENGTH {
+        return format!("❌ Keep the message to {} characters or less.", MAX_SPECIAL_MESSAGE_LENGTH);
+    }
+    let Some((month, day)) = config::parse_month_day(date) else {
+        return format!("❌ '{}' isn't a date, expected MM-DD like 06-07", date);
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.custom_specials.insert((month, day), message.clone());
+        }
+        Ok(format!("✅ Game nights on {:02}-{:02} will be announced with: {}", month, day, message))
+    })
+    .await
+}
+
+// !pause / !resume - hold back every reminder and announcement for the
+// guild, or let them go out again
+#[cfg(feature = "prefix-commands")]
+async fn pause_announcements(ctx: &Context, msg: &Message, pause: bool) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        if schedule.nights.iter().all(|night| night.announcements_enabled != pause) {
+            return Err(if pause {
+                "❌ Announcements are already paused. Use `!resume` to turn them back on.".to_string()
+            } else {
+                "❌ Announcements aren't paused.".to_string()
+            });
+        }
+        for night in &mut schedule.nights {
+            night.announcements_enabled = !pause;
+        }
+        Ok(if pause {
+            "⏸️ Reminders and announcements are paused. Use `!resume` to turn them back on.".to_string()
+        } else {
+            "▶️ Reminders and announcements are back on.".to_string()
+        })
+    })
+    .await
+}
+
+// !setreminder - change what reminders say, or go back to the default text
+#[cfg(feature = "prefix-commands")]
+async fn set_reminder(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let placeholders = game_night::REMINDER_PLACEHOLDERS.map(|name| format!("`{{{}}}`", name)).join(", ");
+    let reminder_template = args.join(" ");
+    if reminder_template.is_empty() {
+        return format!(
+            "Usage: `!setreminder <template>`, or `!setreminder default` to go back to the usual text. \
+            The template can use {}, e.g. `!setreminder {{role}} {{game}} starts in {{countdown}}!`",
+            placeholders
+        );
+    }
+    if reminder_template.chars().count() > MAX_REMINDER_TEMPLATE_LENGTH {
+        return format!("❌ Keep the template to {} characters or less.", MAX_REMINDER_TEMPLATE_LENGTH);
+    }
+    let reset = reminder_template.eq_ignore_ascii_case("default");
+    let unknown = template::unknown_placeholders(&reminder_template, &game_night::REMINDER_PLACEHOLDERS);
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.reminder_template = if reset {
+                game_night::DEFAULT_REMINDER_TEMPLATE.to_string()
+            } else {
+                reminder_template.clone()
+            };
+        }
+        if reset {
+            return Ok("✅ Reminders are back to the usual text.".to_string());
+        }
+        let mut reply = format!("✅ Reminders will now read: {}", reminder_template);
+        if !unknown.is_empty() {
+            let unknown: Vec<String> = unknown.iter().map(|name| format!("`{{{}}}`", name)).collect();
+            reply.push_str(&format!(
+                "\n⚠️ {} will show up as typed, the placeholders are {}.",
+                unknown.join(", "),
+                placeholders
+            ));
+        }
+        Ok(reply)
+    })
+    .await
+}
+
+// !setlocale - pick the language the guild's replies are written in
+#[cfg(feature = "prefix-commands")]
+async fn set_locale(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let codes = i18n::Locale::ALL.map(i18n::Locale::code).join(", ");
+    let Some(locale) = args.first().and_then(|code| i18n::Locale::from_code(code)) else {
+        return format!("Usage: `!setlocale <code>`, where the code is one of {}", codes);
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            night.locale = locale;
+        }
+        Ok(format!("✅ Replies will now be written in {}.", locale.name()))
+    })
+    .await
+}
+
+// !setemoji - swap one of the status emoji for the guild's own, or back
+// with `default`
+#[cfg(feature = "prefix-commands")]
+async fn set_emoji(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let slots = emoji::EmojiSlot::ALL.map(emoji::EmojiSlot::name).join(", ");
+    let usage = format!("Usage: `!setemoji <slot> <emoji>` or `!setemoji <slot> default`, where the slot is one of {}", slots);
+    let parsed = CommandArgs::new(&usage, args).and_then(|mut args| {
+        let slot = args.next_required("slot")?;
+        let value = args.next_required("emoji")?;
+        args.finish()?;
+        Ok((slot, value))
+    });
+    let (slot, value) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reply) => return reply,
+    };
+    let Some(slot) = emoji::EmojiSlot::from_name(&slot) else {
+        return format!("❌ Unknown slot '{}'.\n{}", slot, usage);
+    };
+    let value = if value.eq_ignore_ascii_case("default") {
+        None
+    } else if emoji::is_single_emoji(&value) {
+        Some(value)
+    } else {
+        return "❌ That isn't a single emoji. Use one like 🎲, or a custom one from this server.".to_string();
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            match &value {
+                Some(value) => night.emoji.insert(slot, value.clone()),
+                None => night.emoji.remove(&slot),
+            };
+        }
+        Ok(match &value {
+            Some(value) => format!("✅ The {} emoji is now {}.", slot.name(), value),
+            None => format!("✅ The {} emoji is back to {}.", slot.name(), slot.default_emoji()),
+        })
+    })
+    .await
+}
+
+// !setcolor - pick the sidebar colour of the live or upcoming embeds
+#[cfg(feature = "prefix-commands")]
+async fn set_color(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+
+    let usage = "Usage: `!setcolor live|upcoming <#hex|default>`, e.g. `!setcolor live #FF0000`";
+    let parsed = CommandArgs::new(usage, args).and_then(|mut args| {
+        let which = args.next_required("live|upcoming")?;
+        let value = args.next_required("colour")?;
+        args.finish()?;
+        Ok((which, value))
+    });
+    let (which, value) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reply) => return reply,
+    };
+    let live = match which.to_lowercase().as_str() {
+        "live" => true,
+        "upcoming" => false,
+        _ => return usage.to_string(),
+    };
+    let colour = if value.eq_ignore_ascii_case("default") {
+        if live { game_night::LIVE_COLOUR } else { game_night::UPCOMING_COLOUR }
+    } else {
+        match config::parse_hex_color(&value) {
+            Some(colour) => colour,
+            None => return format!("❌ `{}` isn't a hex colour like `#FF0000` or `#F00`.", value),
+        }
+    };
+
+    update_guild_schedule(ctx, guild_id, |schedule| {
+        for night in &mut schedule.nights {
+            if live {
+                night.embed_color_live = colour;
+            } else {
+                night.embed_color_upcoming = colour;
+            }
+        }
+        Ok(format!(
+            "✅ {} embeds are now {}.",
+            if live { "Live" } else { "Upcoming" },
+            config::format_hex_color(colour)
+        ))
+    })
+    .await
+}
+
+// !setprefix - pick the prefix the guild's commands start with
+#[cfg(feature = "prefix-commands")]
+async fn set_prefix(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let guild_id = match require_guild_permission(ctx, msg, Permissions::MANAGE_GUILD, "Manage Server").await {
+        Ok(guild_id) => guild_id,
+        Err(reply) => return reply,
+    };
+    // All of the arguments, so a prefix with a space in it gets caught
+    let prefix = args.join(" ");
+    let prefix = prefix.as_str();
+    if prefix.is_empty() {
+        return "Usage: `!setprefix <prefix>`, e.g. `!setprefix ?`".to_string();
+    }
+    if let Err(reply) = commands::validate_prefix(prefix) {
+        return reply;
+    }
+
+    let prefixes = {
+        let data = ctx.data.read().await;
+        data.get::<PrefixKey>().expect("prefixes are inserted at startup").clone()
+    };
+    if prefix == commands::DEFAULT_PREFIX {
+        prefixes.write().await.remove(&guild_id);
+    } else {
+        prefixes.write().await.insert(guild_id, prefix.to_string());
+    }
+
+    let reply = format!("✅ Commands in this server now start with `{}`, e.g. `{}help`.", prefix, prefix);
+    if !save_shared_state(ctx, storage::PREFIXES, &prefixes, |store, prefixes| {
+        storage::save_state(store, storage::PREFIXES, prefixes)
+    }).await {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// Count one use of `command` for !stats
+async fn record_command(ctx: &Context, command: &str) {
+    let data = ctx.data.read().await;
+    data.get::<CommandMetricsKey>().expect("command metrics are inserted at startup").record(command);
+}
+
+// Checks the message came from whoever owns the bot's application, with the
+// reply to send when it didn't
+#[cfg(feature = "prefix-commands")]
+async fn require_bot_owner(ctx: &Context, msg: &Message, command: &str) -> Result<(), String> {
+    let owner_id = match ctx.http.get_current_application_info().await {
+        Ok(info) => info.owner.map(|owner| owner.id),
+        Err(why) => {
+            error!("Error fetching application info: {:?}", why);
+            return Err("❌ Couldn't look up who owns this bot, try again later.".to_string());
+        }
+    };
+    if owner_id != Some(msg.author.id) {
+        return Err(format!("❌ Only the bot owner can use `!{}`.", command));
+    }
+    Ok(())
+}
+
+// !debug - dump the resolved schedule, for the bot's owner only since it
+// shows the raw config
+#[cfg(feature = "prefix-commands")]
+async fn debug_reply(ctx: &Context, msg: &Message) -> String {
+    if let Err(reply) = require_bot_owner(ctx, msg, "debug").await {
+        return reply;
+    }
+
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    with_dm_note(format_debug(&schedule), msg.guild_id)
+}
+
+// !preview <time> - what !gamenight and !nextgame would say at any moment,
+// for the bot's owner to check DST changes and late nights from Discord
+#[cfg(feature = "prefix-commands")]
+async fn preview_reply(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    if let Err(reply) = require_bot_owner(ctx, msg, "preview").await {
+        return reply;
+    }
+
+    let usage = "Usage: `!preview <RFC 3339 time>`, e.g. `!preview 2024-11-03T01:30:00-04:00`";
+    let parsed = CommandArgs::new(usage, args).and_then(|mut args| {
+        let time = args.next_required("RFC 3339 time")?;
+        args.finish()?;
+        Ok(time)
+    });
+    let time = match parsed {
+        Ok(time) => time,
+        Err(reply) => return reply,
+    };
+    let Ok(now) = chrono::DateTime::parse_from_rfc3339(time.trim()) else {
+        return format!("❌ '{}' isn't an RFC 3339 time.\n{}", time, usage);
+    };
+    let now = now.with_timezone(&chrono::Utc);
+
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let preview = format!(
+        "🔍 **Replies as of {}**\n\n{}",
+        to_discord_timestamp(now, 'F'),
+        game_night::format_preview_at(&schedule, now)
+    );
+    with_dm_note(preview, msg.guild_id)
+}
+
+// !stats - how often each command was used and how long the bot has been
+// up, for the bot's owner
+#[cfg(feature = "prefix-commands")]
+async fn stats_reply(ctx: &Context, msg: &Message) -> String {
+    if let Err(reply) = require_bot_owner(ctx, msg, "stats").await {
+        return reply;
+    }
+
+    let data = ctx.data.read().await;
+    let metrics = data.get::<CommandMetricsKey>().expect("command metrics are inserted at startup");
+    let started = data.get::<StartedAtKey>().expect("the start time is inserted once the bot is ready");
+    metrics::format_stats(&metrics.counts(), started.elapsed())
+}
+
+// Reply for !about
+#[cfg(feature = "prefix-commands")]
+async fn about_reply(ctx: &Context) -> EmbedReply {
+    let data = ctx.data.read().await;
+    let started = data.get::<StartedAtKey>().expect("the start time is inserted once the bot is ready");
+    let guilds = data.get::<ConnectedGuildsKey>().expect("connected guilds are inserted at startup");
+    EmbedReply {
+        content: String::new(),
+        embeds: vec![metrics::about_embed(env!("CARGO_PKG_VERSION"), started.elapsed(), guilds.read().await.len())],
+        roles: Vec::new(),
+        #[cfg(feature = "prefix-commands")]
+        fallback: None,
+    }
+}
+
+// Reply for a timezone name that didn't parse, with what it might have meant
+fn unknown_timezone_reply(name: &str, suggestions: &[&str]) -> String {
+    if suggestions.is_empty() {
+        format!("❌ Unknown timezone `{}`, expected an IANA name like `Europe/Berlin`.", name)
+    } else {
+        format!("❌ Unknown timezone `{}`. Did you mean: {}?", name, suggestions.join(", "))
+    }
+}
+
+// Most timezones !tzsearch lists before it just says how many more matched
+#[cfg(feature = "prefix-commands")]
+const MAX_TIMEZONE_RESULTS: usize = 20;
+
+// Reply for !tzsearch, listing timezones to pick from for !mytz and !tz
+#[cfg(feature = "prefix-commands")]
+fn timezone_search_reply(args: &[String]) -> String {
+    let query = args.join(" ");
+    if query.trim().is_empty() {
+        return "Usage: `!tzsearch <query>`, e.g. `!tzsearch berlin`".to_string();
+    }
+    let matches = config::search_timezones(&query);
+    if matches.is_empty() {
+        return format!("🔍 No timezone matches `{}`.", query);
+    }
+
+    let shown: Vec<String> = matches.iter().take(MAX_TIMEZONE_RESULTS).map(|name| format!("`{}`", name)).collect();
+    let mut reply = format!("🔍 Timezones matching `{}`:\n{}", query, shown.join("\n"));
+    if matches.len() > MAX_TIMEZONE_RESULTS {
+        reply.push_str(&format!("\n…and {} more, try a longer query.", matches.len() - MAX_TIMEZONE_RESULTS));
+    }
+    reply
+}
+
+// Reply for !tz, converting a time between two timezones
+#[cfg(feature = "prefix-commands")]
+fn timezone_conversion_reply(args: &[String], locale: i18n::Locale) -> String {
+    let usage = "Usage: `!tz <HH:MM> <from_timezone> <to_timezone>`, e.g. `!tz 20:00 America/New_York Europe/Berlin`";
+    let parsed = CommandArgs::new(usage, args).and_then(|mut args| {
+        let parsed = (args.next_required("HH:MM")?, args.next_required("from_timezone")?, args.next_required("to_timezone")?);
+        args.finish()?;
+        Ok(parsed)
+    });
+    let (time, from, to) = match parsed {
+        Ok(parsed) => parsed,
+        Err(reply) => return reply,
+    };
+    let Ok(parsed_time) = chrono::NaiveTime::parse_from_str(&time, "%H:%M") else {
+        return format!("❌ '{}' isn't a time, expected HH:MM like 20:00", time);
+    };
+    let from_timezone = match config::parse_timezone(&from) {
+        Ok(timezone) => timezone,
+        Err(suggestions) => return unknown_timezone_reply(&from, &suggestions),
+    };
+    let to_timezone = match config::parse_timezone(&to) {
+        Ok(timezone) => timezone,
+        Err(suggestions) => return unknown_timezone_reply(&to, &suggestions),
+    };
+    game_night::format_timezone_conversion(parsed_time, from_timezone, to_timezone, locale)
+        .unwrap_or_else(|why| format!("❌ {}.", why))
+}
+
+// !mytz - pick the timezone !nextgame also shows the start time in
+#[cfg(feature = "prefix-commands")]
+async fn set_user_timezone(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let parsed = CommandArgs::new("Usage: `!mytz <timezone>`, e.g. `!mytz Europe/Berlin`", args).and_then(|mut args| {
+        let name = args.next_required("timezone")?;
+        args.finish()?;
+        Ok(name)
+    });
+    let name = match parsed {
+        Ok(name) => name,
+        Err(reply) => return reply,
+    };
+    let timezone = match config::parse_timezone(&name) {
+        Ok(timezone) => timezone,
+        Err(suggestions) => return unknown_timezone_reply(&name, &suggestions),
+    };
+
+    let timezones = {
+        let data = ctx.data.read().await;
+        data.get::<UserTimezonesKey>().expect("user timezones are inserted at startup").clone()
+    };
+    timezones.write().await.insert(msg.author.id, timezone);
+
+    let reply = format!("✅ `!nextgame` will also show game night times in {} for you.", timezone);
+    if !save_shared_state(ctx, storage::USER_TIMEZONES, &timezones, |store, timezones| {
+        storage::save_state(store, storage::USER_TIMEZONES, timezones)
+    }).await {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// !subscribe / !unsubscribe - get this server's game night reminders by DM,
+// on top of the ones posted in its reminder channel
+#[cfg(feature = "prefix-commands")]
+async fn edit_subscription(ctx: &Context, msg: &Message, subscribe: bool) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ Subscribe from inside a server, so I know whose reminders to send you.".to_string();
+    };
+
+    let subscribers = {
+        let data = ctx.data.read().await;
+        data.get::<SubscribersKey>().expect("subscribers are inserted at startup").clone()
+    };
+    let reply = {
+        let mut subscribers = subscribers.write().await;
+        if subscribe {
+            if !subscribers.entry(guild_id).or_default().insert(msg.author.id) {
+                return "🔔 You're already getting game night reminders by DM.".to_string();
+            }
+            "🔔 You'll get game night reminders by DM. Make sure DMs from server members are allowed."
+        } else {
+            let removed = subscribers.get_mut(&guild_id).is_some_and(|members| members.remove(&msg.author.id));
+            if !removed {
+                return "🔕 You weren't getting reminders by DM.".to_string();
+            }
+            if subscribers.get(&guild_id).is_some_and(|members| members.is_empty()) {
+                subscribers.remove(&guild_id);
+            }
+            "🔕 No more game night reminders by DM."
+        }
+    };
+
+    if !save_shared_state(ctx, storage::SUBSCRIBERS, &subscribers, |store, subscribers| {
+        storage::save_state(store, storage::SUBSCRIBERS, subscribers)
+    }).await {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply.to_string()
+}
+
+// !addgame / !removegame - curate the guild's own list of games to suggest
+#[cfg(feature = "prefix-commands")]
+async fn edit_game_library(ctx: &Context, msg: &Message, args: &[String], add: bool) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ Game libraries only exist inside a server.".to_string();
+    };
+    let name = args.join(" ");
+    let name = name.as_str();
+    if name.is_empty() {
+        return if add { "Usage: `!addgame <name>`" } else { "Usage: `!removegame <name>`" }.to_string();
+    }
+
+    let libraries = {
+        let data = ctx.data.read().await;
+        data.get::<GameLibraryKey>().expect("game libraries are inserted at startup").clone()
+    };
+    let reply = {
+        let mut libraries = libraries.write().await;
+        let library = libraries.entry(guild_id).or_default();
+
+        let reply = if add {
+            if !add_to_library(library, name) {
+                return format!("❌ **{}** is already in the game library.", name);
+            }
+            format!("✅ Added **{}** to the game library ({} games).", name, library.len())
+        } else {
+            if !remove_from_library(library, name) {
+                return format!("❌ **{}** isn't in the game library.", name);
+            }
+            format!("✅ Removed **{}** from the game library.", name)
+        };
+        if library.is_empty() {
+            libraries.remove(&guild_id);
+        }
+        reply
+    };
+
+    if !save_shared_state(ctx, storage::GAMES, &libraries, |store, libraries| store.save_games(libraries)).await {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// !gameweight - change how often a library game gets suggested
+#[cfg(feature = "prefix-commands")]
+async fn edit_game_weight(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ Game libraries only exist inside a server.".to_string();
+    };
+    let usage = format!(
+        "Usage: `!gameweight <name> <weight>` with a weight from 1 to {}",
+        game_night::MAX_GAME_WEIGHT
+    );
+    let Some((weight, name)) = args.split_last() else {
+        return usage;
+    };
+    let weight = match weight.parse::<u32>() {
+        Ok(weight) if (1..=game_night::MAX_GAME_WEIGHT).contains(&weight) => weight,
+        _ => return usage,
+    };
+    let name = name.join(" ");
+    if name.is_empty() {
+        return usage;
+    }
+
+    let libraries = {
+        let data = ctx.data.read().await;
+        data.get::<GameLibraryKey>().expect("game libraries are inserted at startup").clone()
+    };
+    {
+        let mut libraries = libraries.write().await;
+        let library = libraries.get_mut(&guild_id).map(Vec::as_mut_slice).unwrap_or_default();
+        if !set_game_weight(library, &name, weight) {
+            return format!("❌ **{}** isn't in the game library.", name);
+        }
+    }
+    let reply = format!("✅ **{}** now has a weight of {}.", name, weight);
+
+    if !save_shared_state(ctx, storage::GAMES, &libraries, |store, libraries| store.save_games(libraries)).await {
+        return format!("{}\n⚠️ Couldn't save the change, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// !suggest - a few games picked from the guild's library, or the built-in
+// games without one
+#[cfg(feature = "prefix-commands")]
+async fn suggest(ctx: &Context, guild_id: Option<GuildId>) -> String {
+    let libraries = {
+        let data = ctx.data.read().await;
+        data.get::<GameLibraryKey>().expect("game libraries are inserted at startup").clone()
+    };
+    let library = match guild_id {
+        Some(guild_id) => libraries.read().await.get(&guild_id).cloned().unwrap_or_default(),
+        None => Vec::new(),
+    };
+    get_game_suggestion(&library, None)
+}
+
+// Run a reaction poll in the message's channel for `minutes`, returning the
+// vote counts. Only one poll runs per channel at a time, so the channel gets
+// claimed before posting and released once voting closes.
+#[cfg(feature = "prefix-commands")]
+async fn run_channel_poll(
+    ctx: &Context,
+    msg: &Message,
+    title: &str,
+    options: &[String],
+    minutes: u64,
+) -> Result<Vec<usize>, String> {
+    let polls = {
+        let data = ctx.data.read().await;
+        data.get::<ActivePollsKey>().expect("active polls are inserted at startup").clone()
+    };
+
+    {
+        let mut polls = polls.lock().await;
+        if let Some(ends_at) = polls.get(&msg.channel_id) {
+            let minutes_left = (*ends_at - chrono::Utc::now()).num_minutes().max(0) + 1;
+            return Err(format!("❌ A vote is already running here, it closes in about {} minutes.", minutes_left));
+        }
+        polls.insert(msg.channel_id, chrono::Utc::now() + chrono::Duration::minutes(minutes as i64));
+    }
+
+    let window = std::time::Duration::from_secs(minutes * 60);
+    let result = poll::run_reaction_poll(ctx, msg.channel_id, title, options, window).await;
+    polls.lock().await.remove(&msg.channel_id);
+
+    result.map_err(|why| {
+        error!("Error running vote: {:?}", why);
+        "❌ Something went wrong running the vote.".to_string()
+    })
+}
+
+// !poll - ask the channel any question with up to 10 options
+#[cfg(feature = "prefix-commands")]
+async fn custom_poll(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let (question, options) = match poll::parse_poll(&args.join(" ")) {
+        Ok(poll) => poll,
+        Err(reply) => return reply,
+    };
+    match run_channel_poll(ctx, msg, &format!("📊 {}", question), &options, DEFAULT_VOTE_MINUTES).await {
+        Ok(counts) => poll::format_results(&question, &options, &counts),
+        Err(reply) => reply,
+    }
+}
+
+// !vote - let members pick tonight's game with a reaction poll
+#[cfg(feature = "prefix-commands")]
+async fn vote(ctx: &Context, msg: &Message, args: &[String]) -> String {
+    let minutes = match args.first() {
+        None => DEFAULT_VOTE_MINUTES,
+        Some(arg) => match arg.parse::<u64>() {
+            Ok(minutes) if (1..=MAX_VOTE_MINUTES).contains(&minutes) => minutes,
+            _ => return format!("Usage: `!vote [minutes]` with 1 to {} minutes", MAX_VOTE_MINUTES),
+        },
+    };
+
+    let libraries = {
+        let data = ctx.data.read().await;
+        data.get::<GameLibraryKey>().expect("game libraries are inserted at startup").clone()
+    };
+    let library = match msg.guild_id {
+        Some(guild_id) => libraries.read().await.get(&guild_id).cloned().unwrap_or_default(),
+        None => Vec::new(),
+    };
+    // "Tonight" is the date where the group plays, not in UTC
+    let schedule = guild_schedule(ctx, msg.guild_id).await;
+    let now = chrono::Utc::now();
+    let night = match schedule.next_occurrence() {
+        Ok((index, _)) => &schedule.nights[index],
+        Err(why) => return schedule_error_reply(&why),
+    };
+    let options = vote_candidates(&library, game_night::local_date(night, now));
+    let counts = match run_channel_poll(ctx, msg, "🗳️ Vote for tonight's game!", &options, minutes).await {
+        Ok(counts) => counts,
+        Err(reply) => return reply,
+    };
+    match poll::winners(&options, &counts).as_slice() {
+        [] => "🗳️ Voting closed, but nobody voted!".to_string(),
+        [winner] => format!("🏆 **Tonight's game:** {} with {} votes!", winner, counts.iter().max().unwrap_or(&0)),
+        tied => format!(
+            "🤝 **It's a tie!** {} each got {} votes.",
+            tied.join(", "),
+            counts.iter().max().unwrap_or(&0)
+        ),
+    }
+}
+
+// !rsvp / !attendance - record a response for the next game night, or show
+// everyone's responses so far
+// Record `user_id`'s response for the guild's next game night, replacing any
+// earlier one. Returns the guild's RSVPs afterwards and whether they were
+// saved, or the reply when the next game night can't be worked out.
+async fn record_rsvp(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    status: RsvpStatus,
+) -> Result<(rsvp::GuildRsvps, bool), String> {
+    let session = guild_schedule(ctx, Some(guild_id)).await.next_session().map_err(|why| schedule_error_reply(&why))?;
+
+    let rsvps = {
+        let data = ctx.data.read().await;
+        data.get::<RsvpKey>().expect("RSVPs are inserted at startup").clone()
+    };
+    let current = {
+        let mut rsvps = rsvps.write().await;
+        let current = rsvp::current_rsvps(&mut rsvps, guild_id, session, chrono::Utc::now());
+        current.responses.insert(user_id, status);
+        current.clone()
+    };
+
+    let saved = save_shared_state(ctx, storage::RSVPS, &rsvps, |store, rsvps| store.save_rsvps(rsvps)).await;
+    Ok((current, saved))
+}
+
+#[cfg(feature = "prefix-commands")]
+async fn rsvp(ctx: &Context, msg: &Message, status: Option<RsvpStatus>) -> String {
+    let Some(guild_id) = msg.guild_id else {
+        return "❌ RSVPs only work inside a server.".to_string();
+    };
+
+    let Some(status) = status else {
+        let session = match guild_schedule(ctx, Some(guild_id)).await.next_session() {
+            Ok(session) => session,
+            Err(why) => return schedule_error_reply(&why),
+        };
+        let rsvps = {
+            let data = ctx.data.read().await;
+            data.get::<RsvpKey>().expect("RSVPs are inserted at startup").clone()
+        };
+        let mut rsvps = rsvps.write().await;
+        return rsvp::format_attendance(rsvp::current_rsvps(&mut rsvps, guild_id, session, chrono::Utc::now()));
+    };
+
+    let (current, saved) = match record_rsvp(ctx, guild_id, msg.author.id, status).await {
+        Ok(recorded) => recorded,
+        Err(reply) => return reply,
+    };
+    let response = match status {
+        RsvpStatus::Yes => "✅ You're going",
+        RsvpStatus::No => "❌ You're not going",
+        RsvpStatus::Maybe => "🤔 You might make it",
+    };
+    let reply = format!("{} to game night on {}.", response, to_discord_timestamp(current.session_start, 'F'));
+    if !saved {
+        return format!("{}\n⚠️ Couldn't save your RSVP, it will be lost on restart.", reply);
+    }
+    reply
+}
+
+// A click on one of the Going / Maybe / Can't buttons under a reminder or
+// announcement. Records the RSVP and updates the counts on that message.
+async fn rsvp_button(ctx: &Context, component: &ComponentInteraction, status: RsvpStatus) {
+    let Some(guild_id) = component.guild_id else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("❌ RSVPs only work inside a server.")
+            .ephemeral(true);
+        if let Err(why) = component.create_response(&ctx.http, CreateInteractionResponse::Message(response)).await {
+            error!("Error responding to interaction: {:?}", why);
+        }
+        return;
+    };
+
+    let (current, saved) = match record_rsvp(ctx, guild_id, component.user.id, status).await {
+        Ok(recorded) => recorded,
+        Err(reply) => {
+            let response = CreateInteractionResponseMessage::new().content(reply).ephemeral(true);
+            if let Err(why) = component.create_response(&ctx.http, CreateInteractionResponse::Message(response)).await {
+                error!("Error responding to interaction: {:?}", why);
+            }
+            return;
+        }
+    };
+    let update = CreateInteractionResponseMessage::new().embed(rsvp::rsvp_counts_embed(&current));
+    if let Err(why) = component.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(update)).await {
+        error!("Error responding to interaction: {:?}", why);
+        return;
+    }
+    if !saved {
+        let warning = CreateInteractionResponseFollowup::new()
+            .content("⚠️ Couldn't save your RSVP, it will be lost on restart.")
+            .ephemeral(true);
+        if let Err(why) = component.create_followup(&ctx.http, warning).await {
+            error!("Error sending message: {:?}", why);
+        }
+    }
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     // Called when a message is created
+    #[cfg(feature = "prefix-commands")]
     async fn message(&self, ctx: Context, msg: Message) {
         // Ignore messages from the bot itself
         if msg.author.bot {
             return;
         }
 
-        let content = msg.content.to_lowercase();
-        
-        match content.as_str() {
-            "!ping" => {
-                if let Err(why) = msg.channel_id.say(&ctx.http, "Pong!").await {
-                    println!("Error sending message: {:?}", why);
-                }
+        let prefix = guild_prefix(&ctx, msg.guild_id).await;
+        let Some(content) = commands::strip_prefix(&msg.content, &prefix) else {
+            return;
+        };
+        let Some((command, args)) = commands::parse_command(content) else {
+            return;
+        };
+        let command = command.as_str();
+
+        // Commands used again too soon get a clock reaction the first time and
+        // are dropped after that
+        let cooldowns = {
+            let data = ctx.data.read().await;
+            data.get::<CooldownsKey>().expect("cooldowns are inserted at startup").clone()
+        };
+        let check = cooldowns.lock().await.check(msg.author.id, command);
+        if let cooldown::CooldownCheck::Waiting { first } = check {
+            if first && let Err(why) = msg.react(&ctx.http, '⏰').await {
+                error!("Error adding reaction: {:?}", why);
             }
-            "!gamenight" => {
-                let config = GameNightConfig::default();
-                let status = format_game_night_status(&config);
-                if let Err(why) = msg.channel_id.say(&ctx.http, status).await {
-                    println!("Error sending message: {:?}", why);
+            return;
+        }
+
+        record_command(&ctx, command).await;
+
+        // Every log line while handling the command says which command and channel it was
+        let span = info_span!("command", command, channel_id = %msg.channel_id);
+        async {
+            match command {
+                "ping" => {
+                    if let Err(why) = msg.channel_id.say(&ctx.http, "Pong!").await {
+                        error!("Error sending message: {:?}", why);
+                    }
                 }
-            }
-            "!nextgame" => {
-                let config = GameNightConfig::default();
-                let next_game = format_next_game_night(&config);
-                if let Err(why) = msg.channel_id.say(&ctx.http, next_game).await {
-                    println!("Error sending message: {:?}", why);
+                "gamenight" if !args.is_empty() => {
+                    let response = game_night_ahead_reply(&ctx, msg.guild_id, &args).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
                 }
-            }
-            "!isgamenight" => {
-                let config = GameNightConfig::default();
-                let response = if is_game_night_now(&config) {
-                    "Yes! Game night is happening now! 🎮"
-                } else {
-                    "No, it's not game night yet. Use !gamenight to see when the next one is."
-                };
-                if let Err(why) = msg.channel_id.say(&ctx.http, response).await {
-                    println!("Error sending message: {:?}", why);
+                "gamenight" => {
+                    let status = game_night_status_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = status.send(&ctx.http, msg.channel_id).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "gamenightconfig" => {
+                    let reply = game_night_config_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = msg.channel_id.send_message(&ctx.http, reply.message()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "nextgame" => {
+                    let next_game = next_game_reply(&ctx, msg.guild_id, msg.author.id, &args).await;
+                    if let Err(why) = next_game.send(&ctx.http, msg.channel_id).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "nextgamejson" => {
+                    let response = next_game_json_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "countdown" => {
+                    let response = countdown_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "livecountdown" => {
+                    if let Some(response) = live_countdown_reply(&ctx, &msg).await
+                        && let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await
+                    {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "remindme" => {
+                    let response = remind_me(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "timeleft" => {
+                    let response = time_left_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "until" => {
+                    let response = until_reply(&ctx, msg.guild_id, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "isgamenight" => {
+                    let response = is_game_night_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setgamenight" => {
+                    let response = set_game_night(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "override" | "clearoverride" => {
+                    let response = set_override(&ctx, &msg, &args, command == "clearoverride").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "skip" | "unskip" => {
+                    let response = skip_game_night(&ctx, &msg, command == "skip").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "holiday" => {
+                    let response = set_holiday_policy(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "addspecial" => {
+                    let response = add_special(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "disablespecial" | "enablespecial" => {
+                    let response = toggle_special(&ctx, &msg, &args, command == "enablespecial").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setchannel" => {
+                    let response = set_channel(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "pause" | "resume" => {
+                    let response = pause_announcements(&ctx, &msg, command == "pause").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setreminder" => {
+                    let response = set_reminder(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setlocale" => {
+                    let response = set_locale(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setemoji" => {
+                    let response = set_emoji(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setcolor" => {
+                    let response = set_color(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setnotifyrole" => {
+                    let response = set_notify_role(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "addgame" | "removegame" => {
+                    let response = edit_game_library(&ctx, &msg, &args, command == "addgame").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "gameweight" => {
+                    let response = edit_game_weight(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "suggest" => {
+                    let response = suggest(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "preview" => {
+                    // The status pings the notify role, which a preview shouldn't
+                    let response = preview_reply(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "debug" => {
+                    let response = debug_reply(&ctx, &msg).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "about" => {
+                    let about = about_reply(&ctx).await;
+                    if let Err(why) = msg.channel_id.send_message(&ctx.http, about.message()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "stats" => {
+                    let response = stats_reply(&ctx, &msg).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "tz" => {
+                    let locale = guild_schedule(&ctx, msg.guild_id).await.locale();
+                    let response = timezone_conversion_reply(&args, locale);
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "tzsearch" => {
+                    let response = timezone_search_reply(&args);
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "mytz" => {
+                    let response = set_user_timezone(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "subscribe" | "unsubscribe" => {
+                    let response = edit_subscription(&ctx, &msg, command == "subscribe").await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "setprefix" => {
+                    let response = set_prefix(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "poll" => {
+                    let response = custom_poll(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "vote" => {
+                    let response = vote(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "nextgames" => {
+                    let response = next_games_reply(&ctx, msg.guild_id, args.first().map(String::as_str)).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "lineup" => {
+                    let response = lineup_reply(&ctx, msg.guild_id, args.first().map(String::as_str)).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "specialnights" => {
+                    let response = special_nights_reply(&ctx, msg.guild_id, args.first().map(String::as_str)).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "history" => {
+                    let response = history_reply(&ctx, msg.guild_id, args.first().map(String::as_str)).await;
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "streak" => {
+                    // Names the member by mention, so make sure nobody gets pinged
+                    let response = streak_reply(&ctx, &msg).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
                 }
+                "calendar" => {
+                    if let Err(why) = send_calendar(&ctx, &msg).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "exportconfig" => {
+                    if let Err(why) = send_config_export(&ctx, &msg).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "rsvp" => {
+                    let response = match args.first().map(|arg| arg.parse()) {
+                        Some(Ok(status)) => rsvp(&ctx, &msg, Some(status)).await,
+                        _ => "Usage: `!rsvp yes|no|maybe`".to_string(),
+                    };
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, response).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "attendance" => {
+                    // Lists members by mention, so make sure nobody gets pinged
+                    let response = rsvp(&ctx, &msg, None).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "attendancerate" => {
+                    // Names the member by mention, so make sure nobody gets pinged
+                    let response = attendance_rate_reply(&ctx, &msg, &args).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "whoisplaying" => {
+                    // Lists members by mention, so make sure nobody gets pinged
+                    let response = who_is_playing_reply(&ctx, msg.guild_id).await;
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, msg.channel_id, response, Vec::new()).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                "help" => {
+                    // Show the commands with the prefix this guild actually uses
+                    let locale = guild_schedule(&ctx, msg.guild_id).await.locale();
+                    let help = i18n::tr(locale, i18n::Text::Help).replace("`!", &format!("`{}", prefix));
+                    if let Err(why) = messaging::send_chunked(&ctx.http, msg.channel_id, help).await {
+                        error!("Error sending message: {:?}", why);
+                    }
+                }
+                _ => {}
             }
-            "!help" => {
-                let help_text = "**Available Commands:**\n\
-                    `!ping` - Test if bot is responsive\n\
-                    `!gamenight` - Show game night status\n\
-                    `!nextgame` - Show when the next game night is\n\
-                    `!isgamenight` - Check if game night is happening now\n\
-                    `!help` - Show this help message";
-                
-                if let Err(why) = msg.channel_id.say(&ctx.http, help_text).await {
-                    println!("Error sending message: {:?}", why);
+        }
+        .instrument(span)
+        .await
+    }
+
+    // Called when the bot is ready
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+        {
+            let data = ctx.data.read().await;
+            let guilds = data.get::<ConnectedGuildsKey>().expect("connected guilds are inserted at startup");
+            *guilds.write().await = ready.guilds.iter().map(|guild| guild.id).collect();
+        }
+
+        let started = {
+            let data = ctx.data.read().await;
+            data.get::<SchedulerStartedKey>().expect("the scheduler flag is inserted at startup").clone()
+        };
+        // The slash commands and the scheduler from the first ready are still there
+        if !scheduler::claim_start(&started) {
+            info!("Reconnected, keeping the running scheduler");
+            return;
+        }
+        #[cfg(feature = "prefix-commands")]
+        ctx.data.write().await.insert::<StartedAtKey>(std::time::Instant::now());
+
+        if let Err(why) = slash::register(&ctx).await {
+            error!("Error registering slash commands: {:?}", why);
+        }
+
+        // Start posting game night reminders and announcements in the background
+        tokio::spawn(scheduler::run(ctx));
+    }
+
+    // Called when a guild becomes available, with who's in its voice channels
+    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: Option<bool>) {
+        let data = ctx.data.read().await;
+        let guilds = data.get::<ConnectedGuildsKey>().expect("connected guilds are inserted at startup");
+        guilds.write().await.insert(guild.id);
+        let states = data.get::<VoiceStatesKey>().expect("voice states are inserted at startup");
+        voice::load_guild(&mut *states.write().await, guild.id, guild.voice_states.values());
+    }
+
+    // Called when the bot leaves or gets removed from a guild, or the guild
+    // goes down in an outage, in which case the bot is still in it
+    async fn guild_delete(&self, ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        if incomplete.unavailable {
+            return;
+        }
+        let data = ctx.data.read().await;
+        let guilds = data.get::<ConnectedGuildsKey>().expect("connected guilds are inserted at startup");
+        guilds.write().await.remove(&incomplete.id);
+    }
+
+    // Called when someone joins, leaves or moves between voice channels
+    async fn voice_state_update(&self, ctx: Context, _old: Option<VoiceState>, new: VoiceState) {
+        let Some(guild_id) = new.guild_id else {
+            return;
+        };
+        let data = ctx.data.read().await;
+        let states = data.get::<VoiceStatesKey>().expect("voice states are inserted at startup");
+        voice::update(&mut *states.write().await, guild_id, new.user_id, new.channel_id);
+    }
+
+    // Called when someone uses a slash command
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::Command(command) => slash::handle(&ctx, &command).await,
+            Interaction::Autocomplete(command) => slash::autocomplete(&ctx, &command).await,
+            Interaction::Component(component) => {
+                if let Some(status) = RsvpStatus::from_button_id(&component.data.custom_id) {
+                    rsvp_button(&ctx, &component, status).await;
                 }
             }
             _ => {}
         }
     }
+}
 
-    // Called when the bot is ready
-    async fn ready(&self, _: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
+// Resolves once the bot is asked to stop, with Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate = signal(SignalKind::terminate()).expect("can listen for SIGTERM");
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    if let Err(why) = ctrl_c.await {
+        error!("Error listening for Ctrl+C: {:?}", why);
+    }
+}
+
+// Write all of the runtime state to disk. Every change already gets saved as
+// it happens, this catches anything a failed save left behind.
+async fn save_state(data: &RwLock<TypeMap>) {
+    // Everything is copied or serialized under the locks, and written once
+    // they're let go
+    let (saving, store, schedules, games, rsvps, history, documents) = {
+        let data = data.read().await;
+        let schedules = data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup");
+        let save_lock = data.get::<SaveLockKey>().expect("the save lock is inserted at startup").clone();
+        let games = data.get::<GameLibraryKey>().expect("game libraries are inserted at startup");
+        let rsvps = data.get::<RsvpKey>().expect("RSVPs are inserted at startup");
+        let prefixes = data.get::<PrefixKey>().expect("prefixes are inserted at startup");
+        let history = data.get::<HistoryKey>().expect("history is inserted at startup");
+        let timezones = data.get::<UserTimezonesKey>().expect("user timezones are inserted at startup");
+        let streaks = data.get::<StreakKey>().expect("streaks are inserted at startup");
+        let subscribers = data.get::<SubscribersKey>().expect("subscribers are inserted at startup");
+        let store = data.get::<StoreKey>().expect("the store is inserted at startup").clone();
+        let saving = save_lock.lock_owned().await;
+        let documents = [
+            (storage::PREFIXES, storage::to_json(&*prefixes.read().await)),
+            (storage::USER_TIMEZONES, storage::to_json(&*timezones.read().await)),
+            (storage::STREAKS, storage::to_json(&*streaks.read().await)),
+            (storage::SUBSCRIBERS, storage::to_json(&*subscribers.read().await)),
+        ];
+        (
+            saving,
+            store,
+            schedules.read().await.clone(),
+            games.read().await.clone(),
+            rsvps.read().await.clone(),
+            history.read().await.clone(),
+            documents,
+        )
+    };
+
+    let saved = tokio::task::spawn_blocking(move || {
+        let mut saved = vec![
+            (storage::GUILD_SCHEDULES, store.save_guild_schedules(&schedules)),
+            (storage::GAMES, store.save_games(&games)),
+            (storage::RSVPS, store.save_rsvps(&rsvps)),
+            (storage::HISTORY, store.save_history(&history)),
+        ];
+        for (name, contents) in documents {
+            saved.push((name, contents.and_then(|contents| store.save(name, &contents))));
+        }
+        saved
+    })
+    .await
+    .expect("saving state doesn't panic");
+    drop(saving);
+    for (name, result) in saved {
+        if let Err(why) = result {
+            error!("Error saving {}: {}", name, why);
+        }
     }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<(), Box<dyn Error>> {
     // Load .env file
     dotenv().ok();
-    
+
+    // Log to stdout, filtered by RUST_LOG (e.g. RUST_LOG=debug) when it's set
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    let dry_run = dry_run_time(std::env::args().skip(1))?;
+
+    // Load the game night schedule, falling back to the defaults without a config file
+    let schedule = GameNightSchedule::load(Path::new(CONFIG_PATH))
+        .map_err(|why| StartupError(format!("could not load {}: {}", CONFIG_PATH, why)))?;
+    if let Some(now) = dry_run {
+        println!("{}", game_night::format_preview_at(&schedule, now));
+        return Ok(());
+    }
+
     // Get token from environment variable
-    let token = std::env::var("DISCORD_TOKEN")
-        .expect("Expected DISCORD_TOKEN in environment");
-    
+    let token = discord_token()?;
+    #[cfg(feature = "prefix-commands")]
+    let cooldowns = command_cooldowns()?;
+    let store = open_state_store()?;
+    let guild_schedules = store
+        .load_guild_schedules()
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::GUILD_SCHEDULES, why)))?;
+    let game_libraries = store
+        .load_games()
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::GAMES, why)))?;
+    let rsvps = store.load_rsvps().map_err(|why| StartupError(format!("could not load {}: {}", storage::RSVPS, why)))?;
+    let prefixes: HashMap<GuildId, String> = storage::load_state(&*store, storage::PREFIXES)
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::PREFIXES, why)))?;
+    let history = store
+        .load_history()
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::HISTORY, why)))?;
+    let streaks: HashMap<GuildId, HashMap<UserId, history::Streak>> = storage::load_state(&*store, storage::STREAKS)
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::STREAKS, why)))?;
+    let user_timezones: HashMap<UserId, chrono_tz::Tz> = storage::load_state(&*store, storage::USER_TIMEZONES)
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::USER_TIMEZONES, why)))?;
+    let subscribers: HashMap<GuildId, HashSet<UserId>> = storage::load_state(&*store, storage::SUBSCRIBERS)
+        .map_err(|why| StartupError(format!("could not load {}: {}", storage::SUBSCRIBERS, why)))?;
+
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES
+    let mut intents = GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_VOICE_STATES
+        | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT;
+        | GatewayIntents::GUILD_MESSAGE_REACTIONS
+        | GatewayIntents::DIRECT_MESSAGE_REACTIONS;
+    // Reading commands out of messages needs the privileged message content intent
+    if cfg!(feature = "prefix-commands") {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
 
     // Create a new instance of the Client
-    let mut client = Client::builder(&token, intents)
+    let builder = Client::builder(&token, intents)
         .event_handler(Handler)
-        .await
-        .expect("Err creating client");
+        .type_map_insert::<DefaultScheduleKey>(Arc::new(schedule))
+        .type_map_insert::<GuildSchedulesKey>(Arc::new(RwLock::new(guild_schedules)))
+        .type_map_insert::<GameLibraryKey>(Arc::new(RwLock::new(game_libraries)))
+        .type_map_insert::<RsvpKey>(Arc::new(RwLock::new(rsvps)))
+        .type_map_insert::<PrefixKey>(Arc::new(RwLock::new(prefixes)))
+        .type_map_insert::<HistoryKey>(Arc::new(RwLock::new(history)))
+        .type_map_insert::<StreakKey>(Arc::new(RwLock::new(streaks)))
+        .type_map_insert::<UserTimezonesKey>(Arc::new(RwLock::new(user_timezones)))
+        .type_map_insert::<SubscribersKey>(Arc::new(RwLock::new(subscribers)))
+        .type_map_insert::<StoreKey>(store)
+        .type_map_insert::<SaveLockKey>(Arc::new(Mutex::new(())))
+        .type_map_insert::<SchedulerStartedKey>(Arc::new(AtomicBool::new(false)))
+        .type_map_insert::<ConnectedGuildsKey>(Arc::new(RwLock::new(HashSet::new())))
+        .type_map_insert::<CommandMetricsKey>(Arc::new(metrics::CommandMetrics::new()))
+        .type_map_insert::<VoiceStatesKey>(Arc::new(RwLock::new(voice::VoiceStates::new())))
+        .type_map_insert::<NextOccurrenceCacheKey>(Arc::new(Mutex::new(cache::NextOccurrenceCache::default())));
+    #[cfg(feature = "prefix-commands")]
+    let builder = builder
+        .type_map_insert::<ActivePollsKey>(Arc::new(Mutex::new(HashMap::new())))
+        .type_map_insert::<CooldownsKey>(Arc::new(Mutex::new(cooldowns)))
+        .type_map_insert::<LiveCountdownsKey>(Arc::new(Mutex::new(live_countdown::LiveCountdowns::default())))
+        .type_map_insert::<PersonalRemindersKey>(Arc::new(Mutex::new(personal_reminder::PersonalReminders::default())));
+    let mut client =
+        builder.await.map_err(|why| StartupError(format!("could not create the Discord client: {}", why)))?;
+
+    #[cfg(feature = "health-server")]
+    {
+        let port = match std::env::var("HEALTH_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| StartupError(format!("HEALTH_PORT must be a port number, got '{}'", port)))?,
+            Err(_) => health::DEFAULT_PORT,
+        };
+        tokio::spawn(health::serve(port, client.data.clone(), client.shard_manager.clone()));
+    }
+
+    // Disconnect from Discord on Ctrl+C or SIGTERM, which makes start() return
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down");
+        shard_manager.shutdown_all().await;
+    });
 
     // Start listening for events
-    if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+    client.start().await.map_err(|why| match why {
+        serenity::Error::Gateway(GatewayError::InvalidAuthentication) => StartupError(
+            "Discord rejected DISCORD_TOKEN; reset the token on the Bot page of the Discord developer portal and update .env"
+                .to_string(),
+        ),
+        why => StartupError(format!("client error: {}", why)),
+    })?;
+
+    #[cfg(feature = "prefix-commands")]
+    {
+        live_countdown::stop_all(&client.http, &client.data).await;
+        personal_reminder::cancel_all(&client.data).await;
     }
+    save_state(&client.data).await;
+    info!("Shut down cleanly");
+    Ok(())
 }
@@ -0,0 +1,242 @@
+#[cfg(feature = "prefix-commands")]
+use chrono::{DateTime, Utc};
+use serenity::builder::{
+    CreateActionRow, CreateAllowedMentions, CreateEmbed, CreateInteractionResponseMessage, CreateMessage,
+};
+use serenity::http::Http;
+#[cfg(feature = "prefix-commands")]
+use serenity::http::HttpError;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, RoleId};
+
+// Most characters Discord takes in one message
+pub const MESSAGE_LIMIT: usize = 2000;
+
+// Send `content` to `channel_id`, letting it ping exactly the given roles.
+// Any other mention in the content (users, @everyone) stays silent. Content
+// too long for one message goes out as several.
+pub async fn send_with_role_pings(
+    http: &Http,
+    channel_id: ChannelId,
+    content: String,
+    roles: Vec<RoleId>,
+) -> serenity::Result<Message> {
+    send_with_components(http, channel_id, content, roles, Vec::new()).await
+}
+
+// Same as send_with_role_pings, with buttons or other components under the
+// last message
+pub async fn send_with_components(
+    http: &Http,
+    channel_id: ChannelId,
+    content: String,
+    roles: Vec<RoleId>,
+    components: Vec<CreateActionRow>,
+) -> serenity::Result<Message> {
+    let chunks = split_message(&content, MESSAGE_LIMIT);
+    let last = chunks.len() - 1;
+    let mut sent = None;
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let mut message = CreateMessage::new()
+            .content(chunk)
+            .allowed_mentions(CreateAllowedMentions::new().roles(roles.clone()));
+        if index == last {
+            message = message.components(components.clone());
+        }
+        sent = Some(channel_id.send_message(http, message).await?);
+    }
+    Ok(sent.expect("split_message returns at least one chunk"))
+}
+
+// Send `content` to `channel_id` like `say`, split into several messages when
+// it's too long for one. Returns the last message sent.
+#[cfg(feature = "prefix-commands")]
+pub async fn send_chunked(http: &Http, channel_id: ChannelId, content: impl Into<String>) -> serenity::Result<Message> {
+    let content = content.into();
+    let mut sent = None;
+    for chunk in split_message(&content, MESSAGE_LIMIT) {
+        sent = Some(channel_id.say(http, chunk).await?);
+    }
+    Ok(sent.expect("split_message returns at least one chunk"))
+}
+
+// Split `content` into pieces of at most `limit` characters, breaking between
+// lines. A line too long by itself gets cut wherever it has to be. A code
+// block cut in two is closed at the end of one piece and opened again at the
+// start of the next, so both still render as code.
+pub fn split_message(content: &str, limit: usize) -> Vec<String> {
+    // Leaves room for closing a code block that has to be cut
+    let budget = limit - "\n```".len();
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_len = 0;
+    // Whether `chunk` has a line yet, which may be an empty one
+    let mut has_line = false;
+    // The line that opened the code block we're in, if any
+    let mut fence: Option<String> = None;
+
+    for line in content.split('\n') {
+        let line_chars: Vec<char> = line.chars().collect();
+        // Short enough for a reopened fence to still leave room for text
+        let piece_limit = budget - fence.as_ref().map_or(0, |fence| fence.chars().count() + 1);
+        let mut pieces: Vec<String> = line_chars.chunks(piece_limit).map(|piece| piece.iter().collect()).collect();
+        if pieces.is_empty() {
+            pieces.push(String::new());
+        }
+        for piece in pieces {
+            let piece_len = piece.chars().count();
+            if has_line && chunk_len + 1 + piece_len > budget {
+                if fence.is_some() {
+                    chunk.push_str("\n```");
+                }
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_len = 0;
+                has_line = false;
+                if let Some(fence) = &fence {
+                    chunk.push_str(fence);
+                    chunk_len = fence.chars().count();
+                    has_line = true;
+                }
+            }
+            if has_line {
+                chunk.push('\n');
+                chunk_len += 1;
+            }
+            chunk.push_str(&piece);
+            chunk_len += piece_len;
+            has_line = true;
+        }
+        if line.trim_start().starts_with("```") {
+            fence = match fence {
+                Some(_) => None,
+                None => Some(line.trim().to_string()),
+            };
+        }
+    }
+    chunks.push(chunk);
+    chunks
+}
+
+// Error code Discord answers with when the bot lacks a permission it needs,
+// e.g. Embed Links for a message with embeds
+#[cfg(feature = "prefix-commands")]
+const MISSING_PERMISSIONS: isize = 50013;
+
+// A reply made of embeds, with optional text above them for role pings and
+// notes. Only the given roles may be pinged. `fallback` says the same thing
+// as plain text, for channels the bot can't post embeds in.
+pub struct EmbedReply {
+    pub content: String,
+    pub embeds: Vec<CreateEmbed>,
+    pub roles: Vec<RoleId>,
+    #[cfg(feature = "prefix-commands")]
+    pub fallback: Option<String>,
+}
+
+impl EmbedReply {
+    // Post the reply in `channel_id`, as its fallback text when Discord won't
+    // take the embeds there
+    #[cfg(feature = "prefix-commands")]
+    pub async fn send(mut self, http: &Http, channel_id: ChannelId) -> serenity::Result<Message> {
+        let fallback = self.fallback.take();
+        let roles = self.roles.clone();
+        match channel_id.send_message(http, self.message()).await {
+            Err(why) if is_missing_permissions(&why) => match fallback {
+                Some(fallback) => send_with_role_pings(http, channel_id, fallback, roles).await,
+                None => Err(why),
+            },
+            sent => sent,
+        }
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    pub fn message(self) -> CreateMessage {
+        let mut message = CreateMessage::new()
+            .embeds(self.embeds)
+            .allowed_mentions(CreateAllowedMentions::new().roles(self.roles));
+        if !self.content.is_empty() {
+            message = message.content(self.content);
+        }
+        message
+    }
+
+    // The same reply, for answering a slash command
+    pub fn interaction(self) -> CreateInteractionResponseMessage {
+        let mut response = CreateInteractionResponseMessage::new()
+            .embeds(self.embeds)
+            .allowed_mentions(CreateAllowedMentions::new().roles(self.roles));
+        if !self.content.is_empty() {
+            response = response.content(self.content);
+        }
+        response
+    }
+}
+
+#[cfg(feature = "prefix-commands")]
+fn is_missing_permissions(why: &serenity::Error) -> bool {
+    matches!(
+        why,
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response)) if response.error.code == MISSING_PERMISSIONS
+    )
+}
+
+// Discord timestamp markup, shown in every reader's own timezone. `style` is
+// one of Discord's format letters, e.g. 'F' for the full date and time or 'R'
+// for a relative time that keeps counting down on its own.
+#[cfg(feature = "prefix-commands")]
+pub fn to_discord_timestamp(datetime: DateTime<Utc>, style: char) -> String {
+    format!("<t:{}:{}>", datetime.timestamp(), style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message_on_lines() {
+        let content: Vec<String> = (0..300).map(|i| format!("Line number {}\n", i)).collect();
+        let content = content.join("\n");
+        assert!(content.len() > MESSAGE_LIMIT);
+
+        let chunks = split_message(&content, MESSAGE_LIMIT);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= MESSAGE_LIMIT));
+        assert_eq!(chunks.join("\n"), content);
+        assert_eq!(split_message("short", MESSAGE_LIMIT), vec!["short"]);
+        assert_eq!(split_message("", MESSAGE_LIMIT), vec![""]);
+    }
+
+    #[test]
+    fn test_split_message_cuts_long_lines() {
+        let content = "é".repeat(4500);
+        let chunks = split_message(&content, MESSAGE_LIMIT);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= MESSAGE_LIMIT));
+        assert_eq!(chunks.concat(), content);
+    }
+
+    #[test]
+    fn test_split_message_reopens_code_blocks() {
+        let lines: Vec<String> = (0..300).map(|i| format!("night {}", i)).collect();
+        let content = format!("Schedule\n```text\n{}\n```\nDone", lines.join("\n"));
+
+        let chunks = split_message(&content, 500);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 500);
+            assert_eq!(chunk.matches("```").count() % 2, 0, "{}", chunk);
+        }
+        assert!(chunks[1].starts_with("```text\n"));
+        assert!(chunks.last().unwrap().ends_with("```\nDone"));
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_to_discord_timestamp() {
+        use chrono::TimeZone;
+
+        let datetime = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        assert_eq!(to_discord_timestamp(datetime, 'F'), "<t:1717804800:F>");
+        assert_eq!(to_discord_timestamp(datetime, 'R'), "<t:1717804800:R>");
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "prefix-commands")]
+use std::time::Duration;
+
+#[cfg(feature = "prefix-commands")]
+use serenity::builder::CreateEmbed;
+
+// Every prefix command the bot answers to. Slash commands share these names,
+// and /settings, which is slash only, is counted here too.
+pub const COMMAND_NAMES: &[&str] = &[
+    "ping", "gamenight", "gamenightconfig", "nextgame", "nextgamejson", "countdown", "livecountdown", "remindme",
+    "timeleft", "until", "isgamenight", "setgamenight", "override", "clearoverride", "skip", "unskip", "holiday",
+    "addspecial", "disablespecial", "enablespecial", "setchannel", "pause", "resume", "setreminder", "setlocale",
+    "setemoji", "setcolor", "setnotifyrole", "addgame", "removegame", "gameweight", "suggest", "debug", "preview",
+    "stats", "about", "tz", "tzsearch", "mytz", "setprefix", "poll", "vote", "nextgames", "lineup", "specialnights",
+    "history", "streak", "attendancerate", "subscribe", "unsubscribe", "calendar", "exportconfig", "rsvp",
+    "attendance", "whoisplaying", "settings", "help",
+];
+
+// How often each command was used since the bot started, for !stats. The
+// map never changes after it's made, so counting a command is a single
+// atomic increment and needs no lock.
+pub struct CommandMetrics {
+    counts: HashMap<&'static str, AtomicU64>,
+}
+
+impl CommandMetrics {
+    pub fn new() -> Self {
+        Self {
+            counts: COMMAND_NAMES.iter().map(|name| (*name, AtomicU64::new(0))).collect(),
+        }
+    }
+
+    // Count one use of `command`. Anything that isn't a command is ignored.
+    pub fn record(&self, command: &str) {
+        if let Some(count) = self.counts.get(command) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Commands used at least once, most used first
+    #[cfg(feature = "prefix-commands")]
+    pub fn counts(&self) -> Vec<(&'static str, u64)> {
+        let mut counts: Vec<(&'static str, u64)> = self.counts
+            .iter()
+            .map(|(name, count)| (*name, count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+}
+
+// Format for !stats
+#[cfg(feature = "prefix-commands")]
+pub fn format_stats(counts: &[(&str, u64)], uptime: Duration) -> String {
+    let total: u64 = counts.iter().map(|(_, count)| count).sum();
+    let uptime = crate::game_night::format_duration(
+        chrono::Duration::seconds(uptime.as_secs() as i64),
+        crate::i18n::Locale::English,
+    );
+    let mut stats = format!("📊 **Bot Stats**\n⏱️ Up for {}\n🔢 {} commands used", uptime, total);
+    if !counts.is_empty() {
+        let lines: Vec<String> = counts.iter().map(|(name, count)| format!("{:<16} {}", name, count)).collect();
+        stats.push_str(&format!("\n```\n{}\n```", lines.join("\n")));
+    }
+    stats
+}
+
+// Embed fields for !about: the version running, how long it's been up and
+// how many servers it's in
+#[cfg(feature = "prefix-commands")]
+fn about_fields(version: &str, uptime: Duration, guilds: usize) -> Vec<(&'static str, String)> {
+    let uptime = crate::game_night::format_duration(
+        chrono::Duration::seconds(uptime.as_secs() as i64),
+        crate::i18n::Locale::English,
+    );
+    vec![
+        ("🏷️ Version", version.to_string()),
+        ("⏱️ Uptime", uptime),
+        ("🏠 Servers", guilds.to_string()),
+    ]
+}
+
+#[cfg(feature = "prefix-commands")]
+pub fn about_embed(version: &str, uptime: Duration, guilds: usize) -> CreateEmbed {
+    about_fields(version, uptime, guilds)
+        .into_iter()
+        .fold(CreateEmbed::new().title("🤖 About Tannius Bot"), |embed, (name, value)| embed.field(name, value, true))
+}
+
+#[cfg(all(test, feature = "prefix-commands"))]
+mod tests {
+    use super::*;
+    use crate::i18n::{Locale, Text, tr};
+
+    #[test]
+    fn test_record_counts() {
+        let metrics = CommandMetrics::new();
+        assert!(metrics.counts().is_empty());
+
+        metrics.record("help");
+        metrics.record("gamenight");
+        metrics.record("gamenight");
+        metrics.record("notacommand");
+        assert_eq!(metrics.counts(), vec![("gamenight", 2), ("help", 1)]);
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let stats = format_stats(&[("gamenight", 2), ("help", 1)], Duration::from_secs(3661));
+        assert_eq!(
+            stats,
+            "📊 **Bot Stats**\n⏱️ Up for 0 days, 1 hours, 1 minutes, 1 seconds\n🔢 3 commands used\n\
+            ```\ngamenight        2\nhelp             1\n```"
+        );
+        assert!(!format_stats(&[], Duration::ZERO).contains("```"));
+    }
+
+    #[test]
+    fn test_about_fields() {
+        assert_eq!(
+            about_fields("0.1.0", Duration::from_secs(90061), 3),
+            vec![
+                ("🏷️ Version", "0.1.0".to_string()),
+                ("⏱️ Uptime", "1 days, 1 hours, 1 minutes, 1 seconds".to_string()),
+                ("🏠 Servers", "3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_help_commands_are_counted() {
+        for line in tr(Locale::English, Text::Help).lines().skip(1) {
+            for command in line.split("`!").skip(1) {
+                let name = command.split([' ', '`']).next().unwrap_or_default();
+                assert!(COMMAND_NAMES.contains(&name), "{} isn't in COMMAND_NAMES", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serenity::builder::CreateMessage;
+use serenity::http::Http;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::messaging::to_discord_timestamp;
+use crate::state::PersonalRemindersKey;
+
+// Longest lead !remindme takes, in minutes: a week
+pub const MAX_LEAD_MINUTES: u32 = 7 * 24 * 60;
+
+struct Pending {
+    at: DateTime<Utc>,
+    task: JoinHandle<()>,
+}
+
+// Reminders set with !remindme that haven't been sent yet, one per member.
+// They only live as long as the bot runs.
+#[derive(Default)]
+pub struct PersonalReminders {
+    pending: HashMap<UserId, Pending>,
+}
+
+// When to send a reminder `minutes` before game night starts at `start`. The
+// error is the reply explaining why that's not going to work.
+pub fn remind_at(start: DateTime<Utc>, now: DateTime<Utc>, minutes: u32) -> Result<DateTime<Utc>, String> {
+    if minutes == 0 || minutes > MAX_LEAD_MINUTES {
+        return Err(format!("❌ Pick a lead between 1 and {} minutes.", MAX_LEAD_MINUTES));
+    }
+    let at = start - chrono::Duration::minutes(minutes as i64);
+    if at <= now {
+        let left = (start - now).num_minutes();
+        return Err(format!(
+            "❌ Game night starts in {} minutes, so I can't remind you {} minutes before. Pick a shorter lead.",
+            left, minutes
+        ));
+    }
+    Ok(at)
+}
+
+// What the reminder says once it's sent
+pub fn format_personal_reminder(start: DateTime<Utc>) -> String {
+    format!(
+        "⏰ **Game night starts {}!**\n🕐 {}\n_You asked me to remind you with `!remindme`_",
+        to_discord_timestamp(start, 'R'),
+        to_discord_timestamp(start, 'F')
+    )
+}
+
+// DM `user_id` at `at` about game night starting at `start`, replacing any
+// reminder they already had pending. Returns whether one got replaced.
+pub async fn schedule(
+    http: Arc<Http>,
+    data: &RwLock<TypeMap>,
+    user_id: UserId,
+    at: DateTime<Utc>,
+    start: DateTime<Utc>,
+) -> bool {
+    let reminders = {
+        let data = data.read().await;
+        data.get::<PersonalRemindersKey>().expect("personal reminders are inserted at startup").clone()
+    };
+    let mut pending = reminders.lock().await;
+    let earlier = pending.pending.remove(&user_id);
+    if let Some(earlier) = &earlier {
+        earlier.task.abort();
+    }
+    let task = tokio::spawn(send_at(http, reminders.clone(), user_id, at, start));
+    pending.pending.insert(user_id, Pending { at, task });
+    earlier.is_some()
+}
+
+async fn send_at(
+    http: Arc<Http>,
+    reminders: Arc<Mutex<PersonalReminders>>,
+    user_id: UserId,
+    at: DateTime<Utc>,
+    start: DateTime<Utc>,
+) {
+    tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+    {
+        let mut pending = reminders.lock().await;
+        if pending.pending.get(&user_id).is_some_and(|pending| pending.at == at) {
+            pending.pending.remove(&user_id);
+        }
+    }
+    // Nothing else to do for members who don't take DMs
+    let message = CreateMessage::new().content(format_personal_reminder(start));
+    if let Err(why) = user_id.direct_message(&http, message).await {
+        warn!(%user_id, "Couldn't DM personal reminder: {:?}", why);
+    }
+}
+
+// Drop every pending reminder, e.g. on shutdown, so none fires halfway
+// through it
+pub async fn cancel_all(data: &RwLock<TypeMap>) {
+    let reminders = {
+        let data = data.read().await;
+        data.get::<PersonalRemindersKey>().expect("personal reminders are inserted at startup").clone()
+    };
+    for (_, reminder) in reminders.lock().await.pending.drain() {
+        reminder.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_remind_at() {
+        let now = start() - chrono::Duration::hours(2);
+        assert_eq!(remind_at(start(), now, 30), Ok(start() - chrono::Duration::minutes(30)));
+        assert_eq!(remind_at(start(), now, 119), Ok(start() - chrono::Duration::minutes(119)));
+
+        // Longer than what's left, or right now
+        let too_long = remind_at(start(), now, 180).unwrap_err();
+        assert!(too_long.contains("starts in 120 minutes"), "{}", too_long);
+        assert!(remind_at(start(), now, 120).is_err());
+        assert!(remind_at(start(), now, 0).is_err());
+        assert!(remind_at(start(), now - chrono::Duration::weeks(2), MAX_LEAD_MINUTES + 1).is_err());
+    }
+
+    #[test]
+    fn test_format_personal_reminder() {
+        assert_eq!(
+            format_personal_reminder(start()),
+            "⏰ **Game night starts <t:1717804800:R>!**\n🕐 <t:1717804800:F>\n_You asked me to remind you with `!remindme`_"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::collector::collect;
+use serenity::futures::StreamExt;
+use serenity::model::channel::{Reaction, ReactionType};
+use serenity::model::event::Event;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::prelude::*;
+
+// Reactions for each option, which is also why a poll tops out at 10 options
+pub const NUMBER_EMOJI: [&str; 10] = ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+
+// Which option a number reaction stands for. Discord isn't consistent about
+// the variation selector on keycap emoji, so it's ignored when comparing.
+fn option_index(emoji: &ReactionType) -> Option<usize> {
+    let ReactionType::Unicode(emoji) = emoji else {
+        return None;
+    };
+    let emoji = emoji.replace('\u{FE0F}', "");
+    NUMBER_EMOJI.iter().position(|number| number.replace('\u{FE0F}', "") == emoji)
+}
+
+// Who is currently voting for what. Members can vote for several options, and
+// taking a reaction back takes the vote back too.
+#[derive(Default)]
+pub struct PollTally {
+    votes: HashSet<(UserId, usize)>,
+}
+
+impl PollTally {
+    pub fn add(&mut self, user_id: UserId, option: usize) {
+        self.votes.insert((user_id, option));
+    }
+
+    pub fn remove(&mut self, user_id: UserId, option: usize) {
+        self.votes.remove(&(user_id, option));
+    }
+
+    // Number of votes for each of the first `options` options
+    pub fn counts(&self, options: usize) -> Vec<usize> {
+        let mut counts = vec![0; options];
+        for (_, option) in &self.votes {
+            if let Some(count) = counts.get_mut(*option) {
+                *count += 1;
+            }
+        }
+        counts
+    }
+}
+
+// Every option sharing the highest vote count, so ties list all of them.
+// Empty when nobody voted.
+pub fn winners<'a>(options: &'a [String], counts: &[usize]) -> Vec<&'a str> {
+    let top = counts.iter().copied().max().unwrap_or(0);
+    if top == 0 {
+        return Vec::new();
+    }
+    options
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| **count == top)
+        .map(|(option, _)| option.as_str())
+        .collect()
+}
+
+// Split `"Question" option1 | option2 | ...` as given to !poll into the
+// question and its options, explaining what's wrong with it if anything
+pub fn parse_poll(text: &str) -> Result<(String, Vec<String>), String> {
+    let text = text.trim();
+    // Phones like to turn quotes into curly ones
+    let Some(rest) = text.strip_prefix(['"', '“']) else {
+        return Err("❌ Put the question in quotes, e.g. `!poll \"What should we play?\" Minecraft | Valheim`".to_string());
+    };
+    let Some((question, options)) = rest.split_once(['"', '”']) else {
+        return Err("❌ The question is missing its closing quote.".to_string());
+    };
+    let question = question.trim();
+    if question.is_empty() {
+        return Err("❌ The question can't be empty.".to_string());
+    }
+
+    let options: Vec<String> = options
+        .split('|')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(str::to_string)
+        .collect();
+    if options.len() < 2 {
+        return Err("❌ A poll needs at least 2 options, separated by `|`.".to_string());
+    }
+    if options.len() > NUMBER_EMOJI.len() {
+        return Err(format!("❌ A poll can have at most {} options.", NUMBER_EMOJI.len()));
+    }
+    Ok((question.to_string(), options))
+}
+
+// Results of a closed poll, most votes first. Options with the same number
+// of votes keep the order they were listed in.
+pub fn format_results(question: &str, options: &[String], counts: &[usize]) -> String {
+    let mut results: Vec<(&String, usize)> = options.iter().zip(counts.iter().copied()).collect();
+    results.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let lines: Vec<String> = results
+        .iter()
+        .map(|(option, count)| format!("• **{}** - {} {}", option, count, if *count == 1 { "vote" } else { "votes" }))
+        .collect();
+    format!("📊 **Results: {}**\n{}", question, lines.join("\n"))
+}
+
+// A reaction being added to or taken off the poll message
+enum VoteEvent {
+    Added(Reaction),
+    Removed(Reaction),
+}
+
+// Post a poll embed with a number reaction per option, collect votes for
+// `window`, then return the vote count for each option
+pub async fn run_reaction_poll(
+    ctx: &Context,
+    channel_id: ChannelId,
+    title: &str,
+    options: &[String],
+    window: Duration,
+) -> serenity::Result<Vec<usize>> {
+    let lines: Vec<String> = options
+        .iter()
+        .zip(NUMBER_EMOJI)
+        .map(|(option, emoji)| format!("{} {}", emoji, option))
+        .collect();
+    let embed = CreateEmbed::new()
+        .title(title)
+        .description(lines.join("\n"))
+        .footer(CreateEmbedFooter::new(format!(
+            "React to vote! Voting closes in {} minutes.",
+            window.as_secs().div_ceil(60)
+        )));
+    let message = channel_id.send_message(&ctx.http, CreateMessage::new().embed(embed)).await?;
+    let bot_id = ctx.http.get_current_user().await?.id;
+
+    // Start listening before adding our own reactions so no early vote is missed
+    let message_id: MessageId = message.id;
+    let events = collect(&ctx.shard, move |event| match event {
+        Event::ReactionAdd(event) if event.reaction.message_id == message_id => {
+            Some(VoteEvent::Added(event.reaction.clone()))
+        }
+        Event::ReactionRemove(event) if event.reaction.message_id == message_id => {
+            Some(VoteEvent::Removed(event.reaction.clone()))
+        }
+        _ => None,
+    });
+    let mut events = Box::pin(events.take_until(Box::pin(tokio::time::sleep(window))));
+
+    for emoji in NUMBER_EMOJI.iter().take(options.len()) {
+        message.react(&ctx.http, ReactionType::Unicode(emoji.to_string())).await?;
+    }
+
+    let mut tally = PollTally::default();
+    while let Some(event) = events.next().await {
+        let (reaction, added) = match event {
+            VoteEvent::Added(reaction) => (reaction, true),
+            VoteEvent::Removed(reaction) => (reaction, false),
+        };
+        let (Some(user_id), Some(option)) = (reaction.user_id, option_index(&reaction.emoji)) else {
+            continue;
+        };
+        if user_id == bot_id || option >= options.len() {
+            continue;
+        }
+        if added {
+            tally.add(user_id, option);
+        } else {
+            tally.remove(user_id, option);
+        }
+    }
+
+    Ok(tally.counts(options.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_option_index() {
+        assert_eq!(option_index(&ReactionType::Unicode("1️⃣".to_string())), Some(0));
+        assert_eq!(option_index(&ReactionType::Unicode("3\u{20E3}".to_string())), Some(2));
+        assert_eq!(option_index(&ReactionType::Unicode("🔟".to_string())), Some(9));
+        assert_eq!(option_index(&ReactionType::Unicode("👍".to_string())), None);
+    }
+
+    #[test]
+    fn test_tally_counts_added_and_removed_votes() {
+        let mut tally = PollTally::default();
+        tally.add(UserId::new(1), 0);
+        tally.add(UserId::new(2), 0);
+        tally.add(UserId::new(2), 1);
+        tally.add(UserId::new(2), 1);
+        tally.remove(UserId::new(1), 0);
+        assert_eq!(tally.counts(3), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn test_parse_poll() {
+        let (question, options) = parse_poll("\"What should we play?\" Among Us | Fall Guys |CS2").unwrap();
+        assert_eq!(question, "What should we play?");
+        assert_eq!(options, vec!["Among Us", "Fall Guys", "CS2"]);
+
+        let (question, options) = parse_poll("“Pizza or tacos?” pizza | tacos").unwrap();
+        assert_eq!(question, "Pizza or tacos?");
+        assert_eq!(options, vec!["pizza", "tacos"]);
+    }
+
+    #[test]
+    fn test_parse_poll_rejects_bad_polls() {
+        assert!(parse_poll("What should we play? a | b").is_err());
+        assert!(parse_poll("\"What should we play? a | b").is_err());
+        assert!(parse_poll("\"\" a | b").is_err());
+        assert!(parse_poll("\"Only one?\" a |  | ").is_err());
+
+        let eleven = ["x"; 11].join(" | ");
+        assert!(parse_poll(&format!("\"Too many?\" {}", eleven)).is_err());
+        let ten = ["x"; 10].join(" | ");
+        assert!(parse_poll(&format!("\"Just enough?\" {}", ten)).is_ok());
+    }
+
+    #[test]
+    fn test_format_results_sorted_by_votes() {
+        let options = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            format_results("Which?", &options, &[1, 3, 1]),
+            "📊 **Results: Which?**\n• **b** - 3 votes\n• **a** - 1 vote\n• **c** - 1 vote"
+        );
+    }
+
+    #[test]
+    fn test_winners_with_tie() {
+        let options = vec!["Minecraft".to_string(), "Valheim".to_string(), "CS2".to_string()];
+        assert_eq!(winners(&options, &[2, 1, 0]), vec!["Minecraft"]);
+        assert_eq!(winners(&options, &[2, 0, 2]), vec!["Minecraft", "CS2"]);
+        assert!(winners(&options, &[0, 0, 0]).is_empty());
+    }
+}
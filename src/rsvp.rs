@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::builder::{CreateActionRow, CreateButton, CreateEmbed};
+use serenity::model::application::ButtonStyle;
+use serenity::model::id::{GuildId, UserId};
+#[cfg(feature = "prefix-commands")]
+use serenity::model::mention::Mentionable;
+
+#[cfg(feature = "prefix-commands")]
+use crate::messaging::to_discord_timestamp;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RsvpStatus {
+    Yes,
+    No,
+    Maybe,
+}
+
+impl FromStr for RsvpStatus {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "yes" | "y" => Ok(RsvpStatus::Yes),
+            "no" | "n" => Ok(RsvpStatus::No),
+            "maybe" | "m" => Ok(RsvpStatus::Maybe),
+            _ => Err(()),
+        }
+    }
+}
+
+// A guild's RSVPs for its next game night. They only count until that
+// session ends, after which the next night starts with a clean slate.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GuildRsvps {
+    pub session_start: DateTime<Utc>,
+    pub session_end: DateTime<Utc>,
+    pub responses: HashMap<UserId, RsvpStatus>,
+}
+
+// The guild's RSVPs for the upcoming session. Responses for a session that
+// already ended get dropped, and the RSVPs move on to the given session.
+pub fn current_rsvps(
+    rsvps: &mut HashMap<GuildId, GuildRsvps>,
+    guild_id: GuildId,
+    session: (DateTime<Utc>, DateTime<Utc>),
+    now: DateTime<Utc>,
+) -> &mut GuildRsvps {
+    let (session_start, session_end) = session;
+    let entry = rsvps.entry(guild_id).or_insert_with(|| GuildRsvps {
+        session_start,
+        session_end,
+        responses: HashMap::new(),
+    });
+    if now >= entry.session_end {
+        *entry = GuildRsvps {
+            session_start,
+            session_end,
+            responses: HashMap::new(),
+        };
+    }
+    entry
+}
+
+// Format for !attendance - who's going, grouped by response
+#[cfg(feature = "prefix-commands")]
+pub fn format_attendance(rsvps: &GuildRsvps) -> String {
+    let group = |status: RsvpStatus| {
+        let mut users: Vec<UserId> = rsvps.responses
+            .iter()
+            .filter(|(_, response)| **response == status)
+            .map(|(user_id, _)| *user_id)
+            .collect();
+        users.sort();
+        let names: Vec<String> = users.iter().map(|user_id| user_id.mention().to_string()).collect();
+        let list = if names.is_empty() { "nobody yet".to_string() } else { names.join(", ") };
+        (users.len(), list)
+    };
+    let (going, going_list) = group(RsvpStatus::Yes);
+    let (maybe, maybe_list) = group(RsvpStatus::Maybe);
+    let (not_going, not_going_list) = group(RsvpStatus::No);
+
+    format!(
+        "📋 **Attendance for {}**\n\
+        ━━━━━━━━━━━━━━━━━━━━━\n\
+        ✅ **Going ({}):** {}\n\
+        🤔 **Maybe ({}):** {}\n\
+        ❌ **Not going ({}):** {}",
+        to_discord_timestamp(rsvps.session_start, 'F'),
+        going,
+        going_list,
+        maybe,
+        maybe_list,
+        not_going,
+        not_going_list
+    )
+}
+
+// Start of the custom id of each RSVP button, followed by the response
+const BUTTON_ID_PREFIX: &str = "rsvp:";
+
+impl RsvpStatus {
+    fn button_id(self) -> String {
+        let status = match self {
+            RsvpStatus::Yes => "yes",
+            RsvpStatus::No => "no",
+            RsvpStatus::Maybe => "maybe",
+        };
+        format!("{}{}", BUTTON_ID_PREFIX, status)
+    }
+
+    // The response an RSVP button stands for, none for any other component
+    pub fn from_button_id(custom_id: &str) -> Option<Self> {
+        custom_id.strip_prefix(BUTTON_ID_PREFIX)?.parse().ok()
+    }
+}
+
+// Going / Maybe / Can't buttons put under reminders and announcements
+pub fn rsvp_buttons() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(RsvpStatus::Yes.button_id()).label("Going").emoji('✅').style(ButtonStyle::Success),
+        CreateButton::new(RsvpStatus::Maybe.button_id()).label("Maybe").emoji('🤔').style(ButtonStyle::Secondary),
+        CreateButton::new(RsvpStatus::No.button_id()).label("Can't").emoji('❌').style(ButtonStyle::Danger),
+    ])
+}
+
+// How many of each response there are, e.g. "✅ 3 going · 🤔 1 maybe · ❌ 0 can't"
+pub fn format_rsvp_counts(rsvps: &GuildRsvps) -> String {
+    let count = |status: RsvpStatus| rsvps.responses.values().filter(|response| **response == status).count();
+    format!(
+        "✅ {} going · 🤔 {} maybe · ❌ {} can't",
+        count(RsvpStatus::Yes),
+        count(RsvpStatus::Maybe),
+        count(RsvpStatus::No)
+    )
+}
+
+// Embed kept under a message with RSVP buttons, updated on every click
+pub fn rsvp_counts_embed(rsvps: &GuildRsvps) -> CreateEmbed {
+    CreateEmbed::new().title("📋 RSVPs").description(format_rsvp_counts(rsvps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session(day: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = Utc.with_ymd_and_hms(2024, 6, day, 0, 0, 0).unwrap();
+        (start, start + chrono::Duration::hours(4))
+    }
+
+    #[test]
+    fn test_parse_rsvp_status() {
+        assert_eq!("YES".parse(), Ok(RsvpStatus::Yes));
+        assert_eq!("no".parse(), Ok(RsvpStatus::No));
+        assert_eq!(" maybe ".parse(), Ok(RsvpStatus::Maybe));
+        assert_eq!("sure".parse::<RsvpStatus>(), Err(()));
+    }
+
+    #[test]
+    fn test_rsvps_reset_after_session_ends() {
+        let mut rsvps = HashMap::new();
+        let guild_id = GuildId::new(1);
+        let (start, end) = session(8);
+
+        current_rsvps(&mut rsvps, guild_id, (start, end), start - chrono::Duration::days(1))
+            .responses
+            .insert(UserId::new(10), RsvpStatus::Yes);
+
+        // Still counts while the session is running
+        let during = current_rsvps(&mut rsvps, guild_id, session(15), start + chrono::Duration::hours(1));
+        assert_eq!(during.responses.len(), 1);
+        assert_eq!(during.session_start, start);
+
+        // Gone once it's over
+        let after = current_rsvps(&mut rsvps, guild_id, session(15), end);
+        assert!(after.responses.is_empty());
+        assert_eq!(after.session_start, session(15).0);
+    }
+
+    #[test]
+    fn test_rsvp_button_ids() {
+        for status in [RsvpStatus::Yes, RsvpStatus::No, RsvpStatus::Maybe] {
+            assert_eq!(RsvpStatus::from_button_id(&status.button_id()), Some(status));
+        }
+        assert_eq!(RsvpStatus::from_button_id("yes"), None);
+        assert_eq!(RsvpStatus::from_button_id("poll:yes"), None);
+    }
+
+    #[test]
+    fn test_latest_click_counts() {
+        let (session_start, session_end) = session(8);
+        let mut rsvps = GuildRsvps { session_start, session_end, responses: HashMap::new() };
+        rsvps.responses.insert(UserId::new(1), RsvpStatus::Yes);
+        rsvps.responses.insert(UserId::new(2), RsvpStatus::Maybe);
+        assert_eq!(format_rsvp_counts(&rsvps), "✅ 1 going · 🤔 1 maybe · ❌ 0 can't");
+
+        // Changing their mind replaces the earlier response
+        rsvps.responses.insert(UserId::new(1), RsvpStatus::No);
+        assert_eq!(format_rsvp_counts(&rsvps), "✅ 0 going · 🤔 1 maybe · ❌ 1 can't");
+    }
+
+    #[cfg(feature = "prefix-commands")]
+    #[test]
+    fn test_format_attendance() {
+        let (session_start, session_end) = session(8);
+        let mut responses = HashMap::new();
+        responses.insert(UserId::new(1), RsvpStatus::Yes);
+        responses.insert(UserId::new(2), RsvpStatus::Yes);
+        responses.insert(UserId::new(3), RsvpStatus::No);
+        let rsvps = GuildRsvps { session_start, session_end, responses };
+
+        let attendance = format_attendance(&rsvps);
+        assert!(attendance.contains("**Going (2):** <@1>, <@2>"));
+        assert!(attendance.contains("**Maybe (0):** nobody yet"));
+        assert!(attendance.contains("**Not going (1):** <@3>"));
+    }
+}
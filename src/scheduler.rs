@@ -0,0 +1,638 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateMessage;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use crate::game_night::{
+    GameNightConfig, GameNightSchedule, format_last_call, format_live_announcement, format_reminder,
+    get_next_game_night, live_session_at, planned_game,
+};
+use crate::history::{GameNightRecord, attendees, record_completed_session, update_streaks};
+use crate::state::{
+    DefaultScheduleKey, GameLibraryKey, GuildSchedulesKey, HistoryKey, RsvpKey, SaveLockKey, StoreKey, StreakKey,
+    SubscribersKey,
+};
+use crate::rsvp::{GuildRsvps, RsvpStatus};
+use crate::storage::Store;
+use crate::{messaging, rsvp, storage};
+
+// One night of one schedule: the guild it belongs to (none for the default
+// schedule) and its index in that schedule
+type NightKey = (Option<GuildId>, usize);
+
+// Reminders already sent for one upcoming session of a night
+struct ReminderState {
+    session_start: DateTime<Utc>,
+    sent: HashSet<u32>,
+}
+
+// Lead times (in minutes) that came due for the session starting at `start`
+// and haven't been sent yet. Everything returned is marked as sent, and the
+// state resets itself once the next session gets scheduled.
+fn due_reminders(
+    state: &mut ReminderState,
+    lead_minutes: &[u32],
+    start: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<u32> {
+    if state.session_start != start {
+        state.session_start = start;
+        state.sent.clear();
+    }
+
+    let due: Vec<u32> = lead_minutes
+        .iter()
+        .copied()
+        .filter(|lead| !state.sent.contains(lead))
+        .filter(|lead| now >= start - chrono::Duration::minutes(*lead as i64) && now < start)
+        .collect();
+    state.sent.extend(&due);
+    due
+}
+
+// Last session each night announced as live, as saved to disk. NightKey
+// doesn't work as a JSON map key, so this is a list instead.
+#[derive(Deserialize, Serialize)]
+struct Announcement {
+    guild_id: Option<GuildId>,
+    night: usize,
+    session_start: DateTime<Utc>,
+}
+
+// Start of the live session, if it hasn't been announced yet. `announced`
+// remembers the last session each night announced, so every session gets
+// announced once no matter how many ticks it's live for.
+fn session_to_announce(
+    announced: &mut HashMap<NightKey, DateTime<Utc>>,
+    key: NightKey,
+    live_start: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    let start = live_start?;
+    if announced.insert(key, start) == Some(start) {
+        return None;
+    }
+    Some(start)
+}
+
+// End of the live session once it's within `minutes` of wrapping up, if its
+// last call hasn't gone out yet. `last_calls` remembers the last session each
+// night had one for. A session shorter than `minutes` gets none, it would
+// only land on top of the live announcement.
+fn last_call_due(
+    last_calls: &mut HashMap<NightKey, DateTime<Utc>>,
+    key: NightKey,
+    live_window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    minutes: u32,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let (start, end) = live_window?;
+    let trigger = end - chrono::Duration::minutes(minutes as i64);
+    if trigger <= start || now < trigger || last_calls.get(&key) == Some(&start) {
+        return None;
+    }
+    last_calls.insert(key, start);
+    Some(end)
+}
+
+fn from_announcements(announcements: Vec<Announcement>) -> HashMap<NightKey, DateTime<Utc>> {
+    announcements
+        .into_iter()
+        .map(|announcement| ((announcement.guild_id, announcement.night), announcement.session_start))
+        .collect()
+}
+
+fn to_announcements(announced: &HashMap<NightKey, DateTime<Utc>>) -> Vec<Announcement> {
+    announced
+        .iter()
+        .map(|((guild_id, night), session_start)| Announcement {
+            guild_id: *guild_id,
+            night: *night,
+            session_start: *session_start,
+        })
+        .collect()
+}
+
+fn load_announcements(store: &dyn Store) -> HashMap<NightKey, DateTime<Utc>> {
+    match storage::load_state(store, storage::ANNOUNCEMENTS) {
+        Ok(announcements) => from_announcements(announcements),
+        Err(why) => {
+            error!("Error loading {}: {}", storage::ANNOUNCEMENTS, why);
+            HashMap::new()
+        }
+    }
+}
+
+fn save_announcements(store: &dyn Store, announced: &HashMap<NightKey, DateTime<Utc>>) {
+    if let Err(why) = storage::save_state(store, storage::ANNOUNCEMENTS, &to_announcements(announced)) {
+        error!("Error saving {}: {}", storage::ANNOUNCEMENTS, why);
+    }
+}
+
+// Start of the session that was live on the last tick and isn't anymore.
+// `live` remembers which session each night had running.
+fn finished_session(
+    live: &mut HashMap<NightKey, DateTime<Utc>>,
+    key: NightKey,
+    live_start: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    let previous = match live_start {
+        Some(start) => live.insert(key, start),
+        None => live.remove(&key),
+    };
+    previous.filter(|previous| Some(*previous) != live_start)
+}
+
+// Guilds whose RSVPs count for the session of `guild_id`'s schedule starting
+// at `start`. That's the guild itself, or for the default schedule every
+// guild that RSVP'd for that session and doesn't have a schedule of its own.
+fn rsvp_guilds(
+    guild_id: Option<GuildId>,
+    rsvps: &HashMap<GuildId, GuildRsvps>,
+    schedules: &HashMap<GuildId, GameNightSchedule>,
+    start: DateTime<Utc>,
+) -> Vec<GuildId> {
+    match guild_id {
+        Some(guild_id) => vec![guild_id],
+        None => rsvps
+            .iter()
+            .filter(|(guild_id, rsvps)| rsvps.session_start == start && !schedules.contains_key(guild_id))
+            .map(|(guild_id, _)| *guild_id)
+            .collect(),
+    }
+}
+
+// Members to DM the reminders of `guild_id`'s schedule to: the guild's own
+// subscribers, or for the default schedule those of every guild without a
+// schedule of its own. Someone subscribed in several of those gets one DM.
+fn reminder_subscribers(
+    guild_id: Option<GuildId>,
+    subscribers: &HashMap<GuildId, HashSet<UserId>>,
+    schedules: &HashMap<GuildId, GameNightSchedule>,
+) -> HashSet<UserId> {
+    subscribers
+        .iter()
+        .filter(|(subscribed_in, _)| match guild_id {
+            Some(guild_id) => **subscribed_in == guild_id,
+            None => !schedules.contains_key(subscribed_in),
+        })
+        .flat_map(|(_, members)| members.iter().copied())
+        .collect()
+}
+
+// DM a reminder to everyone subscribed to it. Members with DMs closed are
+// skipped, there's no one to tell.
+async fn dm_subscribers(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    night: &GameNightConfig,
+    start: DateTime<Utc>,
+    now: DateTime<Utc>,
+) {
+    let (schedules, subscribers) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup").clone(),
+            data.get::<SubscribersKey>().expect("subscribers are inserted at startup").clone(),
+        )
+    };
+    let user_ids = reminder_subscribers(guild_id, &*subscribers.read().await, &*schedules.read().await);
+    if user_ids.is_empty() {
+        return;
+    }
+
+    // A role mention means nothing in a DM
+    let night = GameNightConfig { notify_role_id: None, ..night.clone() };
+    let reminder = format_reminder(&night, start, now);
+    for user_id in user_ids {
+        if let Err(why) = user_id.direct_message(&ctx.http, CreateMessage::new().content(&reminder)).await {
+            warn!(%user_id, guild_id = ?guild_id, "Couldn't DM reminder: {:?}", why);
+        }
+    }
+}
+
+// How many RSVP'd "going" for the session starting at `start`
+async fn going_count(ctx: &Context, guild_id: Option<GuildId>, start: DateTime<Utc>) -> usize {
+    let (schedules, rsvps) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup").clone(),
+            data.get::<RsvpKey>().expect("RSVPs are inserted at startup").clone(),
+        )
+    };
+    let rsvps = rsvps.read().await;
+    rsvp_guilds(guild_id, &rsvps, &*schedules.read().await, start)
+        .iter()
+        .filter_map(|guild_id| rsvps.get(guild_id).filter(|rsvps| rsvps.session_start == start))
+        .map(|rsvps| rsvps.responses.values().filter(|response| **response == RsvpStatus::Yes).count())
+        .sum()
+}
+
+// Add the session that just ended to the history, and carry the attendance
+// streaks over to it, for each guild in `rsvp_guilds`.
+async fn record_history(ctx: &Context, guild_id: Option<GuildId>, night: &GameNightConfig, start: DateTime<Utc>) {
+    let (schedules, libraries, rsvps, history, streaks, store, saves) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup").clone(),
+            data.get::<GameLibraryKey>().expect("game libraries are inserted at startup").clone(),
+            data.get::<RsvpKey>().expect("RSVPs are inserted at startup").clone(),
+            data.get::<HistoryKey>().expect("history is inserted at startup").clone(),
+            data.get::<StreakKey>().expect("streaks are inserted at startup").clone(),
+            data.get::<StoreKey>().expect("the store is inserted at startup").clone(),
+            data.get::<SaveLockKey>().expect("the save lock is inserted at startup").clone(),
+        )
+    };
+    let rsvps = rsvps.read().await;
+    let guild_ids = rsvp_guilds(guild_id, &rsvps, &*schedules.read().await, start);
+    if guild_ids.is_empty() {
+        return;
+    }
+
+    let date = night.timezone.from_utc_datetime(&start.naive_utc()).date_naive();
+    // Each guild played a game from its own library
+    let games: HashMap<GuildId, String> = {
+        let libraries = libraries.read().await;
+        guild_ids
+            .iter()
+            .map(|guild_id| {
+                let library = libraries.get(guild_id).map(Vec::as_slice).unwrap_or_default();
+                (*guild_id, planned_game(library, date))
+            })
+            .collect()
+    };
+    {
+        let mut history = history.write().await;
+        let mut streaks = streaks.write().await;
+        for guild_id in guild_ids {
+            // Skipped nights never get recorded, so the last recorded session is
+            // the one a streak has to carry on from
+            let previous = history
+                .get(&guild_id)
+                .and_then(|records| {
+                    records.iter().map(|record| record.start).filter(|previous| *previous < start).max()
+                })
+                .map(|previous| night.timezone.from_utc_datetime(&previous.naive_utc()).date_naive());
+            let record = GameNightRecord::new(start, &games[&guild_id], rsvps.get(&guild_id));
+            if record_completed_session(&mut history, guild_id, record) {
+                let attendees = attendees(start, rsvps.get(&guild_id));
+                update_streaks(streaks.entry(guild_id).or_default(), previous, date, &attendees);
+            }
+        }
+    }
+    drop(rsvps);
+
+    for (name, saved) in [
+        (
+            storage::HISTORY,
+            storage::save_shared(store.clone(), &saves, &history, |store, history| store.save_history(history)).await,
+        ),
+        (
+            storage::STREAKS,
+            storage::save_shared(store, &saves, &streaks, |store, streaks| {
+                storage::save_state(store, storage::STREAKS, streaks)
+            })
+            .await,
+        ),
+    ] {
+        if let Err(why) = saved {
+            error!("Error saving {}: {}", name, why);
+        }
+    }
+}
+
+// Whether this is the first call since startup, marking the scheduler as
+// started so every later call says no. `ready` fires again after each
+// reconnect, and two schedulers would send every reminder twice.
+pub fn claim_start(started: &AtomicBool) -> bool {
+    !started.swap(true, Ordering::SeqCst)
+}
+
+// Every schedule that might want reminders: the default one, plus each guild
+// that set its own
+async fn all_schedules(ctx: &Context) -> Vec<(Option<GuildId>, GameNightSchedule)> {
+    let data = ctx.data.read().await;
+    let default = data.get::<DefaultScheduleKey>().expect("default schedule is inserted at startup");
+    let guilds = data.get::<GuildSchedulesKey>().expect("guild schedules are inserted at startup");
+
+    let mut schedules = vec![(None, (**default).clone())];
+    for (guild_id, schedule) in guilds.read().await.iter() {
+        schedules.push((Some(*guild_id), schedule.clone()));
+    }
+    schedules
+}
+
+// Background task posting reminders as game night approaches, announcing
+// it once it starts, and keeping the history once it's over. Spawned once
+// the bot is ready and runs for as long as the bot does.
+pub async fn run(ctx: Context) {
+    let mut reminders: HashMap<NightKey, ReminderState> = HashMap::new();
+    let mut live: HashMap<NightKey, DateTime<Utc>> = HashMap::new();
+    let mut last_calls: HashMap<NightKey, DateTime<Utc>> = HashMap::new();
+    // Saved so a restart in the middle of a session doesn't announce it again
+    let (store, tick) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<StoreKey>().expect("the store is inserted at startup").clone(),
+            data.get::<DefaultScheduleKey>().expect("default schedule is inserted at startup").scheduler_tick(),
+        )
+    };
+    // scheduler_tick_seconds is global, only gamenight.toml's counts. Guilds
+    // asking for a shorter one would otherwise never hear why they don't get it.
+    for (guild_id, schedule) in all_schedules(&ctx).await {
+        if let Some(guild_id) = guild_id
+            && schedule.scheduler_tick() < tick
+        {
+            warn!(
+                guild_id = ?guild_id,
+                "Ignoring the guild's scheduler_tick_seconds, the scheduler ticks every {}s for every guild",
+                tick.as_secs()
+            );
+        }
+    }
+    let mut interval = tokio::time::interval(tick);
+    let mut announced = load_announcements(&*store);
+
+    loop {
+        interval.tick().await;
+
+        for (guild_id, schedule) in all_schedules(&ctx).await {
+            for (index, night) in schedule.nights.iter().enumerate() {
+                let now = Utc::now();
+                let live_window = live_session_at(night, now);
+                let live_start = live_window.map(|(start, _)| start);
+                if let Some(start) = finished_session(&mut live, (guild_id, index), live_start) {
+                    record_history(&ctx, guild_id, night, start).await;
+                }
+
+                // Paused nights still get their history kept, they just stay quiet
+                if !night.announcements_enabled {
+                    continue;
+                }
+                // Without a reminder channel only subscribers hear about it
+                let channel_id = night.reminder_channel;
+
+                if let Some(start) = session_to_announce(&mut announced, (guild_id, index), live_start) {
+                    save_announcements(&*store, &announced);
+                    let Some(channel_id) = channel_id else {
+                        continue;
+                    };
+                    let going = match night.min_players {
+                        Some(_) => going_count(&ctx, guild_id, start).await,
+                        None => 0,
+                    };
+                    let announcement = match format_live_announcement(night, going) {
+                        Ok(announcement) => announcement,
+                        Err(why) => {
+                            error!(guild_id = ?guild_id, "Can't announce game night: {}", why);
+                            continue;
+                        }
+                    };
+                    let roles = night.notify_role_id.into_iter().collect();
+                    let buttons = vec![rsvp::rsvp_buttons()];
+                    if let Err(why) = messaging::send_with_components(&ctx.http, channel_id, announcement, roles, buttons).await {
+                        error!(%channel_id, guild_id = ?guild_id, "Error sending live announcement: {:?}", why);
+                    }
+                    continue;
+                }
+
+                if let Some(minutes) = night.last_call_minutes
+                    && let Some(end) = last_call_due(&mut last_calls, (guild_id, index), live_window, minutes, now)
+                    && let Some(channel_id) = channel_id
+                {
+                    // Everyone's already playing, no need to ping them
+                    let last_call = format_last_call(night, end, now);
+                    if let Err(why) = messaging::send_with_role_pings(&ctx.http, channel_id, last_call, Vec::new()).await {
+                        error!(%channel_id, guild_id = ?guild_id, "Error sending last call: {:?}", why);
+                    }
+                    continue;
+                }
+
+                let start = match get_next_game_night(night) {
+                    Ok(start) => start,
+                    Err(why) => {
+                        warn!(guild_id = ?guild_id, "Can't schedule reminders: {}", why);
+                        continue;
+                    }
+                };
+                let state = reminders.entry((guild_id, index)).or_insert_with(|| ReminderState {
+                    session_start: start,
+                    sent: HashSet::new(),
+                });
+
+                // Several leads can come due in the same tick, e.g. right after a
+                // restart. One reminder with the real countdown covers them all.
+                if due_reminders(state, &night.reminder_lead_minutes, start, now).is_empty() {
+                    continue;
+                }
+
+                if let Some(channel_id) = channel_id {
+                    let reminder = format_reminder(night, start, now);
+                    let roles = night.notify_role_id.into_iter().collect();
+                    let buttons = vec![rsvp::rsvp_buttons()];
+                    if let Err(why) = messaging::send_with_components(&ctx.http, channel_id, reminder, roles, buttons).await {
+                        error!(%channel_id, guild_id = ?guild_id, "Error sending reminder: {:?}", why);
+                    }
+                }
+                dm_subscribers(&ctx, guild_id, night, start, now).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::game_night::MAX_SCHEDULER_TICK_SECONDS;
+
+    fn state_for(start: DateTime<Utc>) -> ReminderState {
+        ReminderState {
+            session_start: start,
+            sent: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_reminders_fire_once_per_mark() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut state = state_for(start);
+        let leads = [60, 15];
+
+        assert!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(90)).is_empty());
+        assert_eq!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(59)), vec![60]);
+        assert!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(58)).is_empty());
+        assert_eq!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(14)), vec![15]);
+        assert!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(1)).is_empty());
+    }
+
+    #[test]
+    fn test_reminders_reset_for_next_session() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut state = state_for(start);
+        let leads = [15];
+
+        assert_eq!(due_reminders(&mut state, &leads, start, start - chrono::Duration::minutes(10)), vec![15]);
+
+        let next_start = start + chrono::Duration::weeks(1);
+        let now = next_start - chrono::Duration::minutes(10);
+        assert_eq!(due_reminders(&mut state, &leads, next_start, now), vec![15]);
+    }
+
+    #[test]
+    fn test_finished_session_once_live_window_ends() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut live = HashMap::new();
+        let key = (None, 0);
+
+        assert_eq!(finished_session(&mut live, key, None), None);
+        assert_eq!(finished_session(&mut live, key, Some(start)), None);
+        assert_eq!(finished_session(&mut live, key, Some(start)), None);
+        assert_eq!(finished_session(&mut live, key, None), Some(start));
+        assert_eq!(finished_session(&mut live, key, None), None);
+
+        // Straight into the next session still finishes the first one
+        let next_start = start + chrono::Duration::hours(4);
+        finished_session(&mut live, key, Some(start));
+        assert_eq!(finished_session(&mut live, key, Some(next_start)), Some(start));
+    }
+
+    #[test]
+    fn test_announce_once_per_session() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut announced = HashMap::new();
+        let key = (None, 0);
+
+        assert_eq!(session_to_announce(&mut announced, key, None), None);
+        assert_eq!(session_to_announce(&mut announced, key, Some(start)), Some(start));
+        assert_eq!(session_to_announce(&mut announced, key, Some(start)), None);
+        assert_eq!(session_to_announce(&mut announced, key, None), None);
+
+        let next_start = start + chrono::Duration::weeks(1);
+        assert_eq!(session_to_announce(&mut announced, key, Some(next_start)), Some(next_start));
+
+        // Other nights keep track of their own sessions
+        assert_eq!(session_to_announce(&mut announced, (None, 1), Some(next_start)), Some(next_start));
+    }
+
+    #[test]
+    fn test_restart_mid_session_does_not_reannounce() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let key = (Some(GuildId::new(1)), 0);
+        let mut announced = HashMap::new();
+        session_to_announce(&mut announced, key, Some(start));
+
+        let saved = serde_json::to_string(&to_announcements(&announced)).unwrap();
+        let mut restored = from_announcements(serde_json::from_str(&saved).unwrap());
+        assert_eq!(session_to_announce(&mut restored, key, Some(start)), None);
+    }
+
+    #[test]
+    fn test_last_call_across_midnight() {
+        // 10 PM Friday to 2 AM Saturday, so the last call is at 1:30 AM
+        let night = GameNightConfig {
+            start_time: chrono::NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            ..GameNightConfig::default()
+        };
+        let eastern = |d, h, min| chrono_tz::US::Eastern.with_ymd_and_hms(2024, 6, d, h, min, 0).unwrap().with_timezone(&Utc);
+        let mut last_calls = HashMap::new();
+        let key = (None, 0);
+        let mut due = |now| last_call_due(&mut last_calls, key, live_session_at(&night, now), 30, now);
+
+        assert_eq!(due(eastern(7, 21, 45)), None);
+        assert_eq!(due(eastern(7, 23, 59)), None);
+        assert_eq!(due(eastern(8, 1, 29)), None);
+        assert_eq!(due(eastern(8, 1, 30)), Some(eastern(8, 2, 0)));
+        assert_eq!(due(eastern(8, 1, 31)), None);
+        assert_eq!(due(eastern(8, 2, 0)), None);
+
+        // The next week's session gets its own
+        assert_eq!(due(eastern(15, 1, 45)), Some(eastern(15, 2, 0)));
+    }
+
+    #[test]
+    fn test_no_last_call_for_short_sessions() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let window = Some((start, start + chrono::Duration::minutes(20)));
+        assert_eq!(last_call_due(&mut HashMap::new(), (None, 0), window, 30, start), None);
+    }
+
+    // Every instant from `from` up to `to`, `tick` apart
+    fn ticks(from: DateTime<Utc>, to: DateTime<Utc>, tick: chrono::Duration) -> Vec<DateTime<Utc>> {
+        std::iter::successors(Some(from), |now| Some(*now + tick)).take_while(|now| *now < to).collect()
+    }
+
+    #[test]
+    fn test_coarse_tick_still_catches_reminders() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut state = state_for(start);
+        let leads = [60, 15];
+
+        // Ten minute ticks that never land on a reminder's exact mark
+        let tick = chrono::Duration::minutes(10);
+        let from = start - chrono::Duration::minutes(127);
+        let mut sent = Vec::new();
+        for now in ticks(from, start + chrono::Duration::hours(1), tick) {
+            for lead in due_reminders(&mut state, &leads, start, now) {
+                sent.push((lead, now));
+            }
+        }
+        assert_eq!(
+            sent,
+            vec![(60, start - chrono::Duration::minutes(57)), (15, start - chrono::Duration::minutes(7))]
+        );
+    }
+
+    #[test]
+    fn test_coarse_tick_still_catches_last_call() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::hours(4);
+        let mut last_calls = HashMap::new();
+        let tick = chrono::Duration::seconds(MAX_SCHEDULER_TICK_SECONDS as i64);
+
+        let calls: Vec<DateTime<Utc>> = ticks(start + chrono::Duration::minutes(7), end, tick)
+            .into_iter()
+            .filter(|now| last_call_due(&mut last_calls, (None, 0), Some((start, end)), 30, *now).is_some())
+            .collect();
+        assert_eq!(calls, vec![end - chrono::Duration::minutes(23)]);
+    }
+
+    #[test]
+    fn test_no_reminders_after_start() {
+        let start = Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let mut state = state_for(start);
+        assert!(due_reminders(&mut state, &[60, 15], start, start).is_empty());
+    }
+
+    #[test]
+    fn test_reminder_subscribers() {
+        let (own, default_a, default_b) = (GuildId::new(1), GuildId::new(2), GuildId::new(3));
+        let subscribers = HashMap::from([
+            (own, HashSet::from([UserId::new(10)])),
+            (default_a, HashSet::from([UserId::new(20), UserId::new(30)])),
+            (default_b, HashSet::from([UserId::new(30)])),
+        ]);
+        let schedules = HashMap::from([(own, GameNightSchedule::default())]);
+
+        assert_eq!(reminder_subscribers(Some(own), &subscribers, &schedules), HashSet::from([UserId::new(10)]));
+        // Guilds on the default schedule share its reminders, once per member
+        assert_eq!(
+            reminder_subscribers(None, &subscribers, &schedules),
+            HashSet::from([UserId::new(20), UserId::new(30)])
+        );
+        assert!(reminder_subscribers(Some(GuildId::new(4)), &subscribers, &schedules).is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_starts_once() {
+        let started = AtomicBool::new(false);
+        assert!(claim_start(&started));
+        // Every ready after a reconnect
+        assert!(!claim_start(&started));
+        assert!(!claim_start(&started));
+    }
+}
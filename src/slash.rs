@@ -0,0 +1,163 @@
+use chrono::Weekday;
+use serenity::builder::{
+    CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use serenity::model::application::{Command, CommandInteraction, CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::config::{self, Setting};
+use crate::i18n::{Locale, Text, fill, tr, weekday_name};
+use crate::{
+    game_night_config_reply, game_night_status_reply, guild_schedule, is_game_night_reply, next_game_reply,
+    record_command, update_guild_schedule,
+};
+
+// Register the slash commands globally. Discord can take a while to show
+// changes to global commands in every guild.
+pub async fn register(ctx: &Context) -> serenity::Result<Vec<Command>> {
+    let commands = vec![
+        CreateCommand::new("gamenight").description("Show game night status"),
+        CreateCommand::new("nextgame").description("Show when the next game night is"),
+        CreateCommand::new("isgamenight").description("Check if game night is happening now"),
+        settings_command(),
+        CreateCommand::new("help").description("Show the available commands"),
+    ];
+    Command::set_global_commands(&ctx.http, commands).await
+}
+
+// Most choices Discord shows for an autocompleted option
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+// /settings and its subcommands. Only members with Manage Server see it,
+// unless a server changes that in its integration settings.
+fn settings_command() -> CreateCommand {
+    let days = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+    let day = days.into_iter().fold(
+        CreateCommandOption::new(CommandOptionType::String, "day", "Day of the week").required(true),
+        |option, day| option.add_string_choice(weekday_name(Locale::English, day), day.to_string()),
+    );
+    let subcommand =
+        |name: &str, description: &str| CreateCommandOption::new(CommandOptionType::SubCommand, name, description);
+
+    CreateCommand::new("settings")
+        .description("See or change when game night is")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .add_option(subcommand("view", "Show the game night settings"))
+        .add_option(subcommand("day", "Move game night to another day").add_sub_option(day))
+        .add_option(subcommand("time", "Change when game night starts").add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "time", "Start time, e.g. 20:00 or 8pm").required(true),
+        ))
+        .add_option(subcommand("timezone", "Change the timezone game night is in").add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "timezone", "IANA timezone, e.g. Europe/Berlin")
+                .required(true)
+                .set_autocomplete(true),
+        ))
+}
+
+// The subcommand picked and the string value passed to it
+fn subcommand<'a>(options: &'a [ResolvedOption<'a>]) -> Option<(&'a str, Option<&'a str>)> {
+    let option = options.first()?;
+    let ResolvedValue::SubCommand(sub_options) = &option.value else {
+        return None;
+    };
+    let value = sub_options.iter().find_map(|option| match option.value {
+        ResolvedValue::String(value) => Some(value),
+        _ => None,
+    });
+    Some((option.name, value))
+}
+
+// Reply for /settings. Changing a setting still checks for Manage Server,
+// since servers can open the command up to anyone.
+async fn settings_reply(ctx: &Context, command: &CommandInteraction) -> CreateInteractionResponseMessage {
+    let locale = guild_schedule(ctx, command.guild_id).await.locale();
+    let text = |content| CreateInteractionResponseMessage::new().content(tr(locale, content)).ephemeral(true);
+    let Some(guild_id) = command.guild_id else {
+        return text(Text::SettingsOutsideGuild);
+    };
+    let options = command.data.options();
+    let Some((name, value)) = subcommand(&options) else {
+        return text(Text::UnknownSetting);
+    };
+    let setting = match (name, value) {
+        ("view", _) => return game_night_config_reply(ctx, Some(guild_id)).await.interaction(),
+        ("day", Some(value)) => (Setting::Day, value),
+        ("time", Some(value)) => (Setting::Time, value),
+        ("timezone", Some(value)) => (Setting::Timezone, value),
+        _ => return text(Text::UnknownSetting),
+    };
+
+    let permissions = command.member.as_ref().and_then(|member| member.permissions).unwrap_or_default();
+    if !permissions.contains(Permissions::MANAGE_GUILD) {
+        return text(Text::SettingsNeedManageServer);
+    }
+    let (setting, value) = setting;
+    let reply = update_guild_schedule(ctx, guild_id, |schedule| {
+        let night = config::change_setting(&schedule.nights[0], setting, value).map_err(|why| format!("❌ {}", why))?;
+        let reply = fill(
+            tr(night.locale, Text::SettingsChanged),
+            &[
+                weekday_name(night.locale, night.day_of_week).to_string(),
+                night.start_time.format(night.time_format.pattern()).to_string(),
+                night.timezone.to_string(),
+            ],
+        );
+        schedule.nights[0] = night;
+        Ok(reply)
+    })
+    .await;
+    CreateInteractionResponseMessage::new().content(reply)
+}
+
+// Timezones matching what's been typed so far into /settings timezone
+pub async fn autocomplete(ctx: &Context, command: &CommandInteraction) {
+    let Some(option) = command.data.autocomplete() else {
+        return;
+    };
+    let choices = config::search_timezones(option.value)
+        .into_iter()
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .fold(CreateAutocompleteResponse::new(), |response, name| response.add_string_choice(name, name));
+    if let Err(why) = command.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(choices)).await {
+        error!(command = %command.data.name, "Error sending autocomplete choices: {:?}", why);
+    }
+}
+
+// Answer a slash command with the same replies the prefix commands give
+pub async fn handle(ctx: &Context, command: &CommandInteraction) {
+    let guild_id = command.guild_id;
+    record_command(ctx, &command.data.name).await;
+    let response = match command.data.name.as_str() {
+        "gamenight" => game_night_status_reply(ctx, guild_id).await.interaction(),
+        "nextgame" => next_game_reply(ctx, guild_id, command.user.id, &[]).await.interaction(),
+        "isgamenight" => CreateInteractionResponseMessage::new().content(is_game_night_reply(ctx, guild_id).await),
+        "settings" => settings_reply(ctx, command).await,
+        "help" => {
+            let locale = guild_schedule(ctx, guild_id).await.locale();
+            CreateInteractionResponseMessage::new().content(tr(locale, Text::SlashHelp)).ephemeral(true)
+        }
+        _ => return,
+    };
+
+    let response = CreateInteractionResponse::Message(response);
+    if let Err(why) = command.create_response(&ctx.http, response).await {
+        error!(
+            command = %command.data.name,
+            channel_id = %command.channel_id,
+            "Error responding to slash command: {:?}",
+            why
+        );
+    }
+}
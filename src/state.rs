@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[cfg(feature = "prefix-commands")]
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+#[cfg(feature = "prefix-commands")]
+use serenity::model::id::ChannelId;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::{Mutex, RwLock, TypeMapKey};
+
+use crate::cache::NextOccurrenceCache;
+#[cfg(feature = "prefix-commands")]
+use crate::cooldown::Cooldowns;
+use crate::game_night::{GameNightSchedule, LibraryGame};
+use crate::history::{GameNightRecord, Streak};
+#[cfg(feature = "prefix-commands")]
+use crate::live_countdown::LiveCountdowns;
+use crate::metrics::CommandMetrics;
+#[cfg(feature = "prefix-commands")]
+use crate::personal_reminder::PersonalReminders;
+use crate::rsvp::GuildRsvps;
+use crate::storage::Store;
+use crate::voice::VoiceStates;
+
+// Schedule loaded from gamenight.toml, used by guilds that haven't set their own
+pub struct DefaultScheduleKey;
+
+impl TypeMapKey for DefaultScheduleKey {
+    type Value = Arc<GameNightSchedule>;
+}
+
+// Each guild's own schedule. Admin commands take the write lock to change it
+// at runtime.
+pub struct GuildSchedulesKey;
+
+impl TypeMapKey for GuildSchedulesKey {
+    type Value = Arc<RwLock<HashMap<GuildId, GameNightSchedule>>>;
+}
+
+// Held while state is written to disk, so saves land in the order the
+// changes were made even though they're written after the state's own lock
+// is let go. Take it before any state lock, never while holding one.
+pub struct SaveLockKey;
+
+impl TypeMapKey for SaveLockKey {
+    type Value = Arc<Mutex<()>>;
+}
+
+// Each guild's own list of games to suggest, managed with !addgame, !removegame
+// and !gameweight
+pub struct GameLibraryKey;
+
+impl TypeMapKey for GameLibraryKey {
+    type Value = Arc<RwLock<HashMap<GuildId, Vec<LibraryGame>>>>;
+}
+
+// Channels with a !vote poll running, and when each one closes. Only one poll
+// runs per channel at a time.
+#[cfg(feature = "prefix-commands")]
+pub struct ActivePollsKey;
+
+#[cfg(feature = "prefix-commands")]
+impl TypeMapKey for ActivePollsKey {
+    type Value = Arc<Mutex<HashMap<ChannelId, DateTime<Utc>>>>;
+}
+
+// Each guild's RSVPs for its next game night
+pub struct RsvpKey;
+
+impl TypeMapKey for RsvpKey {
+    type Value = Arc<RwLock<HashMap<GuildId, GuildRsvps>>>;
+}
+
+// Command prefix of each guild that picked its own with !setprefix
+pub struct PrefixKey;
+
+impl TypeMapKey for PrefixKey {
+    type Value = Arc<RwLock<HashMap<GuildId, String>>>;
+}
+
+// Each guild's past game nights, shown by !history
+pub struct HistoryKey;
+
+impl TypeMapKey for HistoryKey {
+    type Value = Arc<RwLock<HashMap<GuildId, Vec<GameNightRecord>>>>;
+}
+
+// Each guild's attendance streaks, shown by !streak
+pub struct StreakKey;
+
+impl TypeMapKey for StreakKey {
+    type Value = Arc<RwLock<HashMap<GuildId, HashMap<UserId, Streak>>>>;
+}
+
+// Timezone each member picked with !mytz, for showing times in their own zone
+pub struct UserTimezonesKey;
+
+impl TypeMapKey for UserTimezonesKey {
+    type Value = Arc<RwLock<HashMap<UserId, Tz>>>;
+}
+
+// Members of each guild who get reminders by DM, managed with !subscribe and
+// !unsubscribe
+pub struct SubscribersKey;
+
+impl TypeMapKey for SubscribersKey {
+    type Value = Arc<RwLock<HashMap<GuildId, HashSet<UserId>>>>;
+}
+
+// Next occurrence of each schedule, reused until it's over or a schedule changes
+pub struct NextOccurrenceCacheKey;
+
+impl TypeMapKey for NextOccurrenceCacheKey {
+    type Value = Arc<Mutex<NextOccurrenceCache>>;
+}
+
+// When each member last used each prefix command, to keep them from spamming
+#[cfg(feature = "prefix-commands")]
+pub struct CooldownsKey;
+
+#[cfg(feature = "prefix-commands")]
+impl TypeMapKey for CooldownsKey {
+    type Value = Arc<Mutex<Cooldowns>>;
+}
+
+// Guilds the bot is in, for !about
+pub struct ConnectedGuildsKey;
+
+impl TypeMapKey for ConnectedGuildsKey {
+    type Value = Arc<RwLock<HashSet<GuildId>>>;
+}
+
+// Countdowns !livecountdown is keeping current
+#[cfg(feature = "prefix-commands")]
+pub struct LiveCountdownsKey;
+
+#[cfg(feature = "prefix-commands")]
+impl TypeMapKey for LiveCountdownsKey {
+    type Value = Arc<Mutex<LiveCountdowns>>;
+}
+
+// Reminders members set for themselves with !remindme
+#[cfg(feature = "prefix-commands")]
+pub struct PersonalRemindersKey;
+
+#[cfg(feature = "prefix-commands")]
+impl TypeMapKey for PersonalRemindersKey {
+    type Value = Arc<Mutex<PersonalReminders>>;
+}
+
+// When the bot first connected, for the uptime in !about and !stats.
+// Reconnecting doesn't reset it.
+#[cfg(feature = "prefix-commands")]
+pub struct StartedAtKey;
+
+#[cfg(feature = "prefix-commands")]
+impl TypeMapKey for StartedAtKey {
+    type Value = std::time::Instant;
+}
+
+// Whether the scheduler was already spawned, so a `ready` after a reconnect
+// doesn't start a second one
+pub struct SchedulerStartedKey;
+
+impl TypeMapKey for SchedulerStartedKey {
+    type Value = Arc<std::sync::atomic::AtomicBool>;
+}
+
+// Where runtime state like RSVPs and history gets saved
+pub struct StoreKey;
+
+impl TypeMapKey for StoreKey {
+    type Value = Arc<dyn Store>;
+}
+
+// How often each command was used since startup, for !stats
+pub struct CommandMetricsKey;
+
+impl TypeMapKey for CommandMetricsKey {
+    type Value = Arc<CommandMetrics>;
+}
+
+// Who's in which voice channel in each guild, for !whoisplaying
+pub struct VoiceStatesKey;
+
+impl TypeMapKey for VoiceStatesKey {
+    type Value = Arc<RwLock<VoiceStates>>;
+}
@@ -0,0 +1,747 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serenity::model::id::GuildId;
+
+use crate::config;
+use crate::game_night::{GameNightSchedule, LibraryGame};
+use crate::history::GameNightRecord;
+use crate::rsvp::GuildRsvps;
+
+// Per-guild game libraries managed with !addgame and !removegame
+pub const GAMES: &str = "games";
+// RSVPs for each guild's next game night
+pub const RSVPS: &str = "rsvps";
+// Per-guild command prefixes picked with !setprefix
+pub const PREFIXES: &str = "prefixes";
+// Game nights that already happened, for !history
+pub const HISTORY: &str = "history";
+// Members' attendance streaks, for !streak
+pub const STREAKS: &str = "streaks";
+// Last session of each night the scheduler announced as live
+pub const ANNOUNCEMENTS: &str = "announcements";
+// Members' own timezones picked with !mytz
+pub const USER_TIMEZONES: &str = "timezones";
+// Members who asked for reminders by DM with !subscribe
+pub const SUBSCRIBERS: &str = "subscribers";
+// Each guild's own schedule, changed with the admin commands
+pub const GUILD_SCHEDULES: &str = "guilds";
+
+pub type GuildSchedules = HashMap<GuildId, GameNightSchedule>;
+pub type GameLibraries = HashMap<GuildId, Vec<LibraryGame>>;
+pub type Rsvps = HashMap<GuildId, GuildRsvps>;
+pub type History = HashMap<GuildId, Vec<GameNightRecord>>;
+
+// Where the bot keeps its state between restarts. Each piece of state is
+// saved whole, as JSON, under its own name, unless the store has somewhere
+// better for it: guild schedules, game libraries, RSVPs and history each get
+// methods of their own a store can keep in tables.
+pub trait Store: Send + Sync {
+    // What was last saved under `name`, none when nothing has been yet
+    fn load(&self, name: &str) -> io::Result<Option<String>>;
+    fn save(&self, name: &str, contents: &str) -> io::Result<()>;
+
+    fn load_guild_schedules(&self) -> io::Result<GuildSchedules>;
+    fn save_guild_schedules(&self, schedules: &GuildSchedules) -> io::Result<()>;
+
+    fn load_games(&self) -> io::Result<GameLibraries> {
+        load_state(self, GAMES)
+    }
+
+    fn save_games(&self, libraries: &GameLibraries) -> io::Result<()> {
+        save_state(self, GAMES, libraries)
+    }
+
+    fn load_rsvps(&self) -> io::Result<Rsvps> {
+        load_state(self, RSVPS)
+    }
+
+    fn save_rsvps(&self, rsvps: &Rsvps) -> io::Result<()> {
+        save_state(self, RSVPS, rsvps)
+    }
+
+    fn load_history(&self) -> io::Result<History> {
+        load_state(self, HISTORY)
+    }
+
+    fn save_history(&self, history: &History) -> io::Result<()> {
+        save_state(self, HISTORY, history)
+    }
+}
+
+// One JSON file per piece of state in a directory, e.g. rsvps.json, and the
+// guild schedules in guilds.toml next to them
+pub struct JsonFiles {
+    dir: PathBuf,
+}
+
+impl JsonFiles {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    fn guilds_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.toml", GUILD_SCHEDULES))
+    }
+}
+
+impl Store for JsonFiles {
+    fn load(&self, name: &str) -> io::Result<Option<String>> {
+        match std::fs::read_to_string(self.path(name)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(why) => Err(why),
+        }
+    }
+
+    fn save(&self, name: &str, contents: &str) -> io::Result<()> {
+        std::fs::write(self.path(name), contents)
+    }
+
+    fn load_guild_schedules(&self) -> io::Result<GuildSchedules> {
+        config::load_guild_schedules(&self.guilds_path()).map_err(invalid_data)
+    }
+
+    fn save_guild_schedules(&self, schedules: &GuildSchedules) -> io::Result<()> {
+        config::save_guild_schedules(&self.guilds_path(), schedules).map_err(invalid_data)
+    }
+}
+
+// Keeps everything in memory and forgets it on restart, for tests
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    documents: std::sync::Mutex<HashMap<String, String>>,
+    schedules: std::sync::Mutex<GuildSchedules>,
+}
+
+#[cfg(test)]
+impl Store for MemoryStore {
+    fn load(&self, name: &str) -> io::Result<Option<String>> {
+        Ok(self.documents.lock().expect("memory store isn't poisoned").get(name).cloned())
+    }
+
+    fn save(&self, name: &str, contents: &str) -> io::Result<()> {
+        self.documents.lock().expect("memory store isn't poisoned").insert(name.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn load_guild_schedules(&self) -> io::Result<GuildSchedules> {
+        Ok(self.schedules.lock().expect("memory store isn't poisoned").clone())
+    }
+
+    fn save_guild_schedules(&self, schedules: &GuildSchedules) -> io::Result<()> {
+        *self.schedules.lock().expect("memory store isn't poisoned") = schedules.clone();
+        Ok(())
+    }
+}
+
+// Everything in one SQLite database. Guild schedules, game libraries, RSVPs
+// and history get tables of their own, the rest is a row each in `state`.
+// Opening it brings its schema up to date first.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStore {
+    connection: std::sync::Mutex<rusqlite::Connection>,
+}
+
+// One schema change: SQL to run, then anything it has to move over in code
+#[cfg(feature = "sqlite")]
+struct Migration {
+    sql: &'static str,
+    data: Option<fn(&rusqlite::Transaction) -> io::Result<()>>,
+}
+
+// Schema changes, oldest first. The database's user_version counts how many
+// of them it has had, so each one runs exactly once.
+#[cfg(feature = "sqlite")]
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        sql: "CREATE TABLE state (name TEXT PRIMARY KEY, contents TEXT NOT NULL);",
+        data: None,
+    },
+    // Each night of a guild's schedule is the TOML gamenight.toml would have
+    // for it. Members' attendance is only known for some of the history,
+    // attendees_kept says which.
+    Migration {
+        sql: "CREATE TABLE guild_nights (
+                guild_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                config TEXT NOT NULL,
+                PRIMARY KEY (guild_id, position)
+            );
+            CREATE TABLE games (
+                guild_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                weight INTEGER NOT NULL,
+                PRIMARY KEY (guild_id, name)
+            );
+            CREATE TABLE rsvp_sessions (
+                guild_id INTEGER PRIMARY KEY,
+                session_start TEXT NOT NULL,
+                session_end TEXT NOT NULL
+            );
+            CREATE TABLE rsvps (
+                guild_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                PRIMARY KEY (guild_id, user_id)
+            );
+            CREATE TABLE history (
+                guild_id INTEGER NOT NULL,
+                start TEXT NOT NULL,
+                game TEXT NOT NULL,
+                attendees INTEGER NOT NULL,
+                attendees_kept INTEGER NOT NULL,
+                PRIMARY KEY (guild_id, start)
+            );
+            CREATE TABLE history_attendees (
+                guild_id INTEGER NOT NULL,
+                start TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                PRIMARY KEY (guild_id, start, user_id)
+            );",
+        data: Some(move_documents_to_tables),
+    },
+];
+
+#[cfg(feature = "sqlite")]
+impl SqliteStore {
+    // Open the database at `path`, creating it when it doesn't exist yet
+    pub fn open(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Self::new(rusqlite::Connection::open(path).map_err(io::Error::other)?)
+    }
+
+    pub fn new(mut connection: rusqlite::Connection) -> io::Result<Self> {
+        migrate(&mut connection)?;
+        Ok(Self { connection: std::sync::Mutex::new(connection) })
+    }
+
+    // Run `change` in a transaction, so a save that fails halfway leaves the
+    // last one in place
+    fn write(&self, change: impl FnOnce(&rusqlite::Transaction) -> rusqlite::Result<()>) -> io::Result<()> {
+        let mut connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        let transaction = connection.transaction().map_err(io::Error::other)?;
+        change(&transaction).map_err(io::Error::other)?;
+        transaction.commit().map_err(io::Error::other)
+    }
+}
+
+// Run the migrations the database hasn't had yet. One it doesn't know about
+// means it was written by a newer bot, and is left alone.
+#[cfg(feature = "sqlite")]
+fn migrate(connection: &mut rusqlite::Connection) -> io::Result<()> {
+    let version: usize = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(io::Error::other)?;
+    if version > MIGRATIONS.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the database is at schema version {}, newer than this bot's {}", version, MIGRATIONS.len()),
+        ));
+    }
+    for (done, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction().map_err(io::Error::other)?;
+        transaction.execute_batch(migration.sql).map_err(io::Error::other)?;
+        if let Some(data) = migration.data {
+            data(&transaction)?;
+        }
+        transaction.pragma_update(None, "user_version", done + 1).map_err(io::Error::other)?;
+        transaction.commit().map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+// Move game libraries, RSVPs and history saved as documents in `state` into
+// their own tables
+#[cfg(feature = "sqlite")]
+fn move_documents_to_tables(transaction: &rusqlite::Transaction) -> io::Result<()> {
+    if let Some(libraries) = saved_document(transaction, GAMES)? {
+        write_games(transaction, &libraries).map_err(io::Error::other)?;
+    }
+    if let Some(rsvps) = saved_document(transaction, RSVPS)? {
+        write_rsvps(transaction, &rsvps).map_err(io::Error::other)?;
+    }
+    if let Some(history) = saved_document(transaction, HISTORY)? {
+        write_history(transaction, &history).map_err(io::Error::other)?;
+    }
+    transaction
+        .execute("DELETE FROM state WHERE name IN (?1, ?2, ?3)", [GAMES, RSVPS, HISTORY])
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+// What's saved under `name` in `state`, if anything
+#[cfg(feature = "sqlite")]
+fn saved_document<T: DeserializeOwned>(transaction: &rusqlite::Transaction, name: &str) -> io::Result<Option<T>> {
+    use rusqlite::OptionalExtension;
+
+    let contents: Option<String> = transaction
+        .query_row("SELECT contents FROM state WHERE name = ?1", [name], |row| row.get(0))
+        .optional()
+        .map_err(io::Error::other)?;
+    contents.map(|contents| serde_json::from_str(&contents).map_err(invalid_data)).transpose()
+}
+
+// Discord ids go in INTEGER columns, which SQLite keeps signed. Snowflakes
+// stay well below 2^63.
+#[cfg(feature = "sqlite")]
+fn to_sql_id(id: u64) -> i64 {
+    id as i64
+}
+
+#[cfg(feature = "sqlite")]
+fn guild_id_column(row: &rusqlite::Row, index: usize) -> rusqlite::Result<GuildId> {
+    Ok(GuildId::new(row.get::<_, i64>(index)? as u64))
+}
+
+#[cfg(feature = "sqlite")]
+fn user_id_column(row: &rusqlite::Row, index: usize) -> rusqlite::Result<serenity::model::id::UserId> {
+    Ok(serenity::model::id::UserId::new(row.get::<_, i64>(index)? as u64))
+}
+
+// How an RSVP is saved, the same word members answer with
+#[cfg(feature = "sqlite")]
+fn rsvp_status_name(status: crate::rsvp::RsvpStatus) -> &'static str {
+    match status {
+        crate::rsvp::RsvpStatus::Yes => "yes",
+        crate::rsvp::RsvpStatus::No => "no",
+        crate::rsvp::RsvpStatus::Maybe => "maybe",
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn write_games(transaction: &rusqlite::Transaction, libraries: &GameLibraries) -> rusqlite::Result<()> {
+    transaction.execute("DELETE FROM games", [])?;
+    let mut insert = transaction.prepare("INSERT INTO games (guild_id, name, weight) VALUES (?1, ?2, ?3)")?;
+    for (guild_id, library) in libraries {
+        for game in library {
+            insert.execute(rusqlite::params![to_sql_id(guild_id.get()), game.name, game.weight])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+fn write_rsvps(transaction: &rusqlite::Transaction, rsvps: &Rsvps) -> rusqlite::Result<()> {
+    transaction.execute_batch("DELETE FROM rsvp_sessions; DELETE FROM rsvps;")?;
+    let mut session = transaction
+        .prepare("INSERT INTO rsvp_sessions (guild_id, session_start, session_end) VALUES (?1, ?2, ?3)")?;
+    let mut response = transaction.prepare("INSERT INTO rsvps (guild_id, user_id, status) VALUES (?1, ?2, ?3)")?;
+    for (guild_id, guild_rsvps) in rsvps {
+        let guild_id = to_sql_id(guild_id.get());
+        session.execute(rusqlite::params![guild_id, guild_rsvps.session_start, guild_rsvps.session_end])?;
+        for (user_id, status) in &guild_rsvps.responses {
+            response.execute(rusqlite::params![guild_id, to_sql_id(user_id.get()), rsvp_status_name(*status)])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+fn write_history(transaction: &rusqlite::Transaction, history: &History) -> rusqlite::Result<()> {
+    transaction.execute_batch("DELETE FROM history; DELETE FROM history_attendees;")?;
+    let mut record = transaction.prepare(
+        "INSERT INTO history (guild_id, start, game, attendees, attendees_kept) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    let mut attendee =
+        transaction.prepare("INSERT INTO history_attendees (guild_id, start, user_id) VALUES (?1, ?2, ?3)")?;
+    for (guild_id, records) in history {
+        let guild_id = to_sql_id(guild_id.get());
+        for night in records {
+            let kept = night.attendee_ids.is_some();
+            record.execute(rusqlite::params![guild_id, night.start, night.game, night.attendees as i64, kept])?;
+            for user_id in night.attendee_ids.iter().flatten() {
+                attendee.execute(rusqlite::params![guild_id, night.start, to_sql_id(user_id.get())])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+impl Store for SqliteStore {
+    fn load(&self, name: &str) -> io::Result<Option<String>> {
+        use rusqlite::OptionalExtension;
+
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        connection
+            .query_row("SELECT contents FROM state WHERE name = ?1", [name], |row| row.get(0))
+            .optional()
+            .map_err(io::Error::other)
+    }
+
+    fn save(&self, name: &str, contents: &str) -> io::Result<()> {
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        connection
+            .execute(
+                "INSERT INTO state (name, contents) VALUES (?1, ?2) \
+                ON CONFLICT (name) DO UPDATE SET contents = excluded.contents",
+                [name, contents],
+            )
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn load_guild_schedules(&self) -> io::Result<GuildSchedules> {
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        let mut select = connection
+            .prepare("SELECT guild_id, config FROM guild_nights ORDER BY guild_id, position")
+            .map_err(io::Error::other)?;
+        let rows = select
+            .query_map([], |row| Ok((guild_id_column(row, 0)?, row.get::<_, String>(1)?)))
+            .map_err(io::Error::other)?;
+        let mut schedules = GuildSchedules::new();
+        for row in rows {
+            let (guild_id, night) = row.map_err(io::Error::other)?;
+            let night = crate::game_night::GameNightConfig::from_toml_str(&night).map_err(invalid_data)?;
+            schedules.entry(guild_id).or_insert_with(|| GameNightSchedule { nights: Vec::new() }).nights.push(night);
+        }
+        Ok(schedules)
+    }
+
+    fn save_guild_schedules(&self, schedules: &GuildSchedules) -> io::Result<()> {
+        self.write(|transaction| {
+            transaction.execute("DELETE FROM guild_nights", [])?;
+            let mut insert =
+                transaction.prepare("INSERT INTO guild_nights (guild_id, position, config) VALUES (?1, ?2, ?3)")?;
+            for (guild_id, schedule) in schedules {
+                for (position, night) in schedule.nights.iter().enumerate() {
+                    insert.execute(rusqlite::params![
+                        to_sql_id(guild_id.get()),
+                        position as i64,
+                        config::night_to_toml(night)
+                    ])?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn load_games(&self) -> io::Result<GameLibraries> {
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        let mut select = connection
+            .prepare("SELECT guild_id, name, weight FROM games ORDER BY rowid")
+            .map_err(io::Error::other)?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((guild_id_column(row, 0)?, LibraryGame { name: row.get(1)?, weight: row.get(2)? }))
+            })
+            .map_err(io::Error::other)?;
+        let mut libraries = GameLibraries::new();
+        for row in rows {
+            let (guild_id, game) = row.map_err(io::Error::other)?;
+            libraries.entry(guild_id).or_default().push(game);
+        }
+        Ok(libraries)
+    }
+
+    fn save_games(&self, libraries: &GameLibraries) -> io::Result<()> {
+        self.write(|transaction| write_games(transaction, libraries))
+    }
+
+    fn load_rsvps(&self) -> io::Result<Rsvps> {
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        let mut sessions = connection
+            .prepare("SELECT guild_id, session_start, session_end FROM rsvp_sessions")
+            .map_err(io::Error::other)?;
+        let mut rsvps = sessions
+            .query_map([], |row| {
+                let guild_rsvps =
+                    GuildRsvps { session_start: row.get(1)?, session_end: row.get(2)?, responses: HashMap::new() };
+                Ok((guild_id_column(row, 0)?, guild_rsvps))
+            })
+            .map_err(io::Error::other)?
+            .collect::<rusqlite::Result<Rsvps>>()
+            .map_err(io::Error::other)?;
+
+        let mut responses =
+            connection.prepare("SELECT guild_id, user_id, status FROM rsvps").map_err(io::Error::other)?;
+        let rows = responses
+            .query_map([], |row| Ok((guild_id_column(row, 0)?, user_id_column(row, 1)?, row.get::<_, String>(2)?)))
+            .map_err(io::Error::other)?;
+        for row in rows {
+            let (guild_id, user_id, status) = row.map_err(io::Error::other)?;
+            let status = status
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("unknown RSVP '{}'", status)))?;
+            if let Some(guild_rsvps) = rsvps.get_mut(&guild_id) {
+                guild_rsvps.responses.insert(user_id, status);
+            }
+        }
+        Ok(rsvps)
+    }
+
+    fn save_rsvps(&self, rsvps: &Rsvps) -> io::Result<()> {
+        self.write(|transaction| write_rsvps(transaction, rsvps))
+    }
+
+    fn load_history(&self) -> io::Result<History> {
+        let connection = self.connection.lock().expect("SQLite store isn't poisoned");
+        let mut attendees = connection
+            .prepare("SELECT guild_id, start, user_id FROM history_attendees ORDER BY rowid")
+            .map_err(io::Error::other)?;
+        let mut attendee_ids: HashMap<(GuildId, chrono::DateTime<chrono::Utc>), Vec<_>> = HashMap::new();
+        let rows = attendees
+            .query_map([], |row| Ok(((guild_id_column(row, 0)?, row.get(1)?), user_id_column(row, 2)?)))
+            .map_err(io::Error::other)?;
+        for row in rows {
+            let (night, user_id) = row.map_err(io::Error::other)?;
+            attendee_ids.entry(night).or_default().push(user_id);
+        }
+
+        let mut records = connection
+            .prepare("SELECT guild_id, start, game, attendees, attendees_kept FROM history ORDER BY guild_id, start")
+            .map_err(io::Error::other)?;
+        let rows = records
+            .query_map([], |row| {
+                let guild_id = guild_id_column(row, 0)?;
+                let start = row.get(1)?;
+                let kept: bool = row.get(4)?;
+                let record = GameNightRecord {
+                    start,
+                    game: row.get(2)?,
+                    attendees: row.get::<_, i64>(3)? as usize,
+                    attendee_ids: kept.then(|| attendee_ids.remove(&(guild_id, start)).unwrap_or_default()),
+                };
+                Ok((guild_id, record))
+            })
+            .map_err(io::Error::other)?;
+        let mut history = History::new();
+        for row in rows {
+            let (guild_id, record) = row.map_err(io::Error::other)?;
+            history.entry(guild_id).or_default().push(record);
+        }
+        Ok(history)
+    }
+
+    fn save_history(&self, history: &History) -> io::Result<()> {
+        self.write(|transaction| write_history(transaction, history))
+    }
+}
+
+// Load the state saved under `name`, starting from empty when nothing has
+// been saved yet
+pub fn load_state<T: DeserializeOwned + Default, S: Store + ?Sized>(store: &S, name: &str) -> io::Result<T> {
+    match store.load(name)? {
+        Some(contents) => serde_json::from_str(&contents).map_err(invalid_data),
+        None => Ok(T::default()),
+    }
+}
+
+pub fn save_state<T: Serialize, S: Store + ?Sized>(store: &S, name: &str, value: &T) -> io::Result<()> {
+    store.save(name, &to_json(value)?)
+}
+
+// Save a copy of what `state` holds with `save`, writing it off the async
+// runtime. `saving` is taken first and `state` only read-locked while it's
+// copied, so saves land in the order the changes were made without a write
+// lock waiting on the disk.
+pub async fn save_shared<T: Clone + Send + 'static>(
+    store: Arc<dyn Store>,
+    saving: &tokio::sync::Mutex<()>,
+    state: &tokio::sync::RwLock<T>,
+    save: impl FnOnce(&dyn Store, &T) -> io::Result<()> + Send + 'static,
+) -> io::Result<()> {
+    let _saving = saving.lock().await;
+    let value = state.read().await.clone();
+    tokio::task::spawn_blocking(move || save(&*store, &value))
+        .await
+        .expect("saving state doesn't panic")
+}
+
+// A file or row that doesn't hold what it should
+fn invalid_data(why: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, why)
+}
+
+// What save_state writes for `value`, for taking it while the state is
+// locked and saving it once the lock is let go
+pub fn to_json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serenity::model::id::GuildId;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut games: HashMap<GuildId, Vec<String>> = HashMap::new();
+        games.insert(GuildId::new(1234), vec!["Minecraft".to_string()]);
+
+        let store = JsonFiles::new(std::env::temp_dir());
+        save_state(&store, "tannius-bot-storage", &games).unwrap();
+        let loaded: HashMap<GuildId, Vec<String>> = load_state(&store, "tannius-bot-storage").unwrap();
+        std::fs::remove_file(std::env::temp_dir().join("tannius-bot-storage.json")).unwrap();
+
+        assert_eq!(loaded, games);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let loaded: HashMap<GuildId, Vec<String>> = load_state(&JsonFiles::new("."), "does-not-exist").unwrap();
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryStore::default();
+        let empty: HashMap<GuildId, String> = load_state(&store, PREFIXES).unwrap();
+        assert!(empty.is_empty());
+
+        let prefixes = HashMap::from([(GuildId::new(1), "?".to_string())]);
+        save_state(&store, PREFIXES, &prefixes).unwrap();
+        assert_eq!(load_state::<HashMap<GuildId, String>, _>(&store, PREFIXES).unwrap(), prefixes);
+
+        store.save(RSVPS, "not json").unwrap();
+        assert!(load_state::<HashMap<GuildId, String>, _>(&store, RSVPS).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        let store = SqliteStore::new(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(store.load(PREFIXES).unwrap(), None);
+
+        let prefixes = HashMap::from([(GuildId::new(1), "?".to_string())]);
+        save_state(&store, PREFIXES, &prefixes).unwrap();
+        assert_eq!(load_state::<HashMap<GuildId, String>, _>(&store, PREFIXES).unwrap(), prefixes);
+
+        // Saving again replaces what was there
+        store.save(PREFIXES, "{}").unwrap();
+        assert!(load_state::<HashMap<GuildId, String>, _>(&store, PREFIXES).unwrap().is_empty());
+        assert_eq!(store.load(RSVPS).unwrap(), None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_migrations() {
+        let version = |connection: &rusqlite::Connection| -> usize {
+            connection.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+        };
+        let mut connection = rusqlite::Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        assert_eq!(version(&connection), MIGRATIONS.len());
+        connection.execute("INSERT INTO state (name, contents) VALUES ('prefixes', '{}')", []).unwrap();
+
+        // Already up to date, so nothing runs again and nothing is lost
+        migrate(&mut connection).unwrap();
+        assert_eq!(version(&connection), MIGRATIONS.len());
+        let store = SqliteStore::new(connection).unwrap();
+        assert_eq!(store.load(PREFIXES).unwrap().as_deref(), Some("{}"));
+
+        let newer = rusqlite::Connection::open_in_memory().unwrap();
+        newer.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+        assert_eq!(SqliteStore::new(newer).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn sqlite_history() -> History {
+        use chrono::TimeZone;
+        use serenity::model::id::UserId;
+
+        let start = chrono::Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let earlier = chrono::Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        HashMap::from([(
+            GuildId::new(1),
+            vec![
+                GameNightRecord { start: earlier, game: "Chess".to_string(), attendees: 3, attendee_ids: None },
+                GameNightRecord {
+                    start,
+                    game: "Catan".to_string(),
+                    attendees: 2,
+                    attendee_ids: Some(vec![UserId::new(7), UserId::new(5)]),
+                },
+            ],
+        )])
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_tables() {
+        use chrono::TimeZone;
+        use serenity::model::id::UserId;
+
+        use crate::game_night::GameNightConfig;
+        use crate::rsvp::RsvpStatus;
+
+        let store = SqliteStore::new(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+        assert!(store.load_guild_schedules().unwrap().is_empty());
+        assert!(store.load_games().unwrap().is_empty());
+        assert!(store.load_rsvps().unwrap().is_empty());
+        assert!(store.load_history().unwrap().is_empty());
+
+        let saturday = GameNightConfig { day_of_week: chrono::Weekday::Sat, ..GameNightConfig::default() };
+        let schedules = HashMap::from([(
+            GuildId::new(1),
+            GameNightSchedule { nights: vec![GameNightConfig::default(), saturday.clone()] },
+        )]);
+        store.save_guild_schedules(&schedules).unwrap();
+        let nights = store.load_guild_schedules().unwrap().remove(&GuildId::new(1)).unwrap().nights;
+        let nights: Vec<String> = nights.iter().map(config::night_to_toml).collect();
+        assert_eq!(nights, [config::night_to_toml(&GameNightConfig::default()), config::night_to_toml(&saturday)]);
+
+        // Libraries keep the order games were added in
+        let games = HashMap::from([(
+            GuildId::new(1),
+            vec![LibraryGame { name: "Zendo".to_string(), weight: 1 }, LibraryGame { name: "Azul".to_string(), weight: 3 }],
+        )]);
+        store.save_games(&games).unwrap();
+        assert_eq!(store.load_games().unwrap(), games);
+
+        let session_start = chrono::Utc.with_ymd_and_hms(2024, 6, 8, 0, 0, 0).unwrap();
+        let rsvps = HashMap::from([(
+            GuildId::new(1),
+            GuildRsvps {
+                session_start,
+                session_end: session_start + chrono::Duration::hours(3),
+                responses: HashMap::from([(UserId::new(5), RsvpStatus::Yes), (UserId::new(7), RsvpStatus::Maybe)]),
+            },
+        )]);
+        store.save_rsvps(&rsvps).unwrap();
+        let loaded = store.load_rsvps().unwrap();
+        assert_eq!(loaded[&GuildId::new(1)].session_end, rsvps[&GuildId::new(1)].session_end);
+        assert_eq!(loaded[&GuildId::new(1)].responses, rsvps[&GuildId::new(1)].responses);
+
+        store.save_history(&sqlite_history()).unwrap();
+        assert_eq!(store.load_history().unwrap(), sqlite_history());
+
+        // Saving again replaces what was there
+        store.save_games(&HashMap::new()).unwrap();
+        assert!(store.load_games().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_migration_moves_documents() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection.execute_batch(MIGRATIONS[0].sql).unwrap();
+        connection.pragma_update(None, "user_version", 1).unwrap();
+        let history = sqlite_history();
+        for (name, contents) in [
+            (GAMES, r#"{"1": ["Catan", {"name": "Azul", "weight": 2}]}"#.to_string()),
+            (HISTORY, to_json(&history).unwrap()),
+            (PREFIXES, r#"{"1": "?"}"#.to_string()),
+        ] {
+            connection.execute("INSERT INTO state (name, contents) VALUES (?1, ?2)", [name, &contents]).unwrap();
+        }
+
+        let store = SqliteStore::new(connection).unwrap();
+        let games = store.load_games().unwrap();
+        assert_eq!(games[&GuildId::new(1)].iter().map(|game| game.weight).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(store.load_history().unwrap(), history);
+        assert!(store.load_rsvps().unwrap().is_empty());
+        assert_eq!(store.load(GAMES).unwrap(), None);
+        assert_eq!(store.load(PREFIXES).unwrap().as_deref(), Some(r#"{"1": "?"}"#));
+    }
+}
@@ -0,0 +1,82 @@
+// Fill each known `{name}` in `template` with its value. Anything else in
+// braces is left as typed.
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after
+            .find('}')
+            .and_then(|close| values.iter().find(|(name, _)| *name == &after[..close]).map(|(_, value)| (close, value)));
+        match value {
+            Some((close, value)) => {
+                rendered.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// Placeholders in `template` that aren't one of `known`, in the order they
+// first show up
+pub fn unknown_placeholders(template: &str, known: &[&str]) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..close];
+        // A brace that opens again first wasn't a placeholder after all
+        if !name.contains('{') && !known.contains(&name) && !unknown.iter().any(|seen| seen == name) {
+            unknown.push(name.to_string());
+        }
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, String)> {
+        vec![
+            ("countdown", "15 minutes".to_string()),
+            ("game", "Among Us".to_string()),
+            ("date", "Friday, June 07".to_string()),
+            ("role", "<@&42>".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_render_all_placeholders() {
+        let rendered = render("{role} {game} on {date}, starting in {countdown}! {countdown}!", &values());
+        assert_eq!(rendered, "<@&42> Among Us on Friday, June 07, starting in 15 minutes! 15 minutes!");
+    }
+
+    #[test]
+    fn test_render_without_placeholders() {
+        assert_eq!(render("Game night soon!", &values()), "Game night soon!");
+        assert_eq!(render("", &values()), "");
+        // Unknown names and stray braces stay as typed
+        assert_eq!(render("{snacks} {game} {", &values()), "{snacks} Among Us {");
+        assert_eq!(render("{{game}}", &values()), "{Among Us}");
+    }
+
+    #[test]
+    fn test_unknown_placeholders() {
+        let known = ["countdown", "game"];
+        assert!(unknown_placeholders("{game} in {countdown}", &known).is_empty());
+        assert_eq!(unknown_placeholders("{gmae} {snacks} {gmae} {game}", &known), vec!["gmae", "snacks"]);
+        assert_eq!(unknown_placeholders("{{game}", &known), Vec::<String>::new());
+        assert!(unknown_placeholders("no braces {", &known).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use serenity::model::id::{ChannelId, GuildId, UserId};
+#[cfg(feature = "prefix-commands")]
+use serenity::model::mention::Mentionable;
+use serenity::model::voice::VoiceState;
+
+#[cfg(feature = "prefix-commands")]
+use crate::game_night::GameNightSchedule;
+
+// Voice channel each member is connected to, per guild. Filled in from the
+// guild's voice states when it becomes available and kept up to date from
+// voice state updates, since the bot runs without serenity's cache.
+pub type VoiceStates = HashMap<GuildId, HashMap<UserId, ChannelId>>;
+
+// Replace everything known about a guild's voice channels, e.g. when the
+// guild comes online
+pub fn load_guild<'a>(states: &mut VoiceStates, guild_id: GuildId, voice_states: impl Iterator<Item = &'a VoiceState>) {
+    let connected = voice_states
+        .filter_map(|state| state.channel_id.map(|channel_id| (state.user_id, channel_id)))
+        .collect();
+    states.insert(guild_id, connected);
+}
+
+// A member joined, moved or left (no channel)
+pub fn update(states: &mut VoiceStates, guild_id: GuildId, user_id: UserId, channel_id: Option<ChannelId>) {
+    let guild = states.entry(guild_id).or_default();
+    match channel_id {
+        Some(channel_id) => {
+            guild.insert(user_id, channel_id);
+        }
+        None => {
+            guild.remove(&user_id);
+        }
+    }
+}
+
+// Members connected to `channel_id`, in a stable order
+#[cfg(feature = "prefix-commands")]
+pub fn members_in(states: &VoiceStates, guild_id: GuildId, channel_id: ChannelId) -> Vec<UserId> {
+    let mut members: Vec<UserId> = states
+        .get(&guild_id)
+        .into_iter()
+        .flatten()
+        .filter(|(_, channel)| **channel == channel_id)
+        .map(|(user_id, _)| *user_id)
+        .collect();
+    members.sort();
+    members
+}
+
+// The voice channel !whoisplaying looks at: the first night that has one
+#[cfg(feature = "prefix-commands")]
+pub fn voice_channel(schedule: &GameNightSchedule) -> Option<ChannelId> {
+    schedule.nights.iter().find_map(|night| night.voice_channel_id)
+}
+
+// Format for !whoisplaying
+#[cfg(feature = "prefix-commands")]
+pub fn format_who_is_playing(channel_id: ChannelId, members: &[UserId]) -> String {
+    if members.is_empty() {
+        return format!("🔇 Nobody is in {} right now.", channel_id.mention());
+    }
+    let names: Vec<String> = members.iter().map(|user_id| format!("• {}", user_id.mention())).collect();
+    format!(
+        "🎧 **{} in {}:**\n{}",
+        if members.len() == 1 { "1 player".to_string() } else { format!("{} players", members.len()) },
+        channel_id.mention(),
+        names.join("\n")
+    )
+}
+
+#[cfg(all(test, feature = "prefix-commands"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_members_follow_updates() {
+        let mut states = VoiceStates::new();
+        let guild_id = GuildId::new(1);
+        let voice = ChannelId::new(10);
+        let afk = ChannelId::new(11);
+
+        update(&mut states, guild_id, UserId::new(3), Some(voice));
+        update(&mut states, guild_id, UserId::new(2), Some(voice));
+        update(&mut states, guild_id, UserId::new(4), Some(afk));
+        assert_eq!(members_in(&states, guild_id, voice), vec![UserId::new(2), UserId::new(3)]);
+
+        // Moving and leaving
+        update(&mut states, guild_id, UserId::new(4), Some(voice));
+        update(&mut states, guild_id, UserId::new(2), None);
+        assert_eq!(members_in(&states, guild_id, voice), vec![UserId::new(3), UserId::new(4)]);
+        assert!(members_in(&states, GuildId::new(2), voice).is_empty());
+    }
+
+    #[test]
+    fn test_format_who_is_playing() {
+        let channel_id = ChannelId::new(10);
+        assert_eq!(format_who_is_playing(channel_id, &[]), "🔇 Nobody is in <#10> right now.");
+        assert_eq!(
+            format_who_is_playing(channel_id, &[UserId::new(1), UserId::new(2)]),
+            "🎧 **2 players in <#10>:**\n• <@1>\n• <@2>"
+        );
+        assert!(format_who_is_playing(channel_id, &[UserId::new(1)]).starts_with("🎧 **1 player in"));
+    }
+}